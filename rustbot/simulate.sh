@@ -0,0 +1,13 @@
+#!/usr/bin/env bash
+
+set -e
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+cd "$SCRIPT_DIR"
+
+# The simulator runs on the host, .cargo/config only picks Windows for the bot
+HOST_TARGET="$(rustc -vV | sed -n 's/^host: //p')"
+
+# Usage: ./simulate.sh [--window N] [file or name]...
+# Checks every build order in build_orders/ (or $RUSTBOT_BUILD_ORDER_DIR) when none are given
+cargo run --quiet --target "$HOST_TARGET" --bin simulate -- "$@"
@@ -0,0 +1,7 @@
+use rustbot::utils::build_orders::build_order_simulator;
+
+// `simulate [--window N] [file or name]...` checks build orders offline, no StarCraft needed
+fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  std::process::exit(build_order_simulator::run_cli(&args));
+}
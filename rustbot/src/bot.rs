@@ -1,11 +1,20 @@
+use crate::utils::base_registry;
 use crate::utils::build_order_management;
-use crate::utils::build_orders::build_order_item::BuildOrderItem;
-use crate::utils::build_orders::pool_speed_expand;
-use crate::utils::building_stuff::{creature_stuff, expansion_location_stuff, researching_stuff};
+use crate::utils::build_order_telemetry;
+use crate::utils::build_orders::build_order_selector;
+use crate::utils::building_stuff::{
+  builder_tracking, creature_stuff, defense_placement, expansion_location_stuff,
+};
 use crate::utils::game_state::{DebugFlag, GameState, SharedGameState};
+use crate::utils::gas_demand;
 use crate::utils::http_status_callbacks::SharedHttpStatusCallbacks;
-use crate::utils::map_utils::region_stuff;
-use crate::utils::military::military_management;
+use crate::utils::income_tracker;
+use crate::utils::macro_planner::MacroGoals;
+use crate::utils::map_utils::{creep_map, region_stuff, tile_reservations};
+use crate::utils::military::{military_management, overlord_management, worker_defense};
+use crate::utils::rally_points;
+use crate::utils::scouting;
+use crate::utils::upgrade_queue;
 use crate::utils::worker_management;
 use rsbwapi::*;
 
@@ -27,13 +36,22 @@ impl AiModule for RustBot {
       return;
     };
 
-    game_state.build_order = pool_speed_expand::build_order();
+    let selected = build_order_selector::select_for_game(game);
+    game_state.build_order_name = selected.name;
+    game_state.build_order = selected.items;
+    game_state.build_order_continuations = selected.continuations;
+    game_state.macro_plan.goals = MacroGoals::for_build_order(&game_state.build_order);
 
-    println!("Making initial build order assignment");
-    build_order_management::make_assignment_for_current_build_order_item(game, &mut game_state);
+    build_order_management::sync_build_order_status(&mut game_state);
 
     game_state.base_locations =
       expansion_location_stuff::get_base_locations_ordered(game, &mut game_state.debug_lines);
+    base_registry::init(game, &mut game_state);
+    scouting::init(game, &mut game_state);
+    creep_map::init(game, &mut game_state);
+    tile_reservations::init(game, &mut game_state);
+    defense_placement::init(game, &mut game_state);
+    overlord_management::init(game, &mut game_state);
 
     for location in &game_state.base_locations {
       match game.can_build_here(None, *location, UnitType::Zerg_Hatchery, false) {
@@ -59,28 +77,41 @@ impl AiModule for RustBot {
       return;
     };
 
-    researching_stuff::check_and_advance_upgrade_if_started(game, &mut locked_state);
+    build_order_selector::apply_race_continuation(game, &mut locked_state);
 
     update_game_speed(game, &locked_state);
 
+    base_registry::update(game, &mut locked_state);
+    scouting::update(game, &mut locked_state);
+    creep_map::update(game, &mut locked_state);
+    tile_reservations::update(game, &mut locked_state);
+    defense_placement::update(game, &mut locked_state);
     build_order_management::build_order_enforce_assignments(game, &mut locked_state);
+    upgrade_queue::update(game, &mut locked_state);
+    build_order_telemetry::record_timings(game, &mut locked_state);
 
+    gas_demand::update(game, &mut locked_state);
     worker_management::update_assignments(game, &mut locked_state);
+    worker_defense::update(game, &mut locked_state);
+    builder_tracking::update(game, &mut locked_state);
     worker_management::enforce_assignments(game, &mut locked_state);
+    income_tracker::update(game, &mut locked_state);
 
     military_management::military_onframe(game, &mut locked_state);
+    overlord_management::update(game, &mut locked_state);
+    rally_points::update(game, &mut locked_state);
 
     draw_debug_lines(game, &locked_state);
 
     if let Ok(mut callbacks) = self.http_callbacks.lock() {
       if callbacks.has_pending() {
-        callbacks.process_all(game, &*locked_state);
+        callbacks.process_all(game, &mut locked_state);
       }
     }
   }
 
   fn on_unit_create(&mut self, game: &Game, unit: Unit) {
-    if game.get_frame_count() < 1 {
+    if game.get_frame_count() < 1 || !is_own_unit(game, &unit) {
       return;
     }
     println!("unit created: {:?}", unit.get_type());
@@ -93,6 +124,9 @@ impl AiModule for RustBot {
   }
 
   fn on_unit_morph(&mut self, game: &Game, unit: Unit) {
+    if !is_own_unit(game, &unit) {
+      return;
+    }
     println!(
       "unit {} started morphing: {:?} -> {:?}",
       unit.get_id(),
@@ -103,85 +137,66 @@ impl AiModule for RustBot {
       return;
     };
     if unit.get_type() == UnitType::Zerg_Egg {
+      let larva_build_idx = locked_state
+        .larva_responsibilities
+        .get(&unit.get_id())
+        .copied();
+
       // unit started morphing, remove larva responsibility
       creature_stuff::remove_larva_responsibility(&mut locked_state, &unit);
 
-      // Check if this morph matches the current build order item
-      let should_advance =
-        if let Some(current_item) = locked_state.build_order.get(locked_state.build_order_index) {
-          match current_item {
-            BuildOrderItem::Unit {
-              unit_type: expected_unit_type,
-              ..
-            } => unit.get_build_type() == *expected_unit_type,
-            BuildOrderItem::Upgrade(_) => {
-              // Don't advance on unit morphs if waiting for an upgrade
-              false
-            }
-            BuildOrderItem::Squad { .. } => {
-              // Don't advance on unit morphs if waiting for a squad
-              false
-            }
-          }
-        } else {
-          false
-        };
-
-      if should_advance {
-        build_order_management::advance_build_order(
-          game,
-          &mut locked_state,
-          &format!("Zerg_Egg started morphing into {:?}", unit.get_build_type()),
-        );
-      } else {
-        println!(
-          "Zerg_Egg morphing into {:?}, but not advancing build order (current item: {:?})",
-          unit.get_build_type(),
-          locked_state.build_order.get(locked_state.build_order_index)
-        );
-      }
+      build_order_management::build_order_on_unit_morph(
+        &mut locked_state,
+        unit.get_id(),
+        unit.get_build_type(),
+        larva_build_idx,
+        &format!("Zerg_Egg started morphing into {:?}", unit.get_build_type()),
+      );
       return;
     }
 
     if unit.get_type().is_building() {
+      // Morphing buildings keep their id, drones mostly do too
+      let builder_id = build_order_management::builder_of(
+        &locked_state,
+        unit.get_id(),
+        unit.get_type(),
+        unit.get_tile_position(),
+      );
+      let assigned_build_idx = builder_id
+        .and_then(|drone_id| locked_state.worker_assignments.get(&drone_id))
+        .and_then(|assignment| assignment.build_order_index)
+        .or_else(|| {
+          locked_state
+            .building_assignments
+            .get(&unit.get_id())
+            .map(|assignment| assignment.build_order_index)
+        });
+
       build_order_management::remove_drone_assignment_after_started_buidling(
-        &unit,
+        builder_id.unwrap_or(unit.get_id()),
         &mut locked_state,
       );
 
-      // Check if this building matches the current build order item
-      let should_advance =
-        if let Some(current_item) = locked_state.build_order.get(locked_state.build_order_index) {
-          match current_item {
-            BuildOrderItem::Unit {
-              unit_type: expected_unit_type,
-              ..
-            } => unit.get_type() == *expected_unit_type,
-            BuildOrderItem::Upgrade(_) => {
-              // Don't advance on building construction if waiting for an upgrade
-              false
-            }
-            BuildOrderItem::Squad { .. } => {
-              // Don't advance on building construction if waiting for a squad
-              false
-            }
-          }
-        } else {
-          false
-        };
-
-      if should_advance {
-        build_order_management::advance_build_order(
-          game,
-          &mut locked_state,
-          &format!("Building {:?} started construction", unit.get_type()),
-        );
-      } else {
-        println!(
-          "{:?} started construction, but not advancing build order (current item: {:?})",
-          unit.get_type(),
-          locked_state.build_order.get(locked_state.build_order_index)
-        );
+      build_order_management::build_order_on_unit_morph(
+        &mut locked_state,
+        unit.get_id(),
+        unit.get_type(),
+        assigned_build_idx,
+        &format!("Building {:?} started construction", unit.get_type()),
+      );
+    }
+  }
+
+  fn on_unit_discover(&mut self, game: &Game, unit: Unit) {
+    let Some(player) = game.self_() else {
+      return;
+    };
+
+    // Remembered for build order triggers waiting on enemy tech
+    if player.is_enemy(&unit.get_player()) {
+      if let Ok(mut locked_state) = self.game_state.lock() {
+        locked_state.seen_enemy_unit_types.insert(unit.get_type());
       }
     }
   }
@@ -202,14 +217,33 @@ impl AiModule for RustBot {
     {
       military_management::assign_unit_to_squad(&game, &unit, &mut self.game_state.lock().unwrap());
     }
+
+    if unit.get_player().get_id() == player.get_id() && unit.get_type().is_worker() {
+      worker_management::assign_new_worker(game, &mut self.game_state.lock().unwrap(), &unit);
+    }
+
+    if unit.get_player().get_id() == player.get_id() && unit.get_type().is_resource_depot() {
+      worker_management::schedule_worker_transfer(
+        &mut self.game_state.lock().unwrap(),
+        &format!("{:?} {} finished", unit.get_type(), unit.get_id()),
+      );
+    }
   }
 
-  fn on_end(&mut self, _game: &Game, is_winner: bool) {
+  fn on_end(&mut self, game: &Game, is_winner: bool) {
     if is_winner {
       println!("Victory!");
     } else {
       println!("Defeat!");
     }
+
+    if let Ok(locked_state) = self.game_state.lock() {
+      let report = build_order_telemetry::build_report(game, &locked_state, Some(is_winner));
+      match build_order_telemetry::export_report(&report) {
+        Ok(path) => println!("Build order timings written to {}", path.display()),
+        Err(e) => println!("Failed to write build order timings: {}", e),
+      }
+    }
   }
 }
 pub struct RustBot {
@@ -226,6 +260,13 @@ impl RustBot {
   }
 }
 
+/// Enemy and neutral units also trigger the unit callbacks, the build order only cares about ours
+fn is_own_unit(game: &Game, unit: &Unit) -> bool {
+  game
+    .self_()
+    .is_some_and(|player| unit.get_player().get_id() == player.get_id())
+}
+
 fn update_game_speed(game: &Game, game_state: &GameState) {
   let speed = game_state.game_speed;
 
@@ -255,10 +296,10 @@ fn draw_debug_lines(game: &Game, game_state: &GameState) {
         worker_management::draw_building_ids(game);
 
         // Draw base locations with numbers
-        for (index, tile_pos) in game_state.base_locations.iter().enumerate() {
-          let pos = Position::new(tile_pos.x * 32, tile_pos.y * 32);
+        for base in &game_state.bases {
+          let pos = Position::new(base.tile.x * 32, base.tile.y * 32);
           game.draw_circle_map(pos, 3, Color::Cyan, false);
-          game.draw_text_map(pos, &format!("Base {}", index));
+          game.draw_text_map(pos, &format!("Base {} {:?}", base.index, base.state));
         }
 
         // Draw all debug lines
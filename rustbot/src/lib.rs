@@ -0,0 +1,3 @@
+//! Everything the bot and the host-side tools share. The `rustbot` binary adds the BWAPI client
+//! and the status webserver, `simulate` only needs the build order modules and runs without StarCraft.
+pub mod utils;
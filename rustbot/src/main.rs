@@ -1,9 +1,9 @@
 mod bot;
 mod map;
 mod status_webserver;
-mod utils;
 
 use bot::RustBot;
+use rustbot::utils;
 use status_webserver::start_server;
 use std::sync::{Arc, Mutex};
 use utils::game_state::GameState;
@@ -1,8 +1,30 @@
-use crate::utils::build_orders::build_order_item::BuildOrderItem;
-use crate::utils::game_state::{DebugFlag, SharedGameState, WorkerAssignment};
+use crate::utils::base_registry::{BaseOwner, BaseState};
+use crate::utils::build_order_editing::{self, BuildOrderEdit};
+use crate::utils::build_order_telemetry::{self, BuildOrderTimingReport};
+use crate::utils::build_orders::build_order_item::{
+  self, BuildOrderBenchmark, BuildOrderItem, BuildOrderItemParseError,
+};
+use crate::utils::build_orders::build_order_selector;
+use crate::utils::build_orders::build_order_trigger::BuildOrderTrigger;
+use crate::utils::building_stuff::builder_tracking::{
+  BuilderFailure, BuilderState, BuilderTracking,
+};
+use crate::utils::game_state::{
+  BuildOrderItemStatus, DebugFlag, GameState, ResourceReservation, SharedGameState,
+  WorkerAssignment,
+};
 use crate::utils::http_status_callbacks::SharedHttpStatusCallbacks;
+use crate::utils::income_tracker::IncomeSample;
+use crate::utils::map_utils::tile_reservations::TileReservation;
+use crate::utils::military::overlord_management::OverlordSpotKind;
+use crate::utils::military::worker_defense::DefenseRole;
+use crate::utils::rally_points::RallyRole;
+use crate::utils::resource_reservations;
+use crate::utils::scouting::{EnemyMainSource, ScoutKind};
+use crate::utils::upgrade_queue::ResearchSource;
 use axum::{
   extract::State,
+  http::StatusCode,
   response::IntoResponse,
   routing::{get, post},
   Json, Router,
@@ -42,7 +64,20 @@ pub async fn start_server(game_state: SharedGameState, callbacks: SharedHttpStat
     .route("/unit-orders", get(unit_orders_handler))
     .route("/military-assignments", get(military_assignments_handler))
     .route("/larvae", get(larvae_handler))
+    .route("/reservations", get(reservations_handler))
+    .route("/upgrade-queue", get(upgrade_queue_handler))
+    .route("/bases", get(bases_handler))
+    .route("/income", get(income_handler))
+    .route("/macro-plan", get(macro_plan_handler))
     .route("/build-order", get(build_order_handler))
+    .route("/build-order/insert", post(build_order_insert_handler))
+    .route("/build-order/replace", post(build_order_replace_handler))
+    .route("/build-order/remove", post(build_order_remove_handler))
+    .route("/build-order/move", post(build_order_move_handler))
+    .route("/build-order/skip", post(build_order_skip_handler))
+    .route("/build-order/load", post(build_order_load_handler))
+    .route("/build-orders", get(available_build_orders_handler))
+    .route("/build-order-timings", get(build_order_timings_handler))
     .route("/map", get(map_handler))
     .route("/game-speed", get(game_speed_handler))
     .route("/debug-flags", get(debug_flags_handler))
@@ -74,17 +109,101 @@ async fn command_handler(
       status.game_speed = cmd.value;
       println!("Game speed set to: {}", cmd.value);
     }
+  } else if cmd.command == "set_build_order_window" {
+    if let Ok(mut status) = game_state.lock() {
+      status.build_order_window = cmd.value.max(1) as usize;
+      println!("Build order window set to: {}", status.build_order_window);
+    }
   }
   "OK"
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct BaseSaturationDTO {
+  pub base_index: usize,
+  pub patches: usize,
+  pub extractors: usize,
+  pub mineral_workers: usize,
+  pub mineral_target: usize,
+  pub gas_workers: usize,
+  pub gas_target: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BaseThreatDTO {
+  pub base_index: usize,
+  pub enemies: usize,
+  pub drones_needed: usize,
+  pub outnumbered: bool,
+  pub first_seen_frame: i32,
+  pub fighting: Vec<usize>,
+  pub escaping: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BuilderFailureDTO {
+  pub frame: i32,
+  pub drone_id: usize,
+  pub site: Option<(i32, i32)>,
+  pub failure: BuilderFailure,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BuilderDTO {
+  pub build_order_index: usize,
+  pub drone_id: Option<usize>,
+  pub state: Option<BuilderState>,
+  pub assigned_frame: Option<i32>,
+  pub failures: Vec<BuilderFailureDTO>,
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct WorkerStatusSnapshot {
   pub worker_assignments: HashMap<usize, WorkerAssignment>,
+  pub base_saturation: Vec<BaseSaturationDTO>,
+  pub threats: Vec<BaseThreatDTO>,
+  /// Items with a builder or earlier failed builders
+  pub builders: Vec<BuilderDTO>,
   pub build_order: Vec<BuildOrderItem>,
   pub frame_count: i32,
 }
 
+fn builder_snapshot(tracking: &BuilderTracking) -> Vec<BuilderDTO> {
+  let mut indices: Vec<usize> = tracking
+    .builders
+    .keys()
+    .chain(tracking.failures.keys())
+    .copied()
+    .collect();
+  indices.sort_unstable();
+  indices.dedup();
+
+  indices
+    .into_iter()
+    .map(|build_order_index| {
+      let tracked = tracking.builders.get(&build_order_index);
+      BuilderDTO {
+        build_order_index,
+        drone_id: tracked.map(|t| t.drone_id),
+        state: tracked.map(|t| t.state),
+        assigned_frame: tracked.map(|t| t.assigned_frame),
+        failures: tracking
+          .failures
+          .get(&build_order_index)
+          .into_iter()
+          .flatten()
+          .map(|record| BuilderFailureDTO {
+            frame: record.frame,
+            drone_id: record.drone_id,
+            site: record.site,
+            failure: record.failure,
+          })
+          .collect(),
+      }
+    })
+    .collect()
+}
+
 async fn worker_status_handler(
   State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
 ) -> impl IntoResponse {
@@ -94,6 +213,45 @@ async fn worker_status_handler(
     move |_game: &rsbwapi::Game, state: &crate::utils::game_state::GameState| {
       let snapshot = WorkerStatusSnapshot {
         worker_assignments: state.worker_assignments.clone(),
+        base_saturation: state
+          .base_saturation
+          .iter()
+          .map(|base| BaseSaturationDTO {
+            base_index: base.base_index,
+            patches: base.mineral_ids.len(),
+            extractors: base.extractor_ids.len(),
+            mineral_workers: base.mineral_workers,
+            mineral_target: base.mineral_target(),
+            gas_workers: base.gas_workers,
+            gas_target: base.gas_target(),
+          })
+          .collect(),
+        threats: state
+          .worker_defense
+          .threats
+          .iter()
+          .map(|(base_index, threat)| {
+            let pulled_as = |role: DefenseRole| {
+              state
+                .worker_defense
+                .pulled
+                .iter()
+                .filter(|(_, drone)| drone.base_index == *base_index && drone.role == role)
+                .map(|(id, _)| *id)
+                .collect()
+            };
+            BaseThreatDTO {
+              base_index: *base_index,
+              enemies: threat.enemy_ids.len(),
+              drones_needed: threat.drones_needed,
+              outnumbered: threat.outnumbered,
+              first_seen_frame: threat.first_seen_frame,
+              fighting: pulled_as(DefenseRole::Fighting),
+              escaping: pulled_as(DefenseRole::Escaping),
+            }
+          })
+          .collect(),
+        builders: builder_snapshot(&state.builder_tracking),
         build_order: state.build_order.clone(),
         frame_count: _game.get_frame_count(),
       };
@@ -103,6 +261,9 @@ async fn worker_status_handler(
 
   let error_return = WorkerStatusSnapshot {
     worker_assignments: HashMap::new(),
+    base_saturation: Vec::new(),
+    threats: Vec::new(),
+    builders: Vec::new(),
     build_order: Vec::new(),
     frame_count: -1,
   };
@@ -204,6 +365,7 @@ async fn unit_orders_handler(
 pub struct LarvaeSnapshot {
   pub larva_responsibilities: HashMap<usize, usize>,
   pub assignment_details: HashMap<usize, String>,
+  pub selection_reasons: HashMap<usize, String>,
   pub frame_count: i32,
 }
 
@@ -217,26 +379,18 @@ async fn larvae_handler(
       let mut assignment_details = HashMap::new();
       for (larva_id, build_order_idx) in &state.larva_responsibilities {
         if let Some(item) = state.build_order.get(*build_order_idx) {
-          let detail = match item {
-            BuildOrderItem::Unit { unit_type, base_index } => {
-              match base_index {
-                Some(idx) => format!("{:?} @base{}", unit_type, idx),
-                None => format!("{:?}", unit_type),
-              }
-            }
-            BuildOrderItem::Upgrade(upgrade_type) => {
-              format!("{:?}", upgrade_type)
-            }
-            BuildOrderItem::Squad { name, role, status } => {
-              format!("Squad({}, {:?}, {:?})", name, role, status)
-            }
-          };
-          assignment_details.insert(*larva_id, detail);
+          assignment_details.insert(*larva_id, item.describe());
         }
       }
       let snapshot = LarvaeSnapshot {
         larva_responsibilities: state.larva_responsibilities.clone(),
         assignment_details,
+        selection_reasons: state
+          .larva_selection_reasons
+          .iter()
+          .filter(|(larva_id, _)| state.larva_responsibilities.contains_key(larva_id))
+          .map(|(larva_id, reason)| (*larva_id, reason.clone()))
+          .collect(),
         frame_count: _game.get_frame_count(),
       };
       let _ = tx.send(snapshot);
@@ -249,6 +403,7 @@ async fn larvae_handler(
     return Json(LarvaeSnapshot {
       larva_responsibilities: HashMap::new(),
       assignment_details: HashMap::new(),
+      selection_reasons: HashMap::new(),
       frame_count: -1,
     });
   }
@@ -258,11 +413,547 @@ async fn larvae_handler(
     Err(_) => Json(LarvaeSnapshot {
       larva_responsibilities: HashMap::new(),
       assignment_details: HashMap::new(),
+      selection_reasons: HashMap::new(),
       frame_count: -1,
     }),
   }
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct ReservationsSnapshot {
+  pub reservations: Vec<ResourceReservation>,
+  pub reserved_minerals: i32,
+  pub reserved_gas: i32,
+  pub minerals: i32,
+  pub gas: i32,
+  pub frame_count: i32,
+}
+
+impl ReservationsSnapshot {
+  fn empty() -> Self {
+    Self {
+      reservations: Vec::new(),
+      reserved_minerals: 0,
+      reserved_gas: 0,
+      minerals: 0,
+      gas: 0,
+      frame_count: -1,
+    }
+  }
+}
+
+async fn reservations_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+) -> impl IntoResponse {
+  let (tx, rx) = oneshot::channel();
+
+  let callback = Box::new(
+    move |game: &rsbwapi::Game, state: &crate::utils::game_state::GameState| {
+      let mut reservations: Vec<ResourceReservation> =
+        state.resource_reservations.values().copied().collect();
+      reservations.sort_by_key(|reservation| reservation.build_order_index);
+
+      let (reserved_minerals, reserved_gas) = resource_reservations::reserved_totals(state);
+      let (minerals, gas) = game
+        .self_()
+        .map(|player| (player.minerals(), player.gas()))
+        .unwrap_or((0, 0));
+
+      let snapshot = ReservationsSnapshot {
+        reservations,
+        reserved_minerals,
+        reserved_gas,
+        minerals,
+        gas,
+        frame_count: game.get_frame_count(),
+      };
+      let _ = tx.send(snapshot);
+    },
+  );
+
+  if let Ok(mut callbacks_lock) = callbacks.lock() {
+    callbacks_lock.add_callback(callback);
+  } else {
+    return Json(ReservationsSnapshot::empty());
+  }
+
+  match rx.await {
+    Ok(snapshot) => Json(snapshot),
+    Err(_) => Json(ReservationsSnapshot::empty()),
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct QueuedResearchDTO {
+  pub name: String,
+  pub priority: i32,
+  /// "Army", or the build order index that queued it
+  pub source: String,
+  pub minerals: i32,
+  pub gas: i32,
+  pub queued_frame: i32,
+  pub started_frame: Option<i32>,
+  pub building_id: Option<usize>,
+  pub waiting_for: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpgradeQueueSnapshot {
+  pub entries: Vec<QueuedResearchDTO>,
+  pub from_army: bool,
+  pub spare_minerals: i32,
+  pub spare_gas: i32,
+  pub frame_count: i32,
+}
+
+impl UpgradeQueueSnapshot {
+  fn empty() -> Self {
+    Self {
+      entries: Vec::new(),
+      from_army: false,
+      spare_minerals: 0,
+      spare_gas: 0,
+      frame_count: -1,
+    }
+  }
+}
+
+async fn upgrade_queue_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+) -> impl IntoResponse {
+  let (tx, rx) = oneshot::channel();
+
+  let callback = Box::new(
+    move |game: &rsbwapi::Game, state: &crate::utils::game_state::GameState| {
+      let entries = state
+        .upgrade_queue
+        .entries
+        .iter()
+        .map(|entry| {
+          let (minerals, gas) = entry.kind.cost(entry.level);
+          QueuedResearchDTO {
+            name: entry.kind.describe(entry.level),
+            priority: entry.priority,
+            source: match entry.source {
+              ResearchSource::BuildOrder(build_idx) => format!("Build order #{}", build_idx),
+              ResearchSource::Army => "Army".to_string(),
+            },
+            minerals,
+            gas,
+            queued_frame: entry.queued_frame,
+            started_frame: entry.started.map(|(_, frame)| frame),
+            building_id: entry.started.map(|(building_id, _)| building_id),
+            waiting_for: entry.waiting_for.clone(),
+          }
+        })
+        .collect();
+      let (spare_minerals, spare_gas) = resource_reservations::spare_resources(game, state);
+
+      let snapshot = UpgradeQueueSnapshot {
+        entries,
+        from_army: state.upgrade_queue.from_army,
+        spare_minerals,
+        spare_gas,
+        frame_count: game.get_frame_count(),
+      };
+      let _ = tx.send(snapshot);
+    },
+  );
+
+  if let Ok(mut callbacks_lock) = callbacks.lock() {
+    callbacks_lock.add_callback(callback);
+  } else {
+    return Json(UpgradeQueueSnapshot::empty());
+  }
+
+  match rx.await {
+    Ok(snapshot) => Json(snapshot),
+    Err(_) => Json(UpgradeQueueSnapshot::empty()),
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BaseDTO {
+  pub index: usize,
+  pub tile_x: i32,
+  pub tile_y: i32,
+  pub is_start_location: bool,
+  pub depot_id: Option<usize>,
+  pub owner: BaseOwner,
+  pub state: BaseState,
+  pub mineral_patches: usize,
+  pub geysers: usize,
+  pub minerals_left: i32,
+  pub gas_left: i32,
+  pub last_seen_frame: Option<i32>,
+  pub rally_role: Option<RallyRole>,
+  pub rally_position: Option<(i32, i32)>,
+  pub rally_set_frame: Option<i32>,
+  /// Where the planned sunkens face
+  pub defense_entrance: Option<(i32, i32)>,
+  pub sunken_sites: Vec<(i32, i32)>,
+  pub spore_sites: Vec<(i32, i32)>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScoutDTO {
+  pub unit_id: usize,
+  pub kind: ScoutKind,
+  pub target: Option<(i32, i32)>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BasesSnapshot {
+  pub bases: Vec<BaseDTO>,
+  pub enemy_main: Option<(i32, i32)>,
+  pub enemy_main_source: Option<EnemyMainSource>,
+  pub enemy_main_found_frame: Option<i32>,
+  pub unexplored_start_locations: Vec<(i32, i32)>,
+  pub scouts: Vec<ScoutDTO>,
+  pub frame_count: i32,
+}
+
+impl BasesSnapshot {
+  fn empty() -> Self {
+    Self {
+      bases: Vec::new(),
+      enemy_main: None,
+      enemy_main_source: None,
+      enemy_main_found_frame: None,
+      unexplored_start_locations: Vec::new(),
+      scouts: Vec::new(),
+      frame_count: -1,
+    }
+  }
+}
+
+async fn bases_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+) -> impl IntoResponse {
+  let (tx, rx) = oneshot::channel();
+
+  let callback = Box::new(
+    move |game: &rsbwapi::Game, state: &crate::utils::game_state::GameState| {
+      let bases = state
+        .bases
+        .iter()
+        .map(|base| {
+          let rally = base
+            .depot_id
+            .and_then(|depot_id| state.rally_points.rallies.get(&depot_id));
+          let defense = state.defense_placement.plan(base.index);
+          let tiles = |sites: &[rsbwapi::TilePosition]| -> Vec<(i32, i32)> {
+            sites.iter().map(|tile| (tile.x, tile.y)).collect()
+          };
+          BaseDTO {
+            index: base.index,
+            tile_x: base.tile.x,
+            tile_y: base.tile.y,
+            is_start_location: base.is_start_location,
+            depot_id: base.depot_id,
+            owner: base.owner,
+            state: base.state,
+            mineral_patches: base.mineral_ids.len(),
+            geysers: base.geyser_ids.len(),
+            minerals_left: base.minerals_left,
+            gas_left: base.gas_left,
+            last_seen_frame: base.last_seen_frame,
+            rally_role: rally.map(|rally| rally.role),
+            rally_position: rally.map(|rally| (rally.position.x, rally.position.y)),
+            rally_set_frame: rally.map(|rally| rally.set_frame),
+            defense_entrance: defense.map(|plan| (plan.entrance.x, plan.entrance.y)),
+            sunken_sites: defense.map_or(Vec::new(), |plan| tiles(&plan.sunken_sites)),
+            spore_sites: defense.map_or(Vec::new(), |plan| tiles(&plan.spore_sites)),
+          }
+        })
+        .collect();
+
+      let scouting = &state.scouting;
+      let snapshot = BasesSnapshot {
+        bases,
+        enemy_main: scouting.enemy_main.map(|tile| (tile.x, tile.y)),
+        enemy_main_source: scouting.enemy_main_source,
+        enemy_main_found_frame: scouting.found_frame,
+        unexplored_start_locations: scouting
+          .locations
+          .iter()
+          .filter(|location| location.explored_frame.is_none())
+          .map(|location| (location.tile.x, location.tile.y))
+          .collect(),
+        scouts: scouting
+          .scouts
+          .iter()
+          .map(|scout| ScoutDTO {
+            unit_id: scout.unit_id,
+            kind: scout.kind,
+            target: scout.target.map(|tile| (tile.x, tile.y)),
+          })
+          .collect(),
+        frame_count: game.get_frame_count(),
+      };
+      let _ = tx.send(snapshot);
+    },
+  );
+
+  if let Ok(mut callbacks_lock) = callbacks.lock() {
+    callbacks_lock.add_callback(callback);
+  } else {
+    return Json(BasesSnapshot::empty());
+  }
+
+  match rx.await {
+    Ok(snapshot) => Json(snapshot),
+    Err(_) => Json(BasesSnapshot::empty()),
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PatchDTO {
+  pub resource_id: usize,
+  pub base_index: Option<usize>,
+  pub is_gas: bool,
+  pub depot_distance: Option<f32>,
+  pub long_distance: bool,
+  pub trips: u32,
+  pub average_trip_frames: Option<f32>,
+  pub workers: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IncomeSnapshot {
+  pub samples: Vec<IncomeSample>,
+  pub patches: Vec<PatchDTO>,
+  pub near_trip_frames: f32,
+  pub frame_count: i32,
+}
+
+impl IncomeSnapshot {
+  fn empty() -> Self {
+    Self {
+      samples: Vec::new(),
+      patches: Vec::new(),
+      near_trip_frames: 0.0,
+      frame_count: -1,
+    }
+  }
+}
+
+async fn income_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+) -> impl IntoResponse {
+  let (tx, rx) = oneshot::channel();
+
+  let callback = Box::new(
+    move |game: &rsbwapi::Game, state: &crate::utils::game_state::GameState| {
+      let mut patches: Vec<PatchDTO> = state
+        .income
+        .patches
+        .values()
+        .map(|patch| PatchDTO {
+          resource_id: patch.resource_id,
+          base_index: patch.base_index,
+          is_gas: patch.is_gas,
+          depot_distance: patch.depot_distance,
+          long_distance: patch.is_long_distance(),
+          trips: patch.trips,
+          average_trip_frames: patch.average_trip_frames,
+          workers: patch.workers,
+        })
+        .collect();
+      patches.sort_by_key(|patch| (patch.base_index, patch.is_gas, patch.resource_id));
+
+      let snapshot = IncomeSnapshot {
+        samples: state.income.samples.clone(),
+        patches,
+        near_trip_frames: state.income.near_trip_frames(),
+        frame_count: game.get_frame_count(),
+      };
+      let _ = tx.send(snapshot);
+    },
+  );
+
+  if let Ok(mut callbacks_lock) = callbacks.lock() {
+    callbacks_lock.add_callback(callback);
+  } else {
+    return Json(IncomeSnapshot::empty());
+  }
+
+  match rx.await {
+    Ok(snapshot) => Json(snapshot),
+    Err(_) => Json(IncomeSnapshot::empty()),
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ArmyGoalDTO {
+  pub unit_type: String,
+  pub weight: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MacroGoalDTO {
+  pub name: String,
+  pub min_drones: usize,
+  pub done: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MacroDecisionDTO {
+  pub frame: i32,
+  pub item: String,
+  pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MacroPlanSnapshot {
+  pub active: bool,
+  pub army_composition: Vec<ArmyGoalDTO>,
+  pub tech: Vec<MacroGoalDTO>,
+  pub upgrades: Vec<MacroGoalDTO>,
+  pub drones: usize,
+  pub drone_target: usize,
+  pub unmet_saturation: usize,
+  pub gas_workers_per_extractor: usize,
+  pub gas_demand: i32,
+  /// Why the gas plan wants that many drones on gas
+  pub gas_reason: String,
+  pub bases: usize,
+  pub hatcheries: usize,
+  pub hatcheries_needed: usize,
+  pub larva_per_minute: f32,
+  pub supply_used: i32,
+  pub supply_total: i32,
+  pub supply_in_production: i32,
+  pub supply_forecast: i32,
+  pub army_supply: i32,
+  pub reasoning: Vec<String>,
+  pub decisions: Vec<MacroDecisionDTO>,
+  pub frame_count: i32,
+}
+
+impl MacroPlanSnapshot {
+  fn empty() -> Self {
+    Self {
+      active: false,
+      army_composition: Vec::new(),
+      tech: Vec::new(),
+      upgrades: Vec::new(),
+      drones: 0,
+      drone_target: 0,
+      unmet_saturation: 0,
+      gas_workers_per_extractor: 0,
+      gas_demand: 0,
+      gas_reason: String::new(),
+      bases: 0,
+      hatcheries: 0,
+      hatcheries_needed: 0,
+      larva_per_minute: 0.0,
+      supply_used: 0,
+      supply_total: 0,
+      supply_in_production: 0,
+      supply_forecast: 0,
+      army_supply: 0,
+      reasoning: Vec::new(),
+      decisions: Vec::new(),
+      frame_count: -1,
+    }
+  }
+}
+
+async fn macro_plan_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+) -> impl IntoResponse {
+  let (tx, rx) = oneshot::channel();
+
+  let callback = Box::new(
+    move |game: &rsbwapi::Game, state: &crate::utils::game_state::GameState| {
+      let plan = &state.macro_plan;
+      let player = game.self_();
+      let player_id = player.as_ref().map(|p| p.get_id());
+      let has_type = |unit_type: rsbwapi::UnitType| {
+        game
+          .get_all_units()
+          .iter()
+          .any(|u| u.get_type() == unit_type && Some(u.get_player().get_id()) == player_id)
+      };
+
+      // Supply is reported the way the game shows it, not in BWAPI's half supply
+      let snapshot = MacroPlanSnapshot {
+        active: state.build_order_index >= state.build_order.len(),
+        army_composition: plan
+          .goals
+          .army_composition
+          .iter()
+          .map(|goal| ArmyGoalDTO {
+            unit_type: format!("{:?}", goal.unit_type),
+            weight: goal.weight,
+          })
+          .collect(),
+        tech: plan
+          .goals
+          .tech
+          .iter()
+          .map(|goal| MacroGoalDTO {
+            name: format!("{:?}", goal.unit_type),
+            min_drones: goal.min_drones,
+            done: has_type(goal.unit_type),
+          })
+          .collect(),
+        upgrades: plan
+          .goals
+          .upgrades
+          .iter()
+          .map(|goal| MacroGoalDTO {
+            name: format!("{:?}", goal.upgrade_type),
+            min_drones: goal.min_drones,
+            done: player
+              .as_ref()
+              .is_some_and(|p| p.get_upgrade_level(goal.upgrade_type) > 0),
+          })
+          .collect(),
+        drones: plan.status.drones,
+        drone_target: plan.status.drone_target,
+        unmet_saturation: plan.status.unmet_saturation,
+        gas_workers_per_extractor: state.gas_plan.workers_per_extractor,
+        gas_demand: state.gas_plan.demand(),
+        gas_reason: state.gas_plan.reason.clone(),
+        bases: plan.status.bases,
+        hatcheries: plan.status.hatcheries,
+        hatcheries_needed: plan.status.hatcheries_needed,
+        larva_per_minute: plan.status.larva_per_minute,
+        supply_used: plan.status.supply_used / 2,
+        supply_total: plan.status.supply_total / 2,
+        supply_in_production: plan.status.supply_in_production / 2,
+        supply_forecast: plan.status.supply_forecast / 2,
+        army_supply: plan.status.army_supply / 2,
+        reasoning: plan.reasoning.clone(),
+        decisions: plan
+          .decisions
+          .iter()
+          .map(|decision| MacroDecisionDTO {
+            frame: decision.frame,
+            item: decision.item.clone(),
+            reason: decision.reason.clone(),
+          })
+          .collect(),
+        frame_count: game.get_frame_count(),
+      };
+      let _ = tx.send(snapshot);
+    },
+  );
+
+  if let Ok(mut callbacks_lock) = callbacks.lock() {
+    callbacks_lock.add_callback(callback);
+  } else {
+    return Json(MacroPlanSnapshot::empty());
+  }
+
+  match rx.await {
+    Ok(snapshot) => Json(snapshot),
+    Err(_) => Json(MacroPlanSnapshot::empty()),
+  }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct MilitaryUnitInfo {
   pub unit_id: usize,
@@ -284,9 +975,20 @@ pub struct SquadData {
   pub target_path_index: Option<usize>,
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct OverlordDTO {
+  pub unit_id: usize,
+  pub spot: Option<OverlordSpotKind>,
+  pub spot_position: Option<(i32, i32)>,
+  pub base_index: Option<usize>,
+  pub retreating: bool,
+  pub current_position: (i32, i32),
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct MilitaryAssignmentsSnapshot {
   pub squads: Vec<SquadData>,
+  pub overlords: Vec<OverlordDTO>,
   pub frame_count: i32,
 }
 
@@ -333,8 +1035,27 @@ async fn military_assignments_handler(
         })
         .collect();
 
+      let mut overlords: Vec<OverlordDTO> = state
+        .overlords
+        .jobs
+        .iter()
+        .filter_map(|(&unit_id, job)| {
+          let position = game.get_unit(unit_id)?.get_position();
+          Some(OverlordDTO {
+            unit_id,
+            spot: job.spot.map(|spot| spot.kind),
+            spot_position: job.spot.map(|spot| (spot.position.x, spot.position.y)),
+            base_index: job.spot.and_then(|spot| spot.base_index),
+            retreating: job.retreating_until.is_some(),
+            current_position: (position.x, position.y),
+          })
+        })
+        .collect();
+      overlords.sort_by_key(|overlord| (overlord.spot, overlord.unit_id));
+
       let snapshot = MilitaryAssignmentsSnapshot {
         squads,
+        overlords,
         frame_count: game.get_frame_count(),
       };
       let _ = tx.send(snapshot);
@@ -346,6 +1067,7 @@ async fn military_assignments_handler(
   } else {
     return Json(MilitaryAssignmentsSnapshot {
       squads: Vec::new(),
+      overlords: Vec::new(),
       frame_count: -1,
     });
   }
@@ -354,97 +1076,396 @@ async fn military_assignments_handler(
     Ok(snapshot) => Json(snapshot),
     Err(_) => Json(MilitaryAssignmentsSnapshot {
       squads: Vec::new(),
+      overlords: Vec::new(),
       frame_count: -1,
     }),
   }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "PascalCase")]
 pub enum BuildOrderItemDTO {
   Unit {
     unit_type: String,
     base_index: Option<usize>,
+    #[serde(default)]
+    trigger: Option<String>,
+    #[serde(default)]
+    benchmark: Option<BuildOrderBenchmark>,
   },
   Upgrade {
     upgrade_type: String,
+    #[serde(default)]
+    trigger: Option<String>,
+    #[serde(default)]
+    benchmark: Option<BuildOrderBenchmark>,
+  },
+  Research {
+    tech_type: String,
+    #[serde(default)]
+    trigger: Option<String>,
+    #[serde(default)]
+    benchmark: Option<BuildOrderBenchmark>,
   },
   Squad {
     name: String,
     role: String,
     status: String,
+    #[serde(default)]
+    trigger: Option<String>,
+    #[serde(default)]
+    benchmark: Option<BuildOrderBenchmark>,
   },
 }
 
 impl From<&BuildOrderItem> for BuildOrderItemDTO {
   fn from(item: &BuildOrderItem) -> Self {
     match item {
-      BuildOrderItem::Unit { unit_type, base_index } => BuildOrderItemDTO::Unit {
+      BuildOrderItem::Unit {
+        unit_type,
+        base_index,
+        trigger,
+        benchmark,
+      } => BuildOrderItemDTO::Unit {
         unit_type: format!("{:?}", unit_type),
         base_index: *base_index,
+        trigger: trigger.as_ref().map(|t| t.to_string()),
+        benchmark: *benchmark,
       },
-      BuildOrderItem::Upgrade(upgrade_type) => BuildOrderItemDTO::Upgrade {
+      BuildOrderItem::Upgrade {
+        upgrade_type,
+        trigger,
+        benchmark,
+      } => BuildOrderItemDTO::Upgrade {
         upgrade_type: format!("{:?}", upgrade_type),
+        trigger: trigger.as_ref().map(|t| t.to_string()),
+        benchmark: *benchmark,
       },
-      BuildOrderItem::Squad { name, role, status } => BuildOrderItemDTO::Squad {
+      BuildOrderItem::Research {
+        tech_type,
+        trigger,
+        benchmark,
+      } => BuildOrderItemDTO::Research {
+        tech_type: format!("{:?}", tech_type),
+        trigger: trigger.as_ref().map(|t| t.to_string()),
+        benchmark: *benchmark,
+      },
+      BuildOrderItem::Squad {
+        name,
+        role,
+        status,
+        trigger,
+        benchmark,
+      } => BuildOrderItemDTO::Squad {
         name: name.clone(),
         role: format!("{:?}", role),
         status: format!("{:?}", status),
+        trigger: trigger.as_ref().map(|t| t.to_string()),
+        benchmark: *benchmark,
       },
     }
   }
 }
 
+impl TryFrom<&BuildOrderItemDTO> for BuildOrderItem {
+  type Error = BuildOrderItemParseError;
+
+  fn try_from(dto: &BuildOrderItemDTO) -> Result<Self, Self::Error> {
+    let parse_trigger = |trigger: &Option<String>| {
+      trigger
+        .as_deref()
+        .map(|t| t.parse::<BuildOrderTrigger>())
+        .transpose()
+    };
+
+    match dto {
+      BuildOrderItemDTO::Unit {
+        unit_type,
+        base_index,
+        trigger,
+        benchmark,
+      } => Ok(BuildOrderItem::Unit {
+        unit_type: build_order_item::parse_unit_type(unit_type)?,
+        base_index: *base_index,
+        trigger: parse_trigger(trigger)?,
+        benchmark: *benchmark,
+      }),
+      BuildOrderItemDTO::Upgrade {
+        upgrade_type,
+        trigger,
+        benchmark,
+      } => Ok(BuildOrderItem::Upgrade {
+        upgrade_type: build_order_item::parse_upgrade_type(upgrade_type)?,
+        trigger: parse_trigger(trigger)?,
+        benchmark: *benchmark,
+      }),
+      BuildOrderItemDTO::Research {
+        tech_type,
+        trigger,
+        benchmark,
+      } => Ok(BuildOrderItem::Research {
+        tech_type: build_order_item::parse_tech_type(tech_type)?,
+        trigger: parse_trigger(trigger)?,
+        benchmark: *benchmark,
+      }),
+      BuildOrderItemDTO::Squad {
+        name,
+        role,
+        status,
+        trigger,
+        benchmark,
+      } => Ok(BuildOrderItem::Squad {
+        name: name.clone(),
+        role: build_order_item::parse_squad_role(role)?,
+        status: build_order_item::parse_squad_status(status)?,
+        trigger: parse_trigger(trigger)?,
+        benchmark: *benchmark,
+      }),
+    }
+  }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct BuildOrderSnapshot {
+  pub build_order_name: String,
   pub build_order: Vec<BuildOrderItemDTO>,
+  pub build_order_status: Vec<BuildOrderItemStatus>,
   pub build_order_index: usize,
+  pub build_order_window: usize,
   pub frame_count: i32,
 }
 
+impl BuildOrderSnapshot {
+  fn from_state(game: &rsbwapi::Game, state: &GameState) -> Self {
+    Self {
+      build_order: state
+        .build_order
+        .iter()
+        .map(BuildOrderItemDTO::from)
+        .collect(),
+      build_order_name: state.build_order_name.clone(),
+      build_order_status: state.build_order_status.clone(),
+      build_order_index: state.build_order_index,
+      build_order_window: state.build_order_window,
+      frame_count: game.get_frame_count(),
+    }
+  }
+
+  fn empty() -> Self {
+    Self {
+      build_order_name: String::new(),
+      build_order: Vec::new(),
+      build_order_status: Vec::new(),
+      build_order_index: 0,
+      build_order_window: 0,
+      frame_count: -1,
+    }
+  }
+}
+
 async fn build_order_handler(
   State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
 ) -> impl IntoResponse {
   let (tx, rx) = oneshot::channel();
 
+  let callback = Box::new(move |game: &rsbwapi::Game, state: &GameState| {
+    let _ = tx.send(BuildOrderSnapshot::from_state(game, state));
+  });
+
+  if let Ok(mut callbacks_lock) = callbacks.lock() {
+    callbacks_lock.add_callback(callback);
+  } else {
+    return Json(BuildOrderSnapshot::empty());
+  }
+
+  match rx.await {
+    Ok(snapshot) => Json(snapshot),
+    Err(_) => Json(BuildOrderSnapshot::empty()),
+  }
+}
+
+/// Item in an edit request, either the text form (`Zerg_Hatchery @base1 if supply >= 12`) or
+/// the same shape `/build-order` returns
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum BuildOrderEditItem {
+  Text(String),
+  Structured(BuildOrderItemDTO),
+}
+
+impl TryFrom<&BuildOrderEditItem> for BuildOrderItem {
+  type Error = BuildOrderItemParseError;
+
+  fn try_from(item: &BuildOrderEditItem) -> Result<Self, Self::Error> {
+    match item {
+      BuildOrderEditItem::Text(text) => text.parse(),
+      BuildOrderEditItem::Structured(dto) => BuildOrderItem::try_from(dto),
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildOrderInsertRequest {
+  pub index: usize,
+  pub item: BuildOrderEditItem,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildOrderRemoveRequest {
+  pub index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildOrderMoveRequest {
+  pub from: usize,
+  pub to: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildOrderLoadRequest {
+  pub name: String,
+}
+
+type BuildOrderEditResult = Result<Json<BuildOrderSnapshot>, (StatusCode, String)>;
+
+fn parse_edit_item(item: &BuildOrderEditItem) -> Result<BuildOrderItem, (StatusCode, String)> {
+  BuildOrderItem::try_from(item).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Applies the edit on the next frame and answers with the build order after it
+async fn queue_build_order_edit(
+  callbacks: SharedHttpStatusCallbacks,
+  edit: BuildOrderEdit,
+) -> BuildOrderEditResult {
+  let (tx, rx) = oneshot::channel();
+
+  let mutation = Box::new(move |game: &rsbwapi::Game, state: &mut GameState| {
+    let result = build_order_editing::apply_edit(state, edit)
+      .map(|_| BuildOrderSnapshot::from_state(game, state))
+      .map_err(|e| e.to_string());
+    let _ = tx.send(result);
+  });
+
+  if let Ok(mut callbacks_lock) = callbacks.lock() {
+    callbacks_lock.add_mutation(mutation);
+  } else {
+    return Err((
+      StatusCode::INTERNAL_SERVER_ERROR,
+      "Error queueing build order edit".to_string(),
+    ));
+  }
+
+  match rx.await {
+    Ok(Ok(snapshot)) => Ok(Json(snapshot)),
+    Ok(Err(e)) => Err((StatusCode::CONFLICT, e)),
+    Err(_) => Err((
+      StatusCode::SERVICE_UNAVAILABLE,
+      "Game ended before the edit was applied".to_string(),
+    )),
+  }
+}
+
+async fn build_order_insert_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+  Json(req): Json<BuildOrderInsertRequest>,
+) -> BuildOrderEditResult {
+  let item = parse_edit_item(&req.item)?;
+  queue_build_order_edit(
+    callbacks,
+    BuildOrderEdit::Insert {
+      index: req.index,
+      item,
+    },
+  )
+  .await
+}
+
+async fn build_order_replace_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+  Json(req): Json<BuildOrderInsertRequest>,
+) -> BuildOrderEditResult {
+  let item = parse_edit_item(&req.item)?;
+  queue_build_order_edit(
+    callbacks,
+    BuildOrderEdit::Replace {
+      index: req.index,
+      item,
+    },
+  )
+  .await
+}
+
+async fn build_order_remove_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+  Json(req): Json<BuildOrderRemoveRequest>,
+) -> BuildOrderEditResult {
+  queue_build_order_edit(callbacks, BuildOrderEdit::Remove { index: req.index }).await
+}
+
+async fn build_order_move_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+  Json(req): Json<BuildOrderMoveRequest>,
+) -> BuildOrderEditResult {
+  queue_build_order_edit(
+    callbacks,
+    BuildOrderEdit::Move {
+      from: req.from,
+      to: req.to,
+    },
+  )
+  .await
+}
+
+async fn build_order_skip_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+) -> BuildOrderEditResult {
+  queue_build_order_edit(callbacks, BuildOrderEdit::SkipCurrent).await
+}
+
+async fn build_order_load_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+  Json(req): Json<BuildOrderLoadRequest>,
+) -> BuildOrderEditResult {
+  queue_build_order_edit(callbacks, BuildOrderEdit::Load { name: req.name }).await
+}
+
+/// Build orders that can be loaded with `/build-order/load`
+async fn available_build_orders_handler() -> impl IntoResponse {
+  Json(build_order_selector::available_build_order_names())
+}
+
+async fn build_order_timings_handler(
+  State((_, callbacks)): State<(SharedGameState, SharedHttpStatusCallbacks)>,
+) -> impl IntoResponse {
+  let (tx, rx) = oneshot::channel();
+
   let callback = Box::new(
-    move |_game: &rsbwapi::Game, state: &crate::utils::game_state::GameState| {
-      let snapshot = BuildOrderSnapshot {
-        build_order: state
-          .build_order
-          .iter()
-          .map(|item| BuildOrderItemDTO::from(item))
-          .collect(),
-        build_order_index: state.build_order_index,
-        frame_count: _game.get_frame_count(),
-      };
-      let _ = tx.send(snapshot);
+    move |game: &rsbwapi::Game, state: &crate::utils::game_state::GameState| {
+      let _ = tx.send(build_order_telemetry::build_report(game, state, None));
     },
   );
 
   if let Ok(mut callbacks_lock) = callbacks.lock() {
     callbacks_lock.add_callback(callback);
   } else {
-    return Json(BuildOrderSnapshot {
-      build_order: Vec::new(),
-      build_order_index: 0,
-      frame_count: -1,
-    });
+    return Json(BuildOrderTimingReport::empty());
   }
 
   match rx.await {
-    Ok(snapshot) => Json(snapshot),
-    Err(_) => Json(BuildOrderSnapshot {
-      build_order: Vec::new(),
-      build_order_index: 0,
-      frame_count: -1,
-    }),
+    Ok(report) => Json(report),
+    Err(_) => Json(BuildOrderTimingReport::empty()),
   }
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub struct MapSnapshot {
   pub map_data: crate::map::MapData,
+  /// Build tiles with creep
+  pub creep_tiles: Vec<(i32, i32)>,
+  /// Build tiles expected to get creep, with the frame
+  pub creep_forecast: Vec<(i32, i32, i32)>,
+  /// Build tiles kept free of buildings, with why
+  pub reserved_tiles: Vec<(i32, i32, TileReservation)>,
   pub frame_count: i32,
 }
 
@@ -454,10 +1475,13 @@ async fn map_handler(
   let (tx, rx) = oneshot::channel();
 
   let callback = Box::new(
-    move |game: &rsbwapi::Game, _state: &crate::utils::game_state::GameState| {
+    move |game: &rsbwapi::Game, state: &crate::utils::game_state::GameState| {
       let map_data = crate::map::collect_map_data(game);
       let snapshot = MapSnapshot {
         map_data,
+        creep_tiles: state.creep_map.creep_tiles().collect(),
+        creep_forecast: state.creep_map.forecast_tiles().collect(),
+        reserved_tiles: state.tile_reservations.reserved_tiles().collect(),
         frame_count: game.get_frame_count(),
       };
       let _ = tx.send(snapshot);
@@ -469,6 +1493,9 @@ async fn map_handler(
   } else {
     return Json(MapSnapshot {
       map_data: crate::map::MapData::default(),
+      creep_tiles: Vec::new(),
+      creep_forecast: Vec::new(),
+      reserved_tiles: Vec::new(),
       frame_count: -1,
     });
   }
@@ -477,6 +1504,9 @@ async fn map_handler(
     Ok(snapshot) => Json(snapshot),
     Err(_) => Json(MapSnapshot {
       map_data: crate::map::MapData::default(),
+      creep_tiles: Vec::new(),
+      creep_forecast: Vec::new(),
+      reserved_tiles: Vec::new(),
       frame_count: -1,
     }),
   }
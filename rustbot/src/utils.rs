@@ -1,11 +1,23 @@
+pub mod base_registry;
+pub mod build_order_editing;
 pub mod build_order_management;
+pub mod build_order_telemetry;
 pub mod game_state;
+pub mod gas_demand;
 pub mod http_status_callbacks;
+pub mod income_tracker;
+pub mod macro_planner;
+pub mod rally_points;
+pub mod resource_reservations;
+pub mod scouting;
+pub mod upgrade_queue;
 pub mod worker_management;
 
 pub mod building_stuff {
   pub mod build_location_utils;
+  pub mod builder_tracking;
   pub mod creature_stuff;
+  pub mod defense_placement;
   pub mod expansion_location_stuff;
   pub mod researching_stuff;
   pub mod structure_stuff;
@@ -14,18 +26,28 @@ pub mod building_stuff {
 pub mod military {
   pub mod avoid_enemy_movement_utils;
   pub mod military_management;
+  pub mod overlord_management;
   pub mod squad_attack_workers;
   pub mod squad_defend;
   pub mod squad_models;
   pub mod squad_mutas;
+  pub mod worker_defense;
 }
 
 pub mod map_utils {
+  pub mod creep_map;
+  pub mod geometry;
   pub mod pathing;
   pub mod region_stuff;
+  pub mod tile_reservations;
 }
 
 pub mod build_orders {
   pub mod build_order_item;
+  pub mod build_order_loader;
+  pub mod build_order_selector;
+  pub mod build_order_simulator;
+  pub mod build_order_trigger;
   pub mod pool_speed_expand;
+  pub mod type_names;
 }
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::utils::build_order_management;
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::game_state::{GameState, WorkerAssignmentType};
+use crate::utils::map_utils::geometry::{base_center, pixel_distance, BASE_RESOURCE_RADIUS};
+use crate::utils::worker_management;
+use rsbwapi::*;
+
+/// A resource depot this close to a base location sits on it
+const BASE_DEPOT_RADIUS: f32 = 4.0 * 32.0;
+/// Fewer minerals than this left and the base isn't worth mining
+const MINED_OUT_MINERALS: i32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BaseOwner {
+  Own,
+  Enemy,
+  /// Seen without a resource depot on it
+  Neutral,
+  /// Never seen, or our hatchery died out of sight
+  Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BaseState {
+  /// Nothing of ours there or on the way
+  Free,
+  /// A hatchery for it is in the build order window or a drone is on the way to build it
+  Planned,
+  /// Our hatchery is morphing there
+  Building,
+  /// Our finished hatchery with minerals to mine
+  Active,
+  /// Our hatchery is there but the minerals are about gone
+  MinedOut,
+  /// We had a hatchery there and it died
+  Lost,
+}
+
+#[derive(Debug, Clone)]
+pub struct Base {
+  /// Index into `base_locations`, what build order items use as `base_index`
+  pub index: usize,
+  pub tile: TilePosition,
+  pub is_start_location: bool,
+  pub depot_id: Option<usize>,
+  pub owner: BaseOwner,
+  pub state: BaseState,
+  pub mineral_ids: Vec<usize>,
+  pub geyser_ids: Vec<usize>,
+  /// As of `last_seen_frame`
+  pub minerals_left: i32,
+  pub gas_left: i32,
+  pub last_seen_frame: Option<i32>,
+}
+
+impl Base {
+  pub fn center(&self) -> Position {
+    base_center(&self.tile)
+  }
+
+  /// Ours with a finished hatchery, mined out or not
+  pub fn is_active(&self) -> bool {
+    matches!(self.state, BaseState::Active | BaseState::MinedOut)
+  }
+
+  /// Somewhere a new hatchery could go
+  pub fn is_free(&self) -> bool {
+    matches!(self.state, BaseState::Free | BaseState::Lost) && self.owner != BaseOwner::Enemy
+  }
+}
+
+/// Builds a base for every entry of `base_locations`, with the resources around it
+pub fn init(game: &Game, game_state: &mut GameState) {
+  let start_locations = game.get_start_locations();
+  let minerals = game.get_static_minerals();
+  let geysers = game.get_static_geysers();
+
+  game_state.bases = game_state
+    .base_locations
+    .iter()
+    .enumerate()
+    .map(|(index, tile)| {
+      let center = base_center(tile);
+      let near_base = |u: &&Unit| pixel_distance(u.get_position(), center) <= BASE_RESOURCE_RADIUS;
+      let base_minerals: Vec<&Unit> = minerals.iter().filter(near_base).collect();
+      let base_geysers: Vec<&Unit> = geysers.iter().filter(near_base).collect();
+
+      Base {
+        index,
+        tile: *tile,
+        is_start_location: start_locations
+          .iter()
+          .any(|start| (start.x - tile.x).abs() <= 3 && (start.y - tile.y).abs() <= 3),
+        depot_id: None,
+        owner: BaseOwner::Unknown,
+        state: BaseState::Free,
+        mineral_ids: base_minerals.iter().map(|m| m.get_id()).collect(),
+        geyser_ids: base_geysers.iter().map(|g| g.get_id()).collect(),
+        minerals_left: base_minerals
+          .iter()
+          .map(|m| m.get_initial_resources())
+          .sum(),
+        gas_left: base_geysers.iter().map(|g| g.get_initial_resources()).sum(),
+        last_seen_frame: None,
+      }
+    })
+    .collect();
+
+  println!("[BASES] {} bases registered", game_state.bases.len());
+}
+
+/// Bases a hatchery is planned for: unstarted ones in the build order window, or wherever a
+/// drone is on its way to build one
+fn planned_base_indices(game_state: &GameState) -> HashSet<usize> {
+  let is_hatchery = |build_idx: usize| {
+    matches!(
+      game_state.build_order.get(build_idx),
+      Some(BuildOrderItem::Unit {
+        unit_type: UnitType::Zerg_Hatchery,
+        ..
+      })
+    )
+  };
+
+  let mut planned: HashSet<usize> = build_order_management::build_order_window_indices(game_state)
+    .filter(|&idx| {
+      game_state
+        .build_order_status
+        .get(idx)
+        .is_some_and(|status| status.is_unstarted())
+    })
+    .filter_map(|idx| match game_state.build_order.get(idx) {
+      Some(BuildOrderItem::Unit {
+        unit_type: UnitType::Zerg_Hatchery,
+        base_index,
+        ..
+      }) => *base_index,
+      _ => None,
+    })
+    .collect();
+
+  for assignment in game_state.worker_assignments.values() {
+    if assignment.assignment_type != WorkerAssignmentType::Building
+      || !assignment.build_order_index.is_some_and(is_hatchery)
+    {
+      continue;
+    }
+    let Some((x, y)) = assignment.target_position else {
+      continue;
+    };
+    if let Some(base) = game_state
+      .bases
+      .iter()
+      .find(|base| (base.tile.x - x).abs() <= 3 && (base.tile.y - y).abs() <= 3)
+    {
+      planned.insert(base.index);
+    }
+  }
+  planned
+}
+
+/// Updates owners, depots, remaining resources and states from what is visible this frame.
+/// Drones are moved off bases that just ran out or were lost.
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let Some(player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+  let depots: Vec<Unit> = game
+    .get_all_units()
+    .into_iter()
+    .filter(|u| u.get_type().is_resource_depot())
+    .collect();
+  let planned = planned_base_indices(game_state);
+  let mut stranded: Option<String> = None;
+
+  for base in game_state.bases.iter_mut() {
+    let center = base.center();
+    let visible = game.is_visible(base.tile);
+    if visible {
+      base.last_seen_frame = Some(frame);
+      let resources_left = |ids: &[usize]| {
+        ids
+          .iter()
+          .filter_map(|id| game.get_unit(*id))
+          .filter(|u| u.exists())
+          .map(|u| u.get_resources())
+          .sum::<i32>()
+      };
+      base.minerals_left = resources_left(&base.mineral_ids);
+      base.gas_left = resources_left(&base.geyser_ids);
+    }
+
+    let depot = depots
+      .iter()
+      .find(|depot| pixel_distance(depot.get_position(), center) <= BASE_DEPOT_RADIUS);
+    let own_depot = depot.filter(|depot| depot.get_player().get_id() == player.get_id());
+
+    let was_ours = matches!(
+      base.state,
+      BaseState::Building | BaseState::Active | BaseState::MinedOut | BaseState::Lost
+    );
+    let state = match own_depot {
+      Some(depot) if !depot.is_completed() => BaseState::Building,
+      Some(_) if base.minerals_left < MINED_OUT_MINERALS => BaseState::MinedOut,
+      Some(_) => BaseState::Active,
+      None if planned.contains(&base.index) => BaseState::Planned,
+      None if was_ours => BaseState::Lost,
+      None => BaseState::Free,
+    };
+
+    // Enemy depots out of sight are remembered from when they were last seen
+    let owner = match depot {
+      Some(_) if own_depot.is_some() => BaseOwner::Own,
+      Some(_) => BaseOwner::Enemy,
+      None if visible => BaseOwner::Neutral,
+      None if base.owner == BaseOwner::Own => BaseOwner::Unknown,
+      None => base.owner,
+    };
+    base.depot_id = match depot {
+      Some(depot) => Some(depot.get_id()),
+      None if owner == BaseOwner::Enemy => base.depot_id,
+      None => None,
+    };
+
+    if state != base.state || owner != base.owner {
+      println!(
+        "[BASES] base {} {:?}/{:?} -> {:?}/{:?}",
+        base.index, base.state, base.owner, state, owner
+      );
+    }
+    if matches!(state, BaseState::MinedOut | BaseState::Lost) && state != base.state {
+      stranded = Some(format!("base {} is {:?}", base.index, state));
+    }
+    base.state = state;
+    base.owner = owner;
+  }
+
+  if let Some(reason) = stranded {
+    worker_management::schedule_worker_transfer(game_state, &reason);
+  }
+}
+
+/// Bases with one of our hatcheries on them, finished or not
+pub fn own_bases(game_state: &GameState) -> impl Iterator<Item = &Base> {
+  game_state
+    .bases
+    .iter()
+    .filter(|base| base.owner == BaseOwner::Own)
+}
@@ -0,0 +1,281 @@
+use std::fmt;
+
+use crate::utils::build_order_management;
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::build_orders::build_order_selector;
+use crate::utils::game_state::{BuildOrderItemStatus, GameState};
+use crate::utils::macro_planner::MacroGoals;
+
+/// A change to the build order made while the game is running. Indices are into the whole
+/// build order, at or after `build_order_index`, and items they change must not have started.
+#[derive(Debug, Clone)]
+pub enum BuildOrderEdit {
+  Insert { index: usize, item: BuildOrderItem },
+  Remove { index: usize },
+  Replace { index: usize, item: BuildOrderItem },
+  Move { from: usize, to: usize },
+  SkipCurrent,
+  Load { name: String },
+}
+
+#[derive(Debug, Clone)]
+pub enum BuildOrderEditError {
+  BeforeCurrent {
+    index: usize,
+    build_order_index: usize,
+  },
+  Started {
+    index: usize,
+  },
+  OutOfRange {
+    index: usize,
+    len: usize,
+  },
+  NothingToSkip,
+  UnknownBuildOrder(String),
+}
+
+impl fmt::Display for BuildOrderEditError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BuildOrderEditError::BeforeCurrent {
+        index,
+        build_order_index,
+      } => write!(
+        f,
+        "index {} is before the current item, edits start at {}",
+        index, build_order_index
+      ),
+      BuildOrderEditError::Started { index } => {
+        write!(f, "item {} has already started", index)
+      }
+      BuildOrderEditError::OutOfRange { index, len } => {
+        write!(
+          f,
+          "index {} is out of range, the build order has {} items",
+          index, len
+        )
+      }
+      BuildOrderEditError::NothingToSkip => write!(f, "no unstarted item to skip"),
+      BuildOrderEditError::UnknownBuildOrder(name) => {
+        write!(f, "no build order named `{}`", name)
+      }
+    }
+  }
+}
+
+impl std::error::Error for BuildOrderEditError {}
+
+impl BuildOrderEdit {
+  fn describe(&self) -> String {
+    match self {
+      BuildOrderEdit::Insert { index, item } => format!("insert {} at {}", item.describe(), index),
+      BuildOrderEdit::Remove { index } => format!("remove item {}", index),
+      BuildOrderEdit::Replace { index, item } => {
+        format!("replace item {} with {}", index, item.describe())
+      }
+      BuildOrderEdit::Move { from, to } => format!("move item {} to {}", from, to),
+      BuildOrderEdit::SkipCurrent => "skip current item".to_string(),
+      BuildOrderEdit::Load { name } => format!("load build order '{}'", name),
+    }
+  }
+}
+
+/// Applies one edit. Items that stay keep their larva, drone, building and reservation under
+/// their new index, only removed or replaced items give theirs up.
+pub fn apply_edit(
+  game_state: &mut GameState,
+  edit: BuildOrderEdit,
+) -> Result<(), BuildOrderEditError> {
+  let reason = format!("edited from status server: {}", edit.describe());
+  build_order_management::sync_build_order_status(game_state);
+
+  let build_order_index = game_state.build_order_index;
+  let len = game_state.build_order.len();
+  // Inserting may also append at the end, every other edit changes an existing item
+  let check = |index: usize, existing: bool| {
+    if index < build_order_index {
+      Err(BuildOrderEditError::BeforeCurrent {
+        index,
+        build_order_index,
+      })
+    } else if index > len || (index == len && existing) {
+      Err(BuildOrderEditError::OutOfRange { index, len })
+    } else if existing
+      && matches!(
+        game_state.build_order_status[index],
+        BuildOrderItemStatus::Started | BuildOrderItemStatus::Completed
+      )
+    {
+      Err(BuildOrderEditError::Started { index })
+    } else {
+      Ok(index)
+    }
+  };
+
+  let mut items = game_state.build_order.clone();
+  // Old index of the item in each slot, `None` for items the edit adds
+  let mut old_index: Vec<Option<usize>> = (0..len).map(Some).collect();
+
+  match edit {
+    BuildOrderEdit::Insert { index, item } => {
+      let at = check(index, false)?;
+      items.insert(at, item);
+      old_index.insert(at, None);
+    }
+    BuildOrderEdit::Remove { index } => {
+      let at = check(index, true)?;
+      items.remove(at);
+      old_index.remove(at);
+    }
+    BuildOrderEdit::Replace { index, item } => {
+      let at = check(index, true)?;
+      items[at] = item;
+      old_index[at] = None;
+    }
+    BuildOrderEdit::Move { from, to } => {
+      let from = check(from, true)?;
+      if to >= len {
+        return Err(BuildOrderEditError::OutOfRange { index: to, len });
+      }
+      let to = check(to, false)?;
+      let item = items.remove(from);
+      let old = old_index.remove(from);
+      items.insert(to, item);
+      old_index.insert(to, old);
+    }
+    BuildOrderEdit::SkipCurrent => {
+      if !build_order_management::skip_current_item(game_state, &reason) {
+        return Err(BuildOrderEditError::NothingToSkip);
+      }
+      return Ok(());
+    }
+    BuildOrderEdit::Load { name } => {
+      let Some(loaded) = build_order_selector::load_by_name(&name) else {
+        return Err(BuildOrderEditError::UnknownBuildOrder(name));
+      };
+      build_order_management::replace_remaining_build_order(game_state, loaded.items, &reason);
+      game_state.build_order_name = format!("{} -> {}", game_state.build_order_name, loaded.name);
+      game_state.build_order_continuations = loaded.continuations;
+      game_state.macro_plan.goals = MacroGoals::for_build_order(&game_state.build_order);
+      return Ok(());
+    }
+  }
+
+  let mut new_index = vec![None; len];
+  for (new, old) in old_index.iter().enumerate() {
+    if let Some(old) = old {
+      new_index[*old] = Some(new);
+    }
+  }
+  build_order_management::rearrange_build_order(game_state, items, &new_index);
+  build_order_management::advance_build_order(game_state, &reason);
+  println!(
+    "[BUILD ORDER] {}, {} items total",
+    reason,
+    game_state.build_order.len()
+  );
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::game_state::WorkerAssignment;
+  use rsbwapi::UnitType;
+
+  const DRONE_ID: usize = 7;
+
+  /// Drone, pool (assigned to drone 7), overlord (started), hatchery
+  fn game_state() -> GameState {
+    let mut game_state = GameState {
+      build_order: vec![
+        BuildOrderItem::unit(UnitType::Zerg_Drone),
+        BuildOrderItem::unit(UnitType::Zerg_Spawning_Pool),
+        BuildOrderItem::unit(UnitType::Zerg_Overlord),
+        BuildOrderItem::unit(UnitType::Zerg_Hatchery),
+      ],
+      ..Default::default()
+    };
+    build_order_management::sync_build_order_status(&mut game_state);
+    game_state.build_order_status[1] = BuildOrderItemStatus::Assigned;
+    game_state.build_order_status[2] = BuildOrderItemStatus::Started;
+    game_state
+      .worker_assignments
+      .insert(DRONE_ID, WorkerAssignment::building(None, (10, 10), 1));
+    game_state
+  }
+
+  fn assigned_index(game_state: &GameState) -> Option<usize> {
+    game_state
+      .worker_assignments
+      .get(&DRONE_ID)
+      .and_then(|assignment| assignment.build_order_index)
+  }
+
+  #[test]
+  fn appending_keeps_assignments() {
+    let mut game_state = game_state();
+    let item = BuildOrderItem::unit(UnitType::Zerg_Zergling);
+    apply_edit(&mut game_state, BuildOrderEdit::Insert { index: 4, item }).unwrap();
+
+    assert_eq!(game_state.build_order.len(), 5);
+    assert_eq!(assigned_index(&game_state), Some(1));
+    assert_eq!(
+      game_state.build_order_status[1],
+      BuildOrderItemStatus::Assigned
+    );
+  }
+
+  #[test]
+  fn inserting_before_an_assigned_item_moves_its_drone_along() {
+    let mut game_state = game_state();
+    let item = BuildOrderItem::unit(UnitType::Zerg_Zergling);
+    apply_edit(&mut game_state, BuildOrderEdit::Insert { index: 0, item }).unwrap();
+
+    assert_eq!(assigned_index(&game_state), Some(2));
+    assert_eq!(
+      game_state.build_order_status[2],
+      BuildOrderItemStatus::Assigned
+    );
+    assert_eq!(
+      game_state.build_order_status[3],
+      BuildOrderItemStatus::Started
+    );
+  }
+
+  #[test]
+  fn removing_an_assigned_item_releases_its_drone() {
+    let mut game_state = game_state();
+    apply_edit(&mut game_state, BuildOrderEdit::Remove { index: 1 }).unwrap();
+
+    assert!(!game_state.worker_assignments.contains_key(&DRONE_ID));
+    assert_eq!(
+      game_state.build_order[1],
+      BuildOrderItem::unit(UnitType::Zerg_Overlord)
+    );
+  }
+
+  #[test]
+  fn only_started_items_and_those_before_the_current_one_are_locked() {
+    let mut game_state = game_state();
+    game_state.build_order_status[0] = BuildOrderItemStatus::Completed;
+    game_state.build_order_index = 1;
+
+    assert!(matches!(
+      apply_edit(&mut game_state, BuildOrderEdit::Remove { index: 0 }),
+      Err(BuildOrderEditError::BeforeCurrent { .. })
+    ));
+    assert!(matches!(
+      apply_edit(&mut game_state, BuildOrderEdit::Remove { index: 2 }),
+      Err(BuildOrderEditError::Started { index: 2 })
+    ));
+    // Behind a started item, but not started itself
+    apply_edit(&mut game_state, BuildOrderEdit::Move { from: 3, to: 1 }).unwrap();
+    assert_eq!(
+      game_state.build_order[1],
+      BuildOrderItem::unit(UnitType::Zerg_Hatchery)
+    );
+    assert_eq!(assigned_index(&game_state), Some(2));
+  }
+}
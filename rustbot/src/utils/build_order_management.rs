@@ -1,18 +1,384 @@
 use crate::utils::build_orders::build_order_item::BuildOrderItem;
 use crate::utils::building_stuff::{creature_stuff, researching_stuff, structure_stuff};
-use crate::utils::game_state::GameState;
+use crate::utils::game_state::{
+  BuildOrderItemStatus, BuildOrderItemTiming, GameState, ReservationKind, WorkerAssignmentType,
+};
+use crate::utils::macro_planner;
 use crate::utils::military::military_management;
+use crate::utils::resource_reservations;
+use crate::utils::upgrade_queue;
 use rsbwapi::*;
+use std::collections::HashMap;
 
-/// Advances the build order to the next item and logs the reason
-pub fn advance_build_order(game: &Game, game_state: &mut GameState, reason: &str) {
+/// Keeps `build_order_status` and `build_order_timings` the same length as `build_order`, new
+/// items start out pending
+pub fn sync_build_order_status(game_state: &mut GameState) {
+  let len = game_state.build_order.len();
+  game_state
+    .build_order_status
+    .resize(len, BuildOrderItemStatus::Pending);
+  game_state
+    .build_order_timings
+    .resize(len, BuildOrderItemTiming::default());
+}
+
+/// Indices of the items that may be worked on at the same time
+pub fn build_order_window_indices(game_state: &GameState) -> std::ops::Range<usize> {
+  let len = game_state.build_order.len();
+  let start = game_state.build_order_index.min(len);
+  let end = (start + game_state.build_order_window.max(1)).min(len);
+  start..end
+}
+
+/// Whether the item still needs a larva, drone or building to act on it
+pub fn is_unstarted(game_state: &GameState, build_idx: usize) -> bool {
+  item_status(game_state, build_idx).is_unstarted()
+}
+
+fn item_status(game_state: &GameState, build_idx: usize) -> BuildOrderItemStatus {
+  game_state
+    .build_order_status
+    .get(build_idx)
+    .copied()
+    .unwrap_or(BuildOrderItemStatus::Pending)
+}
+
+pub fn set_item_status(game_state: &mut GameState, build_idx: usize, status: BuildOrderItemStatus) {
+  sync_build_order_status(game_state);
+  if let Some(item_status) = game_state.build_order_status.get_mut(build_idx) {
+    *item_status = status;
+  }
+}
+
+/// Moves `build_order_index` past every item that has already started and logs the reason
+pub fn advance_build_order(game_state: &mut GameState, reason: &str) {
   let old_index = game_state.build_order_index;
-  game_state.build_order_index += 1;
+  while game_state.build_order_index < game_state.build_order.len()
+    && !item_status(game_state, game_state.build_order_index).is_unstarted()
+  {
+    game_state.build_order_index += 1;
+  }
+  if game_state.build_order_index != old_index {
+    println!(
+      "[BUILD ORDER] {:?} -> {:?} | {}",
+      old_index, game_state.build_order_index, reason
+    );
+  }
+}
+
+/// Marks an item as started by `unit_id` and releases whatever was assigned to it
+pub fn mark_item_started(
+  game_state: &mut GameState,
+  build_idx: usize,
+  unit_id: Option<usize>,
+  reason: &str,
+) {
+  set_item_status(game_state, build_idx, BuildOrderItemStatus::Started);
+  if let Some(unit_id) = unit_id {
+    game_state.build_order_units.insert(unit_id, build_idx);
+  }
+
+  // Another larva or drone may have got there first, free them for other items
+  release_item_assignments(game_state, build_idx, reason);
+
+  println!("[BUILD ORDER] item {} started | {}", build_idx, reason);
+  advance_build_order(game_state, reason);
+}
+
+/// Frees the larvae, drones, buildings and resources assigned to an item
+fn release_item_assignments(game_state: &mut GameState, build_idx: usize, reason: &str) {
+  game_state
+    .larva_responsibilities
+    .retain(|_, idx| *idx != build_idx);
+  game_state
+    .building_assignments
+    .retain(|_, assignment| assignment.build_order_index != build_idx);
+  game_state
+    .worker_assignments
+    .retain(|_, assignment| assignment.build_order_index != Some(build_idx));
+
+  resource_reservations::release(game_state, build_idx, reason);
+}
+
+/// Marks the current item skipped without building it and moves on to the next one
+pub fn skip_current_item(game_state: &mut GameState, reason: &str) -> bool {
+  skip_item(game_state, game_state.build_order_index, reason)
+}
+
+/// Marks an unstarted item skipped, e.g. research that is already done
+pub fn skip_item(game_state: &mut GameState, build_idx: usize, reason: &str) -> bool {
+  if build_idx >= game_state.build_order.len() || !item_status(game_state, build_idx).is_unstarted()
+  {
+    return false;
+  }
+
+  set_item_status(game_state, build_idx, BuildOrderItemStatus::Skipped);
+  release_item_assignments(game_state, build_idx, reason);
+
+  println!("[BUILD ORDER] item {} skipped | {}", build_idx, reason);
+  advance_build_order(game_state, reason);
+  true
+}
+
+/// First index past every started item, only items from here on can be replaced or edited
+pub fn first_editable_index(game_state: &GameState) -> usize {
+  game_state
+    .build_order_status
+    .iter()
+    .rposition(|status| !status.is_unstarted())
+    .map_or(0, |idx| idx + 1)
+    .max(game_state.build_order_index)
+}
+
+/// Replaces every item after the last one that has started with `items`, dropping the
+/// larvae, drones, buildings and reservations assigned to the removed items
+pub fn replace_remaining_build_order(
+  game_state: &mut GameState,
+  items: Vec<BuildOrderItem>,
+  reason: &str,
+) {
+  sync_build_order_status(game_state);
+
+  // Started items past the head keep their index, only unstarted ones after them are replaced
+  let cut = first_editable_index(game_state);
+  let new_index: Vec<Option<usize>> = (0..game_state.build_order.len())
+    .map(|idx| (idx < cut).then_some(idx))
+    .collect();
+  let mut new_order = game_state.build_order[..cut].to_vec();
+  new_order.extend(items);
+  rearrange_build_order(game_state, new_order, &new_index);
+
   println!(
-    "[BUILD ORDER] {:?} -> {:?} | {}",
-    old_index, game_state.build_order_index, reason
+    "[BUILD ORDER] replaced items from {} on, {} items total | {}",
+    cut,
+    game_state.build_order.len(),
+    reason
   );
-  make_assignment_for_current_build_order_item(game, game_state);
+}
+
+/// Swaps in `items` as the build order, where `new_index[idx]` is the index item `idx` ends up
+/// at or `None` if it was removed or replaced. Kept items take their status, timings, larva,
+/// drone, building, reservation and tracking along, removed items release theirs.
+pub fn rearrange_build_order(
+  game_state: &mut GameState,
+  items: Vec<BuildOrderItem>,
+  new_index: &[Option<usize>],
+) {
+  sync_build_order_status(game_state);
+  let remap = |idx: usize| new_index.get(idx).copied().flatten();
+
+  game_state.larva_responsibilities = std::mem::take(&mut game_state.larva_responsibilities)
+    .into_iter()
+    .filter_map(|(larva_id, idx)| Some((larva_id, remap(idx)?)))
+    .collect();
+  game_state.build_order_units = std::mem::take(&mut game_state.build_order_units)
+    .into_iter()
+    .filter_map(|(unit_id, idx)| Some((unit_id, remap(idx)?)))
+    .collect();
+  game_state.building_assignments.retain(|_, assignment| {
+    let new = remap(assignment.build_order_index);
+    if let Some(new) = new {
+      assignment.build_order_index = new;
+    }
+    new.is_some()
+  });
+  game_state.worker_assignments.retain(|_, assignment| {
+    let Some(idx) = assignment.build_order_index else {
+      return true;
+    };
+    assignment.build_order_index = remap(idx);
+    assignment.build_order_index.is_some()
+  });
+  rekey_items(&mut game_state.resource_reservations, remap);
+  game_state.builder_tracking.remap_items(remap);
+  game_state.defense_placement.remap_items(remap);
+  game_state.upgrade_queue.remap_build_order(remap);
+
+  let mut status = vec![BuildOrderItemStatus::Pending; items.len()];
+  let mut timings = vec![BuildOrderItemTiming::default(); items.len()];
+  for (idx, new) in new_index.iter().enumerate() {
+    if let Some(new) = new.filter(|new| *new < items.len()) {
+      status[new] = item_status(game_state, idx);
+      timings[new] = game_state.build_order_timings[idx];
+    }
+  }
+  game_state.build_order = items;
+  game_state.build_order_status = status;
+  game_state.build_order_timings = timings;
+}
+
+/// Moves entries of a map keyed by build order index along with their items
+pub fn rekey_items<V>(items: &mut HashMap<usize, V>, new_index: impl Fn(usize) -> Option<usize>) {
+  *items = std::mem::take(items)
+    .into_iter()
+    .filter_map(|(idx, value)| Some((new_index(idx)?, value)))
+    .collect();
+}
+
+/// Puts items that failed last frame back to pending and moves the build order back to the
+/// first of them, so a unit that died before finishing is made again
+fn retry_failed_items(game_state: &mut GameState) {
+  let failed: Vec<usize> = game_state
+    .build_order_status
+    .iter()
+    .enumerate()
+    .filter(|(_, status)| **status == BuildOrderItemStatus::Failed)
+    .map(|(idx, _)| idx)
+    .collect();
+
+  for &build_idx in &failed {
+    set_item_status(game_state, build_idx, BuildOrderItemStatus::Pending);
+    // Keeps the failed frame, the retry gets its own assigned and started frames
+    if let Some(timing) = game_state.build_order_timings.get_mut(build_idx) {
+      timing.assigned_frame = None;
+      timing.started_frame = None;
+    }
+    println!("[BUILD ORDER] item {} failed, retrying", build_idx);
+  }
+
+  if let Some(&first) = failed.first() {
+    if first < game_state.build_order_index {
+      println!(
+        "[BUILD ORDER] {:?} -> {:?} | retrying failed item",
+        game_state.build_order_index, first
+      );
+      game_state.build_order_index = first;
+    }
+  }
+}
+
+/// Marks started items completed once their unit finishes, or failed if it died first
+fn update_build_order_progress(game: &Game, game_state: &mut GameState) {
+  retry_failed_items(game_state);
+
+  let started_units: Vec<(usize, usize)> = game_state
+    .build_order_units
+    .iter()
+    .map(|(&unit_id, &build_idx)| (unit_id, build_idx))
+    .collect();
+
+  for (unit_id, build_idx) in started_units {
+    let unit = game.get_unit(unit_id).filter(|u| u.exists());
+    let status = match (game_state.build_order.get(build_idx), unit) {
+      (_, None) => BuildOrderItemStatus::Failed,
+      (Some(BuildOrderItem::Upgrade { .. }), Some(building)) if !building.is_upgrading() => {
+        BuildOrderItemStatus::Completed
+      }
+      (Some(BuildOrderItem::Research { .. }), Some(building)) if !building.is_researching() => {
+        BuildOrderItemStatus::Completed
+      }
+      (Some(BuildOrderItem::Unit { unit_type, .. }), Some(u))
+        if u.get_type() == *unit_type && u.is_completed() && !u.is_morphing() =>
+      {
+        BuildOrderItemStatus::Completed
+      }
+      _ => continue,
+    };
+
+    println!(
+      "[BUILD ORDER] item {} {:?} (unit {})",
+      build_idx, status, unit_id
+    );
+    set_item_status(game_state, build_idx, status);
+    game_state.build_order_units.remove(&unit_id);
+  }
+
+  resource_reservations::release_stale(game_state);
+}
+
+pub fn item_cost(player: &Player, item: &BuildOrderItem) -> (i32, i32) {
+  match item {
+    BuildOrderItem::Unit { unit_type, .. } => (unit_type.mineral_price(), unit_type.gas_price()),
+    BuildOrderItem::Upgrade { upgrade_type, .. } => {
+      let level = researching_stuff::next_upgrade_level(player, *upgrade_type);
+      (
+        upgrade_type.mineral_price(level),
+        upgrade_type.gas_price(level),
+      )
+    }
+    BuildOrderItem::Research { tech_type, .. } => {
+      (tech_type.mineral_price(), tech_type.gas_price())
+    }
+    BuildOrderItem::Squad { .. } => (0, 0),
+  }
+}
+
+/// Unstarted, its trigger holds and the tech it needs is done
+pub fn is_ready(game: &Game, game_state: &GameState, build_idx: usize) -> bool {
+  let Some(item) = game_state.build_order.get(build_idx) else {
+    return false;
+  };
+  item_status(game_state, build_idx).is_unstarted()
+    && item
+      .trigger()
+      .is_none_or(|trigger| trigger.is_satisfied(game, game_state))
+    && prerequisites_met(game, item)
+}
+
+/// Morphed buildings still count for what they were, a Lair can make anything a Hatchery can
+pub fn satisfies_requirement(owned: UnitType, required: UnitType) -> bool {
+  owned == required
+    || match required {
+      UnitType::Zerg_Hatchery => owned == UnitType::Zerg_Lair || owned == UnitType::Zerg_Hive,
+      UnitType::Zerg_Lair => owned == UnitType::Zerg_Hive,
+      UnitType::Zerg_Spire => owned == UnitType::Zerg_Greater_Spire,
+      _ => false,
+    }
+}
+
+/// Whether the buildings an item needs are finished, so later items don't grab larvae
+/// or drones they can't use yet
+pub fn prerequisites_met(game: &Game, item: &BuildOrderItem) -> bool {
+  let Some(player) = game.self_() else {
+    return false;
+  };
+
+  let required: Vec<UnitType> = match item {
+    BuildOrderItem::Unit { unit_type, .. } => unit_type
+      .required_units()
+      .iter()
+      .map(|(required_type, _)| *required_type)
+      .filter(|required_type| {
+        *required_type != UnitType::Zerg_Larva && *required_type != UnitType::Zerg_Drone
+      })
+      .collect(),
+    BuildOrderItem::Upgrade { upgrade_type, .. } => {
+      // One level at a time, and later levels need a Lair or Hive
+      if player.is_upgrading(*upgrade_type) {
+        return false;
+      }
+      let level = researching_stuff::next_upgrade_level(&player, *upgrade_type);
+      vec![
+        upgrade_type.what_upgrades(),
+        upgrade_type.whats_required(level),
+      ]
+    }
+    BuildOrderItem::Research { tech_type, .. } => {
+      vec![tech_type.what_researches(), tech_type.required_unit()]
+    }
+    BuildOrderItem::Squad { .. } => vec![],
+  };
+  let required: Vec<UnitType> = required
+    .into_iter()
+    .filter(|required_type| *required_type != UnitType::None)
+    .collect();
+
+  if required.is_empty() {
+    return true;
+  }
+
+  let completed_types: Vec<UnitType> = game
+    .get_all_units()
+    .into_iter()
+    .filter(|u| u.get_player().get_id() == player.get_id() && u.is_completed())
+    .map(|u| u.get_type())
+    .collect();
+
+  required.iter().all(|required_type| {
+    completed_types
+      .iter()
+      .any(|owned| satisfies_requirement(*owned, *required_type))
+  })
 }
 
 pub fn build_order_on_unit_started(game: &Game, completed_unit: &Unit, game_state: &mut GameState) {
@@ -30,147 +396,281 @@ pub fn build_order_on_unit_started(game: &Game, completed_unit: &Unit, game_stat
     return;
   }
 
-  let Some(current_build_order_item) = game_state
-    .build_order
-    .get(game_state.build_order_index)
-    .cloned()
-  else {
+  let head = game_state.build_order_index;
+  let Some(current_build_order_item) = game_state.build_order.get(head).cloned() else {
     println!("Build order empty in build_order_on_unit_create");
     return;
   };
 
   match current_build_order_item {
     BuildOrderItem::Unit { unit_type, .. } => {
-      if completed_unit.get_type() == unit_type {
-        advance_build_order(game, game_state, &format!("Unit {:?} created", unit_type));
+      if completed_unit.get_type() == unit_type && item_status(game_state, head).is_unstarted() {
+        mark_item_started(
+          game_state,
+          head,
+          Some(completed_unit.get_id()),
+          &format!("Unit {:?} created", unit_type),
+        );
       }
     }
-    BuildOrderItem::Upgrade(_) => {}
+    BuildOrderItem::Upgrade { .. } | BuildOrderItem::Research { .. } => {}
     BuildOrderItem::Squad { .. } => {
       // Squads are created manually, not through unit creation
     }
   }
 }
 
-pub fn make_assignment_for_current_build_order_item(game: &Game, game_state: &mut GameState) {
+/// Called when an egg or a building starts morphing into `morph_type`. `assigned_idx` is the
+/// item the larva, drone or building was assigned to, if any.
+pub fn build_order_on_unit_morph(
+  game_state: &mut GameState,
+  unit_id: usize,
+  morph_type: UnitType,
+  assigned_idx: Option<usize>,
+  reason: &str,
+) {
+  let matches_unit = |game_state: &GameState, idx: usize| {
+    item_status(game_state, idx).is_unstarted()
+      && matches!(
+        game_state.build_order.get(idx),
+        Some(BuildOrderItem::Unit { unit_type, .. }) if *unit_type == morph_type
+      )
+  };
+
+  // Fall back to the first matching item in the window for morphs we did not order, leaving
+  // items another larva or drone is already on to that one
+  let build_idx = assigned_idx
+    .filter(|&idx| matches_unit(game_state, idx))
+    .or_else(|| {
+      build_order_window_indices(game_state)
+        .find(|&idx| matches_unit(game_state, idx) && !has_assignment(game_state, idx))
+    });
+
+  match build_idx {
+    Some(build_idx) => mark_item_started(game_state, build_idx, Some(unit_id), reason),
+    None => println!(
+      "{}, but no build order item in flight for it (current item: {:?})",
+      reason,
+      game_state.build_order.get(game_state.build_order_index)
+    ),
+  }
+}
+
+/// Whether a larva, drone or building is working on the item
+fn has_assignment(game_state: &GameState, build_idx: usize) -> bool {
+  game_state
+    .larva_responsibilities
+    .values()
+    .any(|idx| *idx == build_idx)
+    || game_state
+      .building_assignments
+      .values()
+      .any(|assignment| assignment.build_order_index == build_idx)
+    || game_state
+      .worker_assignments
+      .values()
+      .any(|assignment| assignment.build_order_index == Some(build_idx))
+}
+
+pub fn make_assignment_for_build_order_item(
+  game: &Game,
+  game_state: &mut GameState,
+  build_idx: usize,
+) {
   let Some(player) = game.self_() else {
-    println!("Failed to get self player in make_assignment_for_current_build_order_item");
+    println!("Failed to get self player in make_assignment_for_build_order_item");
     return;
   };
 
-  if game_state.build_order_index >= game_state.build_order.len() {
-    println!("build order empty in make_assignment_for_current_build_order_item");
+  let Some(thing_to_build) = game_state.build_order.get(build_idx).cloned() else {
+    println!(
+      "build order index {} out of range in make_assignment_for_build_order_item",
+      build_idx
+    );
+    return;
+  };
+
+  if !is_ready(game, game_state, build_idx) {
     return;
   }
-  let thing_to_build = game_state.build_order[game_state.build_order_index].clone();
 
-  match thing_to_build {
+  let reservation_kind = match thing_to_build {
     BuildOrderItem::Unit {
       unit_type: unit_to_build,
       ..
     } => {
       if unit_to_build.is_building() {
-        structure_stuff::make_building_assignment(game, game_state, unit_to_build);
+        structure_stuff::make_building_assignment(game, game_state, unit_to_build, build_idx);
+        ReservationKind::Structure
       } else {
-        creature_stuff::assign_larva_to_build_current_index(game, game_state, &player);
+        creature_stuff::assign_larva_to_build_index(game, game_state, &player, build_idx);
+        ReservationKind::Larva
       }
     }
-    BuildOrderItem::Upgrade(upgrade) => {
-      researching_stuff::assign_building_to_research_upgrade(game, game_state, &player, upgrade);
+    BuildOrderItem::Upgrade { .. } | BuildOrderItem::Research { .. } => {
+      upgrade_queue::enqueue_build_order_item(game, game_state, build_idx);
+      return;
     }
-    BuildOrderItem::Squad { name, role, status } => {
-      let Some(self_player) = game.self_() else {
-        println!("Failed to get self player in make_assignment_for_current_build_order_item when creating squad");
-        return;
-      };
-
+    BuildOrderItem::Squad {
+      name, role, status, ..
+    } => {
       let new_squad =
-        military_management::create_squad(game, &name, role, status, &self_player, game_state);
+        military_management::create_squad(game, &name, role, status, &player, game_state);
       game_state.military_squads.push(new_squad);
-      advance_build_order(game, game_state, &format!("Squad {} created", name));
+      mark_item_started(
+        game_state,
+        build_idx,
+        None,
+        &format!("Squad {} created", name),
+      );
+      set_item_status(game_state, build_idx, BuildOrderItemStatus::Completed);
+      return;
     }
+  };
+
+  if has_assignment(game_state, build_idx) {
+    set_item_status(game_state, build_idx, BuildOrderItemStatus::Assigned);
+    resource_reservations::reserve(game, game_state, build_idx, reservation_kind);
   }
 }
 
 pub fn build_order_enforce_assignments(game: &Game, game_state: &mut GameState) {
-  let Some(player) = game.self_() else {
-    println!("Failed to get self player in make_assignment_for_current_build_order_item");
+  if game.self_().is_none() {
+    println!("Failed to get self player in build_order_enforce_assignments");
     return;
-  };
+  }
+
+  sync_build_order_status(game_state);
+  update_build_order_progress(game, game_state);
+
   if game_state.build_order_index >= game_state.build_order.len() {
     // println!("nothing to build");
-    figure_out_what_to_build(game, game_state);
+    macro_planner::plan(game, game_state);
+    sync_build_order_status(game_state);
     return;
   }
 
-  let thing_to_build = game_state.build_order[game_state.build_order_index].clone();
+  for build_idx in build_order_window_indices(game_state) {
+    let thing_to_build = game_state.build_order[build_idx].clone();
+    let is_head = build_idx == game_state.build_order_index;
 
-  match thing_to_build {
-    BuildOrderItem::Unit { unit_type, .. } => {
-      if unit_type.is_building() {
-        structure_stuff::enforce_structure_assignment(game, game_state);
-      } else {
-        enforce_larvae_assignment(game, game_state);
-      }
+    // New units join the newest squad, so nothing after a squad may start before it exists
+    if matches!(thing_to_build, BuildOrderItem::Squad { .. }) && !is_head {
+      break;
     }
-    BuildOrderItem::Upgrade(upgrade_type) => {
-      researching_stuff::enforce_research_assignment(game, game_state, &player, upgrade_type);
+
+    if !item_status(game_state, build_idx).is_unstarted() {
+      continue;
     }
-    BuildOrderItem::Squad { .. } => {
-      // Squads are created immediately when assigned, nothing to enforce
+
+    if let Some(trigger) = thing_to_build.trigger() {
+      if !trigger.is_satisfied(game, game_state) {
+        if is_head {
+          game.draw_text_screen(
+            (0, 30),
+            &format!("Build order index {} waiting for: {}", build_idx, trigger),
+          );
+        }
+        continue;
+      }
+    }
+
+    match thing_to_build {
+      BuildOrderItem::Unit { unit_type, .. } => {
+        if !prerequisites_met(game, &thing_to_build) {
+          continue;
+        }
+        if unit_type.is_building() {
+          structure_stuff::enforce_structure_assignment(game, game_state, build_idx);
+        } else {
+          enforce_larvae_assignment(game, game_state, build_idx);
+        }
+      }
+      BuildOrderItem::Upgrade { .. } | BuildOrderItem::Research { .. } => {
+        // The queue waits for the building and money, so later items aren't held up
+        upgrade_queue::enqueue_build_order_item(game, game_state, build_idx);
+      }
+      BuildOrderItem::Squad { .. } => {
+        // Squads are created as soon as they are assigned, the window moves on next frame
+        make_assignment_for_build_order_item(game, game_state, build_idx);
+        break;
+      }
     }
   }
 }
 
-fn enforce_larvae_assignment(game: &Game, game_state: &mut GameState) {
-  let larvae_assigned_for_current_index =
+fn enforce_larvae_assignment(game: &Game, game_state: &mut GameState, build_idx: usize) {
+  let larvae_assigned_for_index =
     game_state
       .larva_responsibilities
       .iter()
-      .find_map(|(&larvae_id, build_idx)| {
-        if *build_idx == game_state.build_order_index {
-          let maybe_larvae = game.get_unit(larvae_id);
-
-          let Some(l) = maybe_larvae else {
-            return None;
-          };
-          Some(l)
+      .find_map(|(&larvae_id, idx)| {
+        if *idx == build_idx {
+          game.get_unit(larvae_id)
         } else {
           None
         }
       });
 
-  let Some(larvae) = larvae_assigned_for_current_index else {
+  let Some(larvae) = larvae_assigned_for_index else {
     game.draw_text_screen(
       (0, 30),
       format!(
-        "No larvae for current build order index {}, trying to assign",
-        game_state.build_order_index
+        "No larvae for build order index {}, trying to assign",
+        build_idx
       )
       .as_str(),
     );
-    make_assignment_for_current_build_order_item(game, game_state);
+    make_assignment_for_build_order_item(game, game_state, build_idx);
     return;
   };
 
-  let type_to_morph = match game_state.build_order[game_state.build_order_index] {
+  let type_to_morph = match game_state.build_order[build_idx] {
     BuildOrderItem::Unit { unit_type, .. } => unit_type,
     _ => {
-      println!("Expected unit to build in build_order_on_frame, found upgrade instead");
+      println!("Expected unit to build in enforce_larvae_assignment, found something else instead");
       return;
     }
   };
 
+  if !resource_reservations::can_spend(game, game_state, build_idx) {
+    return;
+  }
+
   if let Err(e) = larvae.morph(type_to_morph) {
     game.draw_text_screen((0, 10), format!("Failed to morph larvae: {:?}", e).as_str());
   }
 }
 
-pub fn remove_drone_assignment_after_started_buidling(
-  old_drone_now_building_unit: &Unit,
-  game_state: &mut GameState,
-) {
-  let drone_id = old_drone_now_building_unit.get_id();
+/// Drone whose building assignment `building` came from. Drones keep their id when they become
+/// a building, but an extractor morphs under the geyser's id while the drone is destroyed, so
+/// it is matched by the site the drone was sent to.
+pub fn builder_of(
+  game_state: &GameState,
+  building_id: usize,
+  building_type: UnitType,
+  tile: TilePosition,
+) -> Option<usize> {
+  if game_state.worker_assignments.contains_key(&building_id) {
+    return Some(building_id);
+  }
+  game_state
+    .worker_assignments
+    .iter()
+    .find(|(_, assignment)| {
+      assignment.assignment_type == WorkerAssignmentType::Building
+        && assignment.target_position == Some((tile.x, tile.y))
+        && assignment.build_order_index.is_some_and(|idx| {
+          matches!(
+            game_state.build_order.get(idx),
+            Some(BuildOrderItem::Unit { unit_type, .. }) if *unit_type == building_type
+          )
+        })
+    })
+    .map(|(drone_id, _)| *drone_id)
+}
+
+pub fn remove_drone_assignment_after_started_buidling(drone_id: usize, game_state: &mut GameState) {
   if game_state.worker_assignments.remove(&drone_id).is_some() {
     println!(
       "Removed drone {} building assignment after starting construction",
@@ -184,129 +684,53 @@ pub fn remove_drone_assignment_after_started_buidling(
   }
 }
 
-/// Returns a list of upgrades that would benefit the given unit type
-fn get_relevant_upgrades_for_unit(unit_type: UnitType) -> Vec<UpgradeType> {
-  let mut upgrades = Vec::new();
-  
-  // Check if unit is a flyer
-  if unit_type.is_flyer() {
-    upgrades.push(UpgradeType::Zerg_Flyer_Attacks);
-    upgrades.push(UpgradeType::Zerg_Flyer_Carapace);
-  }
-  
-  // Check weapon types for ground units
-  let ground_weapon = unit_type.ground_weapon();
-  let air_weapon = unit_type.air_weapon();
-  
-  // For non-flyers with weapons
-  if !unit_type.is_flyer() {
-    // Check for melee attacks (range <= 32 pixels, which is close range)
-    if ground_weapon != WeaponType::None && ground_weapon.max_range() <= 32 {
-      upgrades.push(UpgradeType::Zerg_Melee_Attacks);
-    }
-    
-    // Check for ranged attacks
-    if ground_weapon != WeaponType::None && ground_weapon.max_range() > 32 {
-      upgrades.push(UpgradeType::Zerg_Missile_Attacks);
-    }
-    
-    // Also check air weapon for ranged
-    if air_weapon != WeaponType::None {
-      upgrades.push(UpgradeType::Zerg_Missile_Attacks);
-    }
-    
-    // Add carapace for all non-flying combat units
-    if ground_weapon != WeaponType::None || air_weapon != WeaponType::None {
-      upgrades.push(UpgradeType::Zerg_Carapace);
-    }
-  }
-  
-  upgrades
-}
-
-fn figure_out_what_to_build(game: &Game, game_state: &mut GameState) {
-  let Some(player) = game.self_() else {
-    println!("Failed to get self player in figure_out_what_to_build");
-    return;
-  };
-  let supply_total = player.supply_total() / 2;
-  let supply_used = player.supply_used() / 2;
-  let supply_remaining = supply_total - supply_used;
-
-  let overlords_in_production = game
-    .get_all_units()
-    .into_iter()
-    .filter(|u| {
-      u.get_type() == UnitType::Zerg_Egg
-        && u.get_player().get_id() == player.get_id()
-        && u.get_build_type() == UnitType::Zerg_Overlord
-    })
-    .count();
-
-  // Queue overlord if supply-blocked or nearly blocked, accounting for overlords already building
-  let should_build_overlord = match overlords_in_production {
-    0 => supply_remaining < 5,
-    3 => supply_remaining < 3,
-    4 => supply_remaining < 2,
-    _ => supply_remaining == 2,
-  };
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::game_state::WorkerAssignment;
 
-  if should_build_overlord {
-    println!(
-      "queuing overlord because supply is {} and overlords in production is {}",
-      supply_remaining, overlords_in_production
-    );
+  #[test]
+  fn extractor_morph_is_matched_to_the_drone_sent_to_its_geyser() {
+    let mut game_state = GameState {
+      build_order: vec![
+        BuildOrderItem::unit(UnitType::Zerg_Drone),
+        BuildOrderItem::unit(UnitType::Zerg_Extractor),
+      ],
+      ..Default::default()
+    };
+    sync_build_order_status(&mut game_state);
+    set_item_status(&mut game_state, 1, BuildOrderItemStatus::Assigned);
+    let (drone_id, geyser_id) = (7, 42);
     game_state
-      .build_order
-      .push(BuildOrderItem::unit(UnitType::Zerg_Overlord));
-    return;
-  }
+      .worker_assignments
+      .insert(drone_id, WorkerAssignment::building(None, (20, 30), 1));
 
-  let total_drones = game
-    .get_all_units()
-    .into_iter()
-    .filter(|u| u.get_type() == UnitType::Zerg_Drone && u.get_player().get_id() == player.get_id())
-    .count();
-
-  if total_drones < 30 {
-    // Check if the last item was a drone
-    let last_was_drone = game_state
-      .build_order
-      .last()
-      .map(|item| {
-        if let BuildOrderItem::Unit { unit_type, .. } = item {
-          *unit_type == UnitType::Zerg_Drone
-        } else {
-          false
-        }
-      })
-      .unwrap_or(false);
-    
-    if !last_was_drone {
-      println!("queuing drone because total drones is {}", total_drones);
-      game_state
-        .build_order
-        .push(BuildOrderItem::unit(UnitType::Zerg_Drone));
-      return;
-    }
-  }
-  // Build the last unit type from the build order (excluding overlords, drones, and buildings)
-  let last_unit = game_state
-    .build_order
-    .iter()
-    .rev()
-    .find_map(|item| {
-      if let BuildOrderItem::Unit { unit_type, .. } = item {
-        if *unit_type != UnitType::Zerg_Overlord 
-           && *unit_type != UnitType::Zerg_Drone 
-           && !unit_type.is_building() {
-          return Some(*unit_type);
-        }
-      }
+    let builder = builder_of(
+      &game_state,
+      geyser_id,
+      UnitType::Zerg_Extractor,
+      TilePosition::new(20, 30),
+    );
+    assert_eq!(builder, Some(drone_id));
+    assert_eq!(
+      builder_of(
+        &game_state,
+        geyser_id,
+        UnitType::Zerg_Hatchery,
+        TilePosition::new(20, 30)
+      ),
       None
-    })
-    .unwrap_or(UnitType::Zerg_Zergling);
+    );
 
-  println!("queuing {:?}", last_unit);
-  game_state.build_order.push(BuildOrderItem::unit(last_unit));
+    remove_drone_assignment_after_started_buidling(drone_id, &mut game_state);
+    build_order_on_unit_morph(
+      &mut game_state,
+      geyser_id,
+      UnitType::Zerg_Extractor,
+      Some(1),
+      "Building Zerg_Extractor started construction",
+    );
+    assert_eq!(item_status(&game_state, 1), BuildOrderItemStatus::Started);
+    assert!(game_state.worker_assignments.is_empty());
+  }
 }
@@ -0,0 +1,178 @@
+use rsbwapi::*;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::utils::build_orders::build_order_item::BuildOrderBenchmark;
+use crate::utils::game_state::{BuildOrderItemStatus, BuildOrderItemTiming, GameState};
+
+const TELEMETRY_DIR_ENV: &str = "RUSTBOT_TELEMETRY_DIR";
+const DEFAULT_TELEMETRY_DIR: &str = "telemetry";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildOrderItemReport {
+  pub index: usize,
+  pub item: String,
+  pub status: BuildOrderItemStatus,
+  #[serde(flatten)]
+  pub timing: BuildOrderItemTiming,
+  pub benchmark: Option<BuildOrderBenchmark>,
+  /// Frames behind the benchmark, negative when ahead. Counts up from the current frame while
+  /// an item is late and has not got there yet.
+  pub assigned_delta: Option<i32>,
+  pub started_delta: Option<i32>,
+  pub completed_delta: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildOrderTimingReport {
+  pub build_order_name: String,
+  pub map_file_name: String,
+  pub enemy_race: String,
+  pub frame_count: i32,
+  pub is_winner: Option<bool>,
+  /// Largest `started_delta` of any item
+  pub worst_started_delta: Option<i32>,
+  pub items: Vec<BuildOrderItemReport>,
+}
+
+impl BuildOrderTimingReport {
+  pub fn empty() -> Self {
+    Self {
+      build_order_name: String::new(),
+      map_file_name: String::new(),
+      enemy_race: String::new(),
+      frame_count: -1,
+      is_winner: None,
+      worst_started_delta: None,
+      items: Vec::new(),
+    }
+  }
+}
+
+/// Stamps the current frame on items that reached a new status since the last frame
+pub fn record_timings(game: &Game, game_state: &mut GameState) {
+  let frame = game.get_frame_count();
+  let GameState {
+    build_order_status,
+    build_order_timings,
+    ..
+  } = game_state;
+
+  for (status, timing) in build_order_status
+    .iter()
+    .zip(build_order_timings.iter_mut())
+  {
+    // An item can skip a status within one frame, e.g. a squad is created as soon as it is assigned
+    let (assigned, started, completed, failed) = match status {
+      BuildOrderItemStatus::Pending => (false, false, false, false),
+      BuildOrderItemStatus::Assigned => (true, false, false, false),
+      BuildOrderItemStatus::Started => (true, true, false, false),
+      BuildOrderItemStatus::Completed => (true, true, true, false),
+      BuildOrderItemStatus::Failed => (true, true, false, true),
+      BuildOrderItemStatus::Skipped => (false, false, false, false),
+    };
+    if assigned && timing.assigned_frame.is_none() {
+      timing.assigned_frame = Some(frame);
+    }
+    if started && timing.started_frame.is_none() {
+      timing.started_frame = Some(frame);
+    }
+    if completed && timing.completed_frame.is_none() {
+      timing.completed_frame = Some(frame);
+    }
+    if failed && timing.failed_frame.is_none() {
+      timing.failed_frame = Some(frame);
+    }
+  }
+}
+
+fn benchmark_delta(actual: Option<i32>, benchmark: Option<i32>, frame: i32) -> Option<i32> {
+  match (actual, benchmark) {
+    (Some(actual), Some(benchmark)) => Some(actual - benchmark),
+    (None, Some(benchmark)) if frame > benchmark => Some(frame - benchmark),
+    _ => None,
+  }
+}
+
+pub fn build_report(
+  game: &Game,
+  game_state: &GameState,
+  is_winner: Option<bool>,
+) -> BuildOrderTimingReport {
+  let frame = game.get_frame_count();
+
+  let items: Vec<BuildOrderItemReport> = game_state
+    .build_order
+    .iter()
+    .enumerate()
+    .map(|(index, item)| {
+      let timing = game_state
+        .build_order_timings
+        .get(index)
+        .copied()
+        .unwrap_or_default();
+      let benchmark = item.benchmark().copied();
+      let delta = |actual: Option<i32>, pick: fn(&BuildOrderBenchmark) -> Option<i32>| {
+        benchmark_delta(actual, benchmark.as_ref().and_then(pick), frame)
+      };
+
+      BuildOrderItemReport {
+        index,
+        item: item.describe(),
+        status: game_state
+          .build_order_status
+          .get(index)
+          .copied()
+          .unwrap_or(BuildOrderItemStatus::Pending),
+        timing,
+        benchmark,
+        assigned_delta: delta(timing.assigned_frame, |b| b.assigned),
+        started_delta: delta(timing.started_frame, |b| b.started),
+        completed_delta: delta(timing.completed_frame, |b| b.completed),
+      }
+    })
+    .collect();
+
+  BuildOrderTimingReport {
+    build_order_name: game_state.build_order_name.clone(),
+    map_file_name: game.map_file_name(),
+    enemy_race: game
+      .enemy()
+      .map(|enemy| format!("{:?}", enemy.get_race()))
+      .unwrap_or_default(),
+    frame_count: frame,
+    is_winner,
+    worst_started_delta: items.iter().filter_map(|item| item.started_delta).max(),
+    items,
+  }
+}
+
+/// Directory reports are written to, `RUSTBOT_TELEMETRY_DIR` or `./telemetry`
+pub fn telemetry_dir() -> PathBuf {
+  match std::env::var(TELEMETRY_DIR_ENV) {
+    Ok(dir) => PathBuf::from(dir),
+    Err(_) => PathBuf::from(DEFAULT_TELEMETRY_DIR),
+  }
+}
+
+/// Writes the report as `<unix time>_<build order>.json` in the telemetry directory
+pub fn export_report(report: &BuildOrderTimingReport) -> std::io::Result<PathBuf> {
+  let dir = telemetry_dir();
+  std::fs::create_dir_all(&dir)?;
+
+  let timestamp = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0);
+  let safe_name: String = report
+    .build_order_name
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+    .collect();
+  let path = dir.join(format!("{}_{}.json", timestamp, safe_name));
+
+  let json = serde_json::to_string_pretty(report).map_err(std::io::Error::other)?;
+  std::fs::write(&path, json)?;
+  Ok(path)
+}
@@ -1,19 +1,47 @@
 use rsbwapi::*;
+use serde::de::value::{Error as ValueError, StrDeserializer};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
+use crate::utils::build_orders::build_order_trigger::BuildOrderTrigger;
+use crate::utils::build_orders::type_names;
 use crate::utils::military::squad_models;
 
+/// Frames a reference run hit for an item, to compare the bot's timings against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildOrderBenchmark {
+  pub assigned: Option<i32>,
+  pub started: Option<i32>,
+  pub completed: Option<i32>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BuildOrderItem {
   Unit {
     unit_type: UnitType,
     base_index: Option<usize>,
+    trigger: Option<BuildOrderTrigger>,
+    benchmark: Option<BuildOrderBenchmark>,
+  },
+  /// Researches the next level of the upgrade, list it again for each further level
+  Upgrade {
+    upgrade_type: UpgradeType,
+    trigger: Option<BuildOrderTrigger>,
+    benchmark: Option<BuildOrderBenchmark>,
+  },
+  Research {
+    tech_type: TechType,
+    trigger: Option<BuildOrderTrigger>,
+    benchmark: Option<BuildOrderBenchmark>,
   },
-  Upgrade(UpgradeType),
   Squad {
     name: String,
     role: squad_models::SquadRole,
     status: squad_models::SquadStatus,
+    trigger: Option<BuildOrderTrigger>,
+    benchmark: Option<BuildOrderBenchmark>,
   },
 }
 
@@ -22,6 +50,8 @@ impl BuildOrderItem {
     BuildOrderItem::Unit {
       unit_type,
       base_index: None,
+      trigger: None,
+      benchmark: None,
     }
   }
 
@@ -29,11 +59,94 @@ impl BuildOrderItem {
     BuildOrderItem::Unit {
       unit_type,
       base_index: Some(base_index),
+      trigger: None,
+      benchmark: None,
+    }
+  }
+
+  pub fn upgrade(upgrade_type: UpgradeType) -> Self {
+    BuildOrderItem::Upgrade {
+      upgrade_type,
+      trigger: None,
+      benchmark: None,
+    }
+  }
+
+  pub fn research(tech_type: TechType) -> Self {
+    BuildOrderItem::Research {
+      tech_type,
+      trigger: None,
+      benchmark: None,
     }
   }
 
   pub fn squad(name: String, role: squad_models::SquadRole, status: squad_models::SquadStatus) -> Self {
-    BuildOrderItem::Squad { name, role, status }
+    BuildOrderItem::Squad {
+      name,
+      role,
+      status,
+      trigger: None,
+      benchmark: None,
+    }
+  }
+
+  /// Makes the item wait until `trigger` holds before it is assigned
+  pub fn when(mut self, new_trigger: BuildOrderTrigger) -> Self {
+    match &mut self {
+      BuildOrderItem::Unit { trigger, .. }
+      | BuildOrderItem::Upgrade { trigger, .. }
+      | BuildOrderItem::Research { trigger, .. }
+      | BuildOrderItem::Squad { trigger, .. } => *trigger = Some(new_trigger),
+    }
+    self
+  }
+
+  pub fn trigger(&self) -> Option<&BuildOrderTrigger> {
+    match self {
+      BuildOrderItem::Unit { trigger, .. }
+      | BuildOrderItem::Upgrade { trigger, .. }
+      | BuildOrderItem::Research { trigger, .. }
+      | BuildOrderItem::Squad { trigger, .. } => trigger.as_ref(),
+    }
+  }
+
+  /// Attaches benchmark frames, only kept by the structured and DTO forms
+  pub fn with_benchmark(mut self, new_benchmark: BuildOrderBenchmark) -> Self {
+    match &mut self {
+      BuildOrderItem::Unit { benchmark, .. }
+      | BuildOrderItem::Upgrade { benchmark, .. }
+      | BuildOrderItem::Research { benchmark, .. }
+      | BuildOrderItem::Squad { benchmark, .. } => *benchmark = Some(new_benchmark),
+    }
+    self
+  }
+
+  pub fn benchmark(&self) -> Option<&BuildOrderBenchmark> {
+    match self {
+      BuildOrderItem::Unit { benchmark, .. }
+      | BuildOrderItem::Upgrade { benchmark, .. }
+      | BuildOrderItem::Research { benchmark, .. }
+      | BuildOrderItem::Squad { benchmark, .. } => benchmark.as_ref(),
+    }
+  }
+
+  /// Item text without the trigger, e.g. `Zerg_Hatchery @base1`
+  pub fn describe(&self) -> String {
+    match self {
+      BuildOrderItem::Unit {
+        unit_type,
+        base_index,
+        ..
+      } => match base_index {
+        Some(idx) => format!("{:?} @base{}", unit_type, idx),
+        None => format!("{:?}", unit_type),
+      },
+      BuildOrderItem::Upgrade { upgrade_type, .. } => format!("{:?}", upgrade_type),
+      BuildOrderItem::Research { tech_type, .. } => format!("{:?}", tech_type),
+      BuildOrderItem::Squad {
+        name, role, status, ..
+      } => format!("Squad({}, {:?}, {:?})", name, role, status),
+    }
   }
 }
 
@@ -42,36 +155,222 @@ impl Serialize for BuildOrderItem {
   where
     S: serde::Serializer,
   {
+    match self.trigger() {
+      Some(trigger) => serializer.serialize_str(&format!("{} if {}", self.describe(), trigger)),
+      None => serializer.serialize_str(&self.describe()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildOrderItemParseError {
+  Empty,
+  UnknownUnitType(String),
+  UnknownUpgradeType(String),
+  UnknownTechType(String),
+  UnknownItem(String),
+  InvalidBaseIndex(String),
+  InvalidSquad(String),
+  UnknownSquadRole(String),
+  UnknownSquadStatus(String),
+  InvalidTrigger(String),
+}
+
+impl fmt::Display for BuildOrderItemParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
-      BuildOrderItem::Unit {
-        unit_type,
-        base_index,
-      } => {
-        let base_str = match base_index {
-          Some(idx) => format!(" @base{}", idx),
-          None => String::new(),
-        };
-        serializer.serialize_str(&format!("{:?}{}", unit_type, base_str))
+      BuildOrderItemParseError::Empty => write!(f, "empty build order item"),
+      BuildOrderItemParseError::UnknownUnitType(name) => write!(f, "unknown unit type `{}`", name),
+      BuildOrderItemParseError::UnknownUpgradeType(name) => {
+        write!(f, "unknown upgrade type `{}`", name)
+      }
+      BuildOrderItemParseError::UnknownTechType(name) => write!(f, "unknown tech type `{}`", name),
+      BuildOrderItemParseError::UnknownItem(name) => {
+        write!(f, "`{}` is not a known unit, upgrade or tech type", name)
+      }
+      BuildOrderItemParseError::InvalidBaseIndex(base) => {
+        write!(f, "invalid base index `{}`, expected `@base<number>`", base)
       }
-      BuildOrderItem::Upgrade(upgrade_type) => {
-        serializer.serialize_str(&format!("{:?}", upgrade_type))
+      BuildOrderItemParseError::InvalidSquad(squad) => {
+        write!(
+          f,
+          "invalid squad `{}`, expected `Squad(name, Role, Status)`",
+          squad
+        )
       }
-      BuildOrderItem::Squad { name, role, status } => {
-        serializer.serialize_str(&format!("Squad({}, {:?}, {:?})", name, role, status))
+      BuildOrderItemParseError::UnknownSquadRole(role) => {
+        write!(f, "unknown squad role `{}`", role)
+      }
+      BuildOrderItemParseError::UnknownSquadStatus(status) => {
+        write!(f, "unknown squad status `{}`", status)
+      }
+      BuildOrderItemParseError::InvalidTrigger(trigger) => {
+        write!(
+          f,
+          "invalid trigger `{}`, expected e.g. `supply >= 9` or `completed Zerg_Spawning_Pool`",
+          trigger
+        )
       }
     }
   }
 }
 
+impl std::error::Error for BuildOrderItemParseError {}
+
+/// Parses the text written by `Serialize`: `Zerg_Drone`, `Zerg_Hatchery @base1`,
+/// `Metabolic_Boost`, `Lurker_Aspect` or `Squad(name, Role, Status)`, optionally followed by
+/// ` if <trigger>`
+impl FromStr for BuildOrderItem {
+  type Err = BuildOrderItemParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let s = s.trim();
+    // Squad names may contain " if ", so only look after the closing paren
+    let trigger_start = s.rfind(" if ").filter(|&idx| !s[idx..].contains(')'));
+    let Some(idx) = trigger_start else {
+      return parse_item(s);
+    };
+    let trigger = s[idx + " if ".len()..].parse::<BuildOrderTrigger>()?;
+    Ok(parse_item(s[..idx].trim())?.when(trigger))
+  }
+}
+
+fn parse_item(s: &str) -> Result<BuildOrderItem, BuildOrderItemParseError> {
+  if s.is_empty() {
+    return Err(BuildOrderItemParseError::Empty);
+  }
+
+  if s.starts_with("Squad(") {
+    return parse_squad(s);
+  }
+
+  if let Some((unit_name, base)) = s.split_once('@') {
+    let unit_type = parse_unit_type(unit_name.trim())?;
+    let base_index = base
+      .trim()
+      .strip_prefix("base")
+      .and_then(|idx| idx.parse::<usize>().ok())
+      .ok_or_else(|| BuildOrderItemParseError::InvalidBaseIndex(base.trim().to_string()))?;
+    return Ok(BuildOrderItem::unit_at_base(unit_type, base_index));
+  }
+
+  if let Some(unit_type) = type_names::unit_type_from_name(s) {
+    return Ok(BuildOrderItem::unit(unit_type));
+  }
+
+  if let Some(upgrade_type) = type_names::upgrade_type_from_name(s) {
+    return Ok(BuildOrderItem::upgrade(upgrade_type));
+  }
+
+  if let Some(tech_type) = type_names::tech_type_from_name(s) {
+    return Ok(BuildOrderItem::research(tech_type));
+  }
+
+  Err(BuildOrderItemParseError::UnknownItem(s.to_string()))
+}
+
+pub fn parse_unit_type(name: &str) -> Result<UnitType, BuildOrderItemParseError> {
+  type_names::unit_type_from_name(name)
+    .ok_or_else(|| BuildOrderItemParseError::UnknownUnitType(name.to_string()))
+}
+
+pub fn parse_upgrade_type(name: &str) -> Result<UpgradeType, BuildOrderItemParseError> {
+  type_names::upgrade_type_from_name(name)
+    .ok_or_else(|| BuildOrderItemParseError::UnknownUpgradeType(name.to_string()))
+}
+
+pub fn parse_tech_type(name: &str) -> Result<TechType, BuildOrderItemParseError> {
+  type_names::tech_type_from_name(name)
+    .ok_or_else(|| BuildOrderItemParseError::UnknownTechType(name.to_string()))
+}
+
+// Squad roles and statuses derive Deserialize, so their variant names are read by serde
+pub fn parse_squad_role(name: &str) -> Result<squad_models::SquadRole, BuildOrderItemParseError> {
+  squad_models::SquadRole::deserialize(StrDeserializer::<ValueError>::new(name))
+    .map_err(|_| BuildOrderItemParseError::UnknownSquadRole(name.to_string()))
+}
+
+pub fn parse_squad_status(
+  name: &str,
+) -> Result<squad_models::SquadStatus, BuildOrderItemParseError> {
+  squad_models::SquadStatus::deserialize(StrDeserializer::<ValueError>::new(name))
+    .map_err(|_| BuildOrderItemParseError::UnknownSquadStatus(name.to_string()))
+}
+
+fn parse_squad(s: &str) -> Result<BuildOrderItem, BuildOrderItemParseError> {
+  let invalid = || BuildOrderItemParseError::InvalidSquad(s.to_string());
+  let inner = s
+    .strip_prefix("Squad(")
+    .and_then(|rest| rest.strip_suffix(')'))
+    .ok_or_else(invalid)?;
+
+  // Split from the right so squad names may contain commas
+  let mut parts = inner.rsplitn(3, ',');
+  let status = parts.next().ok_or_else(invalid)?.trim();
+  let role = parts.next().ok_or_else(invalid)?.trim();
+  let name = parts.next().ok_or_else(invalid)?.trim();
+
+  Ok(BuildOrderItem::squad(
+    name.to_string(),
+    parse_squad_role(role)?,
+    parse_squad_status(status)?,
+  ))
+}
+
+/// Reads the text form written by `Serialize`, the tagged objects `/build-order` returns are
+/// converted in status_webserver
 impl<'de> Deserialize<'de> for BuildOrderItem {
-  fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
   where
     D: serde::Deserializer<'de>,
   {
-    // For now, just return a default value as we don't need to deserialize
-    Ok(BuildOrderItem::Unit {
-      unit_type: UnitType::None,
-      base_index: None,
-    })
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(serde::de::Error::custom)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::military::squad_models::{SquadRole, SquadStatus};
+
+  #[test]
+  fn serialized_items_parse_back() {
+    let items = vec![
+      BuildOrderItem::unit(UnitType::Zerg_Drone),
+      BuildOrderItem::unit_at_base(UnitType::Zerg_Hatchery, 1).when(BuildOrderTrigger::Supply(12)),
+      BuildOrderItem::upgrade(UpgradeType::Metabolic_Boost)
+        .when(BuildOrderTrigger::completed(UnitType::Zerg_Spawning_Pool)),
+      BuildOrderItem::research(TechType::Lurker_Aspect),
+      BuildOrderItem::squad(
+        "mutas, if needed".to_string(),
+        SquadRole::AttackAsMutas,
+        SquadStatus::Gathering,
+      )
+      .when(BuildOrderTrigger::All(vec![
+        BuildOrderTrigger::Minerals(100),
+        BuildOrderTrigger::EnemySeen(Some(UnitType::Terran_Marine)),
+      ])),
+    ];
+
+    let json = serde_json::to_string(&items).unwrap();
+    let parsed: Vec<BuildOrderItem> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, items);
+  }
+
+  #[test]
+  fn unknown_names_are_errors() {
+    assert_eq!(
+      "Zerg_Nothing".parse::<BuildOrderItem>(),
+      Err(BuildOrderItemParseError::UnknownItem(
+        "Zerg_Nothing".to_string()
+      ))
+    );
+    assert_eq!(
+      "Squad(mutas, Harass, Gathering)".parse::<BuildOrderItem>(),
+      Err(BuildOrderItemParseError::UnknownSquadRole(
+        "Harass".to_string()
+      ))
+    );
   }
 }
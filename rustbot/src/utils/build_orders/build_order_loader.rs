@@ -0,0 +1,350 @@
+use rsbwapi::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::utils::build_orders::build_order_selector::{MapCriteria, Matchup};
+use crate::utils::build_orders::build_order_trigger::BuildOrderTrigger;
+use crate::utils::build_orders::build_order_item::{BuildOrderBenchmark, BuildOrderItem};
+use crate::utils::build_orders::type_names;
+use crate::utils::military::squad_models::{SquadRole, SquadStatus};
+
+const BUILD_ORDER_DIR_ENV: &str = "RUSTBOT_BUILD_ORDER_DIR";
+const BUILD_ORDER_NAME_ENV: &str = "RUSTBOT_BUILD_ORDER";
+const DEFAULT_BUILD_ORDER_DIR: &str = "build_orders";
+
+#[derive(Debug, Clone)]
+pub struct LoadedBuildOrder {
+  pub name: String,
+  pub path: PathBuf,
+  pub items: Vec<BuildOrderItem>,
+  /// Matchups this build order is meant for, empty means any
+  pub matchups: Vec<Matchup>,
+  pub map: MapCriteria,
+  /// Build order names to continue with once a Random enemy's race is known
+  pub continuations: HashMap<Matchup, String>,
+  /// Only used as a continuation, never picked at game start
+  pub continuation_only: bool,
+}
+
+#[derive(Debug)]
+pub struct BuildOrderLoadError {
+  pub path: PathBuf,
+  pub line: Option<usize>,
+  pub message: String,
+}
+
+impl fmt::Display for BuildOrderLoadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.line {
+      Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+      None => write!(f, "{}: {}", self.path.display(), self.message),
+    }
+  }
+}
+
+impl std::error::Error for BuildOrderLoadError {}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BuildOrderFile {
+  name: Option<String>,
+  #[serde(default)]
+  matchups: Vec<Matchup>,
+  #[serde(default)]
+  map: MapCriteria,
+  #[serde(default)]
+  continuations: HashMap<Matchup, String>,
+  #[serde(default)]
+  continuation_only: bool,
+  items: Vec<BuildOrderItemEntry>,
+}
+
+/// Items may use the text form the bot reports (`- Zerg_Hatchery @base1`) or the structured form.
+/// Both are turned into items while serde_yaml reads them, so every error gets the item's line
+struct BuildOrderItemEntry(BuildOrderItem);
+
+impl<'de> Deserialize<'de> for BuildOrderItemEntry {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    deserializer.deserialize_any(BuildOrderItemEntryVisitor)
+  }
+}
+
+struct BuildOrderItemEntryVisitor;
+
+impl<'de> serde::de::Visitor<'de> for BuildOrderItemEntryVisitor {
+  type Value = BuildOrderItemEntry;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "a build order item string or map")
+  }
+
+  fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+  where
+    E: serde::de::Error,
+  {
+    value.parse().map(BuildOrderItemEntry).map_err(E::custom)
+  }
+
+  fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+  where
+    A: serde::de::MapAccess<'de>,
+  {
+    RawBuildOrderItem::deserialize(serde::de::value::MapAccessDeserializer::new(map))?
+      .into_build_order_item()
+      .map(BuildOrderItemEntry)
+      .map_err(serde::de::Error::custom)
+  }
+}
+
+/// Structured item, e.g. `- unit: Zerg_Hatchery` with `base: 1`, `when: supply >= 12` and
+/// `benchmark: { started: 2400 }`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBuildOrderItem {
+  unit: Option<UnitTypeName>,
+  base: Option<usize>,
+  upgrade: Option<UpgradeTypeName>,
+  research: Option<TechTypeName>,
+  squad: Option<RawSquad>,
+  when: Option<TriggerText>,
+  benchmark: Option<BuildOrderBenchmark>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSquad {
+  name: String,
+  role: SquadRole,
+  status: SquadStatus,
+}
+
+// Names are resolved while the scalar is being read so serde_yaml reports the
+// item index and line of the bad name
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct UnitTypeName(UnitType);
+
+impl TryFrom<String> for UnitTypeName {
+  type Error = String;
+
+  fn try_from(name: String) -> Result<Self, Self::Error> {
+    type_names::unit_type_from_name(&name)
+      .map(UnitTypeName)
+      .ok_or_else(|| format!("unknown unit type `{}`", name))
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct UpgradeTypeName(UpgradeType);
+
+impl TryFrom<String> for UpgradeTypeName {
+  type Error = String;
+
+  fn try_from(name: String) -> Result<Self, Self::Error> {
+    type_names::upgrade_type_from_name(&name)
+      .map(UpgradeTypeName)
+      .ok_or_else(|| format!("unknown upgrade type `{}`", name))
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct TechTypeName(TechType);
+
+impl TryFrom<String> for TechTypeName {
+  type Error = String;
+
+  fn try_from(name: String) -> Result<Self, Self::Error> {
+    type_names::tech_type_from_name(&name)
+      .map(TechTypeName)
+      .ok_or_else(|| format!("unknown tech type `{}`", name))
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct TriggerText(BuildOrderTrigger);
+
+impl TryFrom<String> for TriggerText {
+  type Error = String;
+
+  fn try_from(text: String) -> Result<Self, Self::Error> {
+    text.parse().map(TriggerText).map_err(|e| e.to_string())
+  }
+}
+
+impl RawBuildOrderItem {
+  fn into_build_order_item(self) -> Result<BuildOrderItem, String> {
+    let mut item = self.item_without_trigger()?;
+    if let Some(TriggerText(trigger)) = self.when {
+      item = item.when(trigger);
+    }
+    if let Some(benchmark) = self.benchmark {
+      item = item.with_benchmark(benchmark);
+    }
+    Ok(item)
+  }
+
+  fn item_without_trigger(&self) -> Result<BuildOrderItem, String> {
+    match (&self.unit, &self.upgrade, &self.research, &self.squad) {
+      (Some(UnitTypeName(unit_type)), None, None, None) => Ok(BuildOrderItem::Unit {
+        unit_type: *unit_type,
+        base_index: self.base,
+        trigger: None,
+        benchmark: None,
+      }),
+      (None, Some(UpgradeTypeName(upgrade_type)), None, None) => {
+        if self.base.is_some() {
+          return Err(format!(
+            "`base` is not allowed on upgrade {:?}",
+            upgrade_type
+          ));
+        }
+        Ok(BuildOrderItem::upgrade(*upgrade_type))
+      }
+      (None, None, Some(TechTypeName(tech_type)), None) => {
+        if self.base.is_some() {
+          return Err(format!("`base` is not allowed on research {:?}", tech_type));
+        }
+        Ok(BuildOrderItem::research(*tech_type))
+      }
+      (None, None, None, Some(squad)) => {
+        if self.base.is_some() {
+          return Err(format!("`base` is not allowed on squad `{}`", squad.name));
+        }
+        Ok(BuildOrderItem::squad(
+          squad.name.clone(),
+          squad.role,
+          squad.status,
+        ))
+      }
+      _ => {
+        Err("each item needs exactly one of `unit`, `upgrade`, `research` or `squad`".to_string())
+      }
+    }
+  }
+}
+
+/// Directory build order files are read from, `RUSTBOT_BUILD_ORDER_DIR` or `./build_orders`
+pub fn build_order_dir() -> PathBuf {
+  match std::env::var(BUILD_ORDER_DIR_ENV) {
+    Ok(dir) => PathBuf::from(dir),
+    Err(_) => std::env::current_dir()
+      .unwrap_or_default()
+      .join(DEFAULT_BUILD_ORDER_DIR),
+  }
+}
+
+pub fn parse_build_order(
+  path: &Path,
+  contents: &str,
+) -> Result<LoadedBuildOrder, BuildOrderLoadError> {
+  let file: BuildOrderFile = serde_yaml::from_str(contents).map_err(|e| BuildOrderLoadError {
+    path: path.to_path_buf(),
+    line: e.location().map(|location| location.line()),
+    message: e.to_string(),
+  })?;
+
+  let name = file.name.unwrap_or_else(|| {
+    path
+      .file_stem()
+      .map(|stem| stem.to_string_lossy().to_string())
+      .unwrap_or_default()
+  });
+
+  let items = file
+    .items
+    .into_iter()
+    .map(|BuildOrderItemEntry(item)| item)
+    .collect();
+
+  Ok(LoadedBuildOrder {
+    name,
+    path: path.to_path_buf(),
+    items,
+    matchups: file.matchups,
+    map: file.map,
+    continuations: file.continuations,
+    continuation_only: file.continuation_only,
+  })
+}
+
+pub fn load_build_order_file(path: &Path) -> Result<LoadedBuildOrder, BuildOrderLoadError> {
+  let contents = std::fs::read_to_string(path).map_err(|e| BuildOrderLoadError {
+    path: path.to_path_buf(),
+    line: None,
+    message: format!("could not read file: {}", e),
+  })?;
+  parse_build_order(path, &contents)
+}
+
+/// Loads every `.yaml`/`.yml` file in the directory, logging and skipping files that fail to parse
+pub fn load_build_orders_from_dir(dir: &Path) -> Vec<LoadedBuildOrder> {
+  let Ok(entries) = std::fs::read_dir(dir) else {
+    println!("Build order directory {} not found", dir.display());
+    return Vec::new();
+  };
+
+  let mut paths: Vec<PathBuf> = entries
+    .filter_map(|entry| entry.ok().map(|e| e.path()))
+    .filter(|path| {
+      path
+        .extension()
+        .map(|ext| ext == "yaml" || ext == "yml")
+        .unwrap_or(false)
+    })
+    .collect();
+  paths.sort();
+
+  paths
+    .iter()
+    .filter_map(|path| match load_build_order_file(path) {
+      Ok(loaded) => {
+        println!(
+          "Loaded build order '{}' ({} items) from {}",
+          loaded.name,
+          loaded.items.len(),
+          path.display()
+        );
+        Some(loaded)
+      }
+      Err(e) => {
+        println!("Failed to load build order: {}", e);
+        None
+      }
+    })
+    .collect()
+}
+
+/// Build order forced with `RUSTBOT_BUILD_ORDER`, if any
+pub fn configured_build_order_name() -> Option<String> {
+  std::env::var(BUILD_ORDER_NAME_ENV)
+    .ok()
+    .filter(|name| !name.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn item_errors_carry_their_line() {
+    let contents = "\
+items:
+  - Zerg_Drone
+  - unit: Zerg_Hatchery
+    base: 1
+  - unit: Zerg_Spawning_Pool
+    upgrade: Metabolic_Boost
+";
+    let error = parse_build_order(Path::new("bad.yaml"), contents).unwrap_err();
+    assert_eq!(error.line, Some(5));
+    assert!(error.message.contains("exactly one of"));
+  }
+}
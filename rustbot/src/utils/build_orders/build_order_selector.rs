@@ -0,0 +1,267 @@
+use rsbwapi::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::utils::build_order_management;
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::build_orders::build_order_loader::{self, LoadedBuildOrder};
+use crate::utils::build_orders::pool_speed_expand;
+use crate::utils::game_state::GameState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Matchup {
+  ZvT,
+  ZvP,
+  ZvZ,
+  /// Enemy picked Random and we have not seen them yet
+  ZvR,
+}
+
+impl Matchup {
+  pub fn from_race(race: Race) -> Self {
+    match race {
+      Race::Terran => Matchup::ZvT,
+      Race::Protoss => Matchup::ZvP,
+      Race::Zerg => Matchup::ZvZ,
+      _ => Matchup::ZvR,
+    }
+  }
+}
+
+/// Map conditions a build order file can require, every field is optional
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapCriteria {
+  pub min_start_locations: Option<usize>,
+  pub max_start_locations: Option<usize>,
+  /// Longest map side in build tiles, e.g. 128 for a 128x128 map
+  pub min_size: Option<i32>,
+  pub max_size: Option<i32>,
+  /// Matches if the map file name contains any of these, ignoring case
+  #[serde(default)]
+  pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MapInfo {
+  pub start_locations: usize,
+  pub size: i32,
+  pub file_name: String,
+}
+
+impl MapInfo {
+  pub fn from_game(game: &Game) -> Self {
+    Self {
+      start_locations: game.get_start_locations().len(),
+      size: game.map_width().max(game.map_height()),
+      file_name: game.map_file_name(),
+    }
+  }
+}
+
+impl MapCriteria {
+  pub fn matches(&self, map: &MapInfo) -> bool {
+    let file_name = map.file_name.to_lowercase();
+    self
+      .min_start_locations
+      .is_none_or(|min| map.start_locations >= min)
+      && self
+        .max_start_locations
+        .is_none_or(|max| map.start_locations <= max)
+      && self.min_size.is_none_or(|min| map.size >= min)
+      && self.max_size.is_none_or(|max| map.size <= max)
+      && (self.names.is_empty()
+        || self
+          .names
+          .iter()
+          .any(|name| file_name.contains(&name.to_lowercase())))
+  }
+
+  /// Number of conditions set, used to prefer the most specific build order
+  fn specificity(&self) -> usize {
+    [
+      self.min_start_locations.is_some(),
+      self.max_start_locations.is_some(),
+      self.min_size.is_some(),
+      self.max_size.is_some(),
+      !self.names.is_empty(),
+    ]
+    .iter()
+    .filter(|set| **set)
+    .count()
+  }
+}
+
+pub struct SelectedBuildOrder {
+  pub name: String,
+  pub items: Vec<BuildOrderItem>,
+  /// Items to switch to once a Random enemy's race is known
+  pub continuations: HashMap<Matchup, (String, Vec<BuildOrderItem>)>,
+}
+
+/// Picks the build order for this matchup and map. Files that list the matchup win over files
+/// that leave `matchups` empty, then the one with the most map conditions wins.
+pub fn select_build_order<'a>(
+  build_orders: &'a [LoadedBuildOrder],
+  matchup: Matchup,
+  map: &MapInfo,
+) -> Option<&'a LoadedBuildOrder> {
+  build_orders
+    .iter()
+    .filter(|build_order| !build_order.continuation_only)
+    .filter(|build_order| {
+      build_order.matchups.is_empty() || build_order.matchups.contains(&matchup)
+    })
+    .filter(|build_order| build_order.map.matches(map))
+    // max_by_key keeps the last of equal keys, so reverse to keep file order on ties
+    .rev()
+    .max_by_key(|build_order| {
+      (
+        !build_order.matchups.is_empty(),
+        build_order.map.specificity(),
+      )
+    })
+}
+
+fn resolve_continuations(
+  build_orders: &[LoadedBuildOrder],
+  selected: &LoadedBuildOrder,
+) -> HashMap<Matchup, (String, Vec<BuildOrderItem>)> {
+  selected
+    .continuations
+    .iter()
+    .filter_map(|(matchup, name)| {
+      let Some(continuation) = build_orders.iter().find(|b| &b.name == name) else {
+        println!(
+          "Build order '{}' continuation '{}' for {:?} not found",
+          selected.name, name, matchup
+        );
+        return None;
+      };
+      Some((*matchup, (name.clone(), continuation.items.clone())))
+    })
+    .collect()
+}
+
+/// `RUSTBOT_BUILD_ORDER` forces a build order by name, otherwise one is picked for the enemy
+/// race and map, falling back to the built-in pool_speed_expand
+pub fn select_for_game(game: &Game) -> SelectedBuildOrder {
+  let build_orders =
+    build_order_loader::load_build_orders_from_dir(&build_order_loader::build_order_dir());
+
+  let selected = match build_order_loader::configured_build_order_name() {
+    Some(name) => {
+      let forced = build_orders.iter().find(|b| b.name == name);
+      if forced.is_none() {
+        println!(
+          "No valid build order named '{}', selecting one instead",
+          name
+        );
+      }
+      forced
+    }
+    None => None,
+  }
+  .or_else(|| {
+    let enemy_race = game
+      .enemy()
+      .map(|enemy| enemy.get_race())
+      .unwrap_or(Race::Unknown);
+    let matchup = Matchup::from_race(enemy_race);
+    let map = MapInfo::from_game(game);
+    println!(
+      "Selecting build order for {:?} on {} ({} start locations, size {})",
+      matchup, map.file_name, map.start_locations, map.size
+    );
+    select_build_order(&build_orders, matchup, &map)
+  });
+
+  match selected {
+    Some(selected) => {
+      println!(
+        "Using build order '{}' from {}",
+        selected.name,
+        selected.path.display()
+      );
+      SelectedBuildOrder {
+        name: selected.name.clone(),
+        items: selected.items.clone(),
+        continuations: resolve_continuations(&build_orders, selected),
+      }
+    }
+    None => {
+      println!("Falling back to built-in pool_speed_expand build order");
+      SelectedBuildOrder {
+        name: "pool_speed_expand".to_string(),
+        items: pool_speed_expand::build_order(),
+        continuations: HashMap::new(),
+      }
+    }
+  }
+}
+
+/// Build order from the build order directory by name, or the built-in pool_speed_expand
+pub fn load_by_name(name: &str) -> Option<SelectedBuildOrder> {
+  let build_orders =
+    build_order_loader::load_build_orders_from_dir(&build_order_loader::build_order_dir());
+
+  match build_orders.iter().find(|b| b.name == name) {
+    Some(found) => Some(SelectedBuildOrder {
+      name: found.name.clone(),
+      items: found.items.clone(),
+      continuations: resolve_continuations(&build_orders, found),
+    }),
+    None if name == "pool_speed_expand" => Some(SelectedBuildOrder {
+      name: name.to_string(),
+      items: pool_speed_expand::build_order(),
+      continuations: HashMap::new(),
+    }),
+    None => None,
+  }
+}
+
+/// Names of every build order file in the build order directory, continuations included
+pub fn available_build_order_names() -> Vec<String> {
+  build_order_loader::load_build_orders_from_dir(&build_order_loader::build_order_dir())
+    .into_iter()
+    .map(|build_order| build_order.name)
+    .collect()
+}
+
+/// Once a Random enemy's race shows up, swaps the rest of the build order for that race's continuation
+pub fn apply_race_continuation(game: &Game, game_state: &mut GameState) {
+  if game_state.build_order_continuations.is_empty() {
+    return;
+  }
+
+  let Some(enemy) = game.enemy() else {
+    return;
+  };
+  let matchup = Matchup::from_race(enemy.get_race());
+  if matchup == Matchup::ZvR {
+    return;
+  }
+
+  let continuations = std::mem::take(&mut game_state.build_order_continuations);
+  let Some((name, items)) = continuations.get(&matchup).cloned() else {
+    println!(
+      "Enemy race revealed as {:?}, no continuation for {:?}, keeping '{}'",
+      enemy.get_race(),
+      matchup,
+      game_state.build_order_name
+    );
+    return;
+  };
+
+  println!(
+    "Enemy race revealed as {:?}, switching to continuation '{}'",
+    enemy.get_race(),
+    name
+  );
+  build_order_management::replace_remaining_build_order(
+    game_state,
+    items,
+    &format!("{:?} continuation '{}'", matchup, name),
+  );
+  game_state.build_order_name = format!("{} -> {}", game_state.build_order_name, name);
+}
@@ -0,0 +1,1097 @@
+use rsbwapi::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use crate::utils::build_order_management::satisfies_requirement;
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::build_orders::build_order_loader::{self, LoadedBuildOrder};
+use crate::utils::build_orders::build_order_trigger::BuildOrderTrigger;
+use crate::utils::game_state::DEFAULT_BUILD_ORDER_WINDOW;
+
+/// Frames between larva spawns at one hatchery
+const LARVA_SPAWN_FRAMES: i32 = 342;
+const MAX_LARVA_PER_HATCHERY: i32 = 3;
+/// Rough mineral income of one drone while its base has two drones per patch or fewer
+const MINERALS_PER_DRONE_FRAME: f32 = 0.045;
+const GAS_PER_DRONE_FRAME: f32 = 0.035;
+const DRONES_PER_BASE: usize = 16;
+const DRONES_PER_EXTRACTOR: usize = 3;
+/// BWAPI counts supply in halves, 200 shown supply
+const MAX_SUPPLY: i32 = 400;
+/// An item still waiting after this long is reported as stalled
+const MAX_WAIT_FRAMES: i32 = 24 * 60 * 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationIssue {
+  /// Nothing finished or earlier in the build order makes a unit this item needs
+  MissingRequirement(UnitType),
+  /// Needs gas with no extractor built or queued before it
+  NoGasIncome(i32),
+  /// Not enough supply and no Overlord or Hatchery on the way
+  SupplyBlocked {
+    needed: i32,
+    free: i32,
+  },
+  SupplyMaxed,
+  /// Gave up after waiting `MAX_WAIT_FRAMES`
+  Stalled {
+    waiting_for: String,
+  },
+  /// A trigger that can't be simulated was treated as satisfied
+  AssumedTrigger(String),
+  /// Nothing left to research, the bot skips the item too
+  Redundant(String),
+}
+
+impl SimulationIssue {
+  /// Errors mean the item would never start in a real game
+  pub fn is_error(&self) -> bool {
+    !matches!(
+      self,
+      SimulationIssue::AssumedTrigger(_) | SimulationIssue::Redundant(_)
+    )
+  }
+}
+
+impl fmt::Display for SimulationIssue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SimulationIssue::MissingRequirement(required) => write!(
+        f,
+        "needs {:?} but nothing before it in the build order makes one",
+        required
+      ),
+      SimulationIssue::NoGasIncome(gas) => {
+        write!(
+          f,
+          "costs {} gas but no Zerg_Extractor is queued before it",
+          gas
+        )
+      }
+      SimulationIssue::SupplyBlocked { needed, free } => write!(
+        f,
+        "supply blocked, needs {} supply with {} free and no Zerg_Overlord queued",
+        needed / 2,
+        free / 2
+      ),
+      SimulationIssue::SupplyMaxed => write!(f, "supply is maxed out"),
+      SimulationIssue::Stalled { waiting_for } => write!(
+        f,
+        "still waiting for {} after {} frames",
+        waiting_for, MAX_WAIT_FRAMES
+      ),
+      SimulationIssue::AssumedTrigger(trigger) => {
+        write!(f, "assumed `{}` holds, it can't be simulated", trigger)
+      }
+      SimulationIssue::Redundant(reason) => write!(f, "skipped, {}", reason),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatedItem {
+  pub index: usize,
+  pub item: String,
+  pub start_frame: Option<i32>,
+  pub complete_frame: Option<i32>,
+  /// Frames after the item's `benchmark.started`, negative when ahead
+  pub benchmark_delta: Option<i32>,
+  pub minerals: i32,
+  pub gas: i32,
+  pub supply_used: i32,
+  pub supply_total: i32,
+  pub issues: Vec<SimulationIssue>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+  pub name: String,
+  pub items: Vec<SimulatedItem>,
+  /// Frame the last item finishes
+  pub end_frame: i32,
+}
+
+impl SimulationReport {
+  pub fn error_count(&self) -> usize {
+    self
+      .items
+      .iter()
+      .flat_map(|item| &item.issues)
+      .filter(|issue| issue.is_error())
+      .count()
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Production {
+  Unit { unit_type: UnitType, count: usize },
+  Upgrade(UpgradeType),
+  Research(TechType),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct InProgress {
+  complete_frame: i32,
+  production: Production,
+  /// Expansions add a mineral line, macro hatcheries only add larva
+  new_base: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LarvaProducer {
+  larva: i32,
+  next_spawn_frame: i32,
+}
+
+/// Economy of one Zerg player, starting from the usual 4 drones, hatchery and overlord
+struct SimulationState {
+  frame: i32,
+  minerals: f32,
+  gas: f32,
+  supply_used: i32,
+  supply_total: i32,
+  mineral_drones: usize,
+  gas_drones: usize,
+  bases: usize,
+  hatcheries: Vec<LarvaProducer>,
+  /// Finished units that are not busy morphing, drones excluded
+  completed: HashMap<UnitType, usize>,
+  in_progress: Vec<InProgress>,
+  upgrade_levels: HashMap<UpgradeType, i32>,
+  researched: HashSet<TechType>,
+}
+
+impl SimulationState {
+  fn new() -> Self {
+    Self {
+      frame: 0,
+      minerals: 50.0,
+      gas: 0.0,
+      supply_used: 4 * UnitType::Zerg_Drone.supply_required(),
+      supply_total: UnitType::Zerg_Hatchery.supply_provided()
+        + UnitType::Zerg_Overlord.supply_provided(),
+      mineral_drones: 4,
+      gas_drones: 0,
+      bases: 1,
+      hatcheries: vec![LarvaProducer {
+        larva: MAX_LARVA_PER_HATCHERY,
+        next_spawn_frame: LARVA_SPAWN_FRAMES,
+      }],
+      completed: [(UnitType::Zerg_Hatchery, 1), (UnitType::Zerg_Overlord, 1)]
+        .into_iter()
+        .collect(),
+      in_progress: Vec::new(),
+      upgrade_levels: HashMap::new(),
+      researched: HashSet::new(),
+    }
+  }
+
+  fn drones(&self) -> usize {
+    self.mineral_drones + self.gas_drones
+  }
+
+  fn count(&self, unit_type: UnitType) -> usize {
+    if unit_type == UnitType::Zerg_Drone {
+      return self.drones();
+    }
+    if unit_type == UnitType::Zerg_Larva {
+      return self.hatcheries.iter().map(|h| h.larva as usize).sum();
+    }
+    self
+      .completed
+      .iter()
+      .filter(|(owned, _)| satisfies_requirement(**owned, unit_type))
+      .map(|(_, count)| *count)
+      .sum()
+  }
+
+  fn will_have(&self, unit_type: UnitType) -> bool {
+    let in_progress = self.in_progress.iter().any(|p| match p.production {
+      Production::Unit { unit_type: t, .. } => satisfies_requirement(t, unit_type),
+      Production::Upgrade(_) | Production::Research(_) => false,
+    });
+    match unit_type {
+      UnitType::Zerg_Larva => !self.hatcheries.is_empty() || in_progress,
+      _ => self.count(unit_type) > 0 || in_progress,
+    }
+  }
+
+  fn is_researching(&self, production: Production) -> bool {
+    self
+      .in_progress
+      .iter()
+      .any(|p| match (p.production, production) {
+        (Production::Upgrade(a), Production::Upgrade(b)) => a == b,
+        (Production::Research(a), Production::Research(b)) => a == b,
+        _ => false,
+      })
+  }
+
+  fn supply_in_production(&self) -> i32 {
+    self
+      .in_progress
+      .iter()
+      .map(|p| match p.production {
+        Production::Unit { unit_type, count } => unit_type.supply_provided() * count as i32,
+        Production::Upgrade(_) | Production::Research(_) => 0,
+      })
+      .sum()
+  }
+
+  fn step(&mut self) {
+    self.frame += 1;
+
+    let saturated = self.mineral_drones.min(self.bases * DRONES_PER_BASE);
+    // A third drone per patch only adds about a third of a drone's income
+    let oversaturated = (self.mineral_drones - saturated).min(self.bases * DRONES_PER_BASE / 2);
+    self.minerals += (saturated as f32 + oversaturated as f32 / 3.0) * MINERALS_PER_DRONE_FRAME;
+    self.gas += self.gas_drones as f32 * GAS_PER_DRONE_FRAME;
+
+    for hatchery in &mut self.hatcheries {
+      if self.frame >= hatchery.next_spawn_frame {
+        if hatchery.larva < MAX_LARVA_PER_HATCHERY {
+          hatchery.larva += 1;
+        }
+        hatchery.next_spawn_frame = self.frame + LARVA_SPAWN_FRAMES;
+      }
+    }
+
+    let frame = self.frame;
+    let (done, still_going): (Vec<InProgress>, Vec<InProgress>) = self
+      .in_progress
+      .drain(..)
+      .partition(|p| p.complete_frame <= frame);
+    self.in_progress = still_going;
+    for finished in done {
+      self.finish(finished);
+    }
+  }
+
+  fn finish(&mut self, finished: InProgress) {
+    match finished.production {
+      Production::Upgrade(upgrade_type) => {
+        *self.upgrade_levels.entry(upgrade_type).or_insert(0) += 1;
+      }
+      Production::Research(tech_type) => {
+        self.researched.insert(tech_type);
+      }
+      Production::Unit { unit_type, count } => {
+        self.supply_total =
+          (self.supply_total + unit_type.supply_provided() * count as i32).min(MAX_SUPPLY);
+        match unit_type {
+          UnitType::Zerg_Drone => self.mineral_drones += count,
+          UnitType::Zerg_Hatchery => {
+            self.hatcheries.push(LarvaProducer {
+              larva: 1,
+              next_spawn_frame: self.frame + LARVA_SPAWN_FRAMES,
+            });
+            if finished.new_base {
+              self.bases += 1;
+            }
+          }
+          UnitType::Zerg_Extractor => {
+            let to_gas = DRONES_PER_EXTRACTOR.min(self.mineral_drones);
+            self.mineral_drones -= to_gas;
+            self.gas_drones += to_gas;
+          }
+          _ => {}
+        }
+        if unit_type != UnitType::Zerg_Drone {
+          *self.completed.entry(unit_type).or_insert(0) += count;
+        }
+      }
+    }
+  }
+
+  /// Takes the larva, drone or unit that morphs into `unit_type`
+  fn consume_builder(&mut self, builder: UnitType) {
+    match builder {
+      UnitType::Zerg_Larva => {
+        if let Some(hatchery) = self.hatcheries.iter_mut().find(|h| h.larva > 0) {
+          if hatchery.larva == MAX_LARVA_PER_HATCHERY {
+            hatchery.next_spawn_frame = self.frame + LARVA_SPAWN_FRAMES;
+          }
+          hatchery.larva -= 1;
+        }
+      }
+      UnitType::Zerg_Drone => {
+        if self.mineral_drones > 0 {
+          self.mineral_drones -= 1;
+        } else {
+          self.gas_drones -= 1;
+        }
+      }
+      _ => {
+        if let Some(count) = self.completed.get_mut(&builder) {
+          *count = count.saturating_sub(1);
+        }
+      }
+    }
+  }
+}
+
+/// What an item needs to start, worked out once before waiting on it
+struct ItemNeeds {
+  minerals: i32,
+  gas: i32,
+  /// Half supply added when it starts, negative when a drone becomes a building
+  supply: i32,
+  builder: Option<UnitType>,
+  requirements: Vec<UnitType>,
+}
+
+fn item_needs(state: &SimulationState, item: &BuildOrderItem) -> ItemNeeds {
+  match item {
+    BuildOrderItem::Unit { unit_type, .. } => {
+      let builder = unit_type.what_builds().0;
+      let supply = if builder == UnitType::Zerg_Larva {
+        unit_type.supply_required() * units_per_egg(*unit_type) as i32
+      } else {
+        unit_type.supply_required() - builder.supply_required()
+      };
+      ItemNeeds {
+        minerals: unit_type.mineral_price(),
+        gas: unit_type.gas_price(),
+        supply,
+        builder: Some(builder),
+        requirements: unit_type
+          .required_units()
+          .keys()
+          .copied()
+          .filter(|required| *required != builder)
+          .collect(),
+      }
+    }
+    BuildOrderItem::Upgrade { upgrade_type, .. } => {
+      let level = state.upgrade_levels.get(upgrade_type).copied().unwrap_or(0) + 1;
+      let mut requirements = vec![upgrade_type.what_upgrades()];
+      let extra = upgrade_type.whats_required(level);
+      if extra != UnitType::None {
+        requirements.push(extra);
+      }
+      ItemNeeds {
+        minerals: upgrade_type.mineral_price(level),
+        gas: upgrade_type.gas_price(level),
+        supply: 0,
+        builder: None,
+        requirements,
+      }
+    }
+    BuildOrderItem::Research { tech_type, .. } => ItemNeeds {
+      minerals: tech_type.mineral_price(),
+      gas: tech_type.gas_price(),
+      supply: 0,
+      builder: None,
+      requirements: [tech_type.what_researches(), tech_type.required_unit()]
+        .into_iter()
+        .filter(|required| *required != UnitType::None)
+        .collect(),
+    },
+    BuildOrderItem::Squad { .. } => ItemNeeds {
+      minerals: 0,
+      gas: 0,
+      supply: 0,
+      builder: None,
+      requirements: Vec::new(),
+    },
+  }
+}
+
+fn units_per_egg(unit_type: UnitType) -> usize {
+  match unit_type {
+    UnitType::Zerg_Zergling | UnitType::Zerg_Scourge => 2,
+    _ => 1,
+  }
+}
+
+/// Whether the trigger holds, triggers on the enemy are assumed to and noted in `issues`
+fn trigger_satisfied(
+  trigger: &BuildOrderTrigger,
+  state: &SimulationState,
+  issues: &mut Vec<SimulationIssue>,
+) -> bool {
+  match trigger {
+    BuildOrderTrigger::Supply(supply) => state.supply_used / 2 >= *supply,
+    BuildOrderTrigger::Minerals(minerals) => state.minerals as i32 >= *minerals,
+    BuildOrderTrigger::Gas(gas) => state.gas as i32 >= *gas,
+    BuildOrderTrigger::Frame(frame) => state.frame >= *frame,
+    BuildOrderTrigger::Completed { unit_type, count } => {
+      let completed = if *unit_type == UnitType::Zerg_Drone {
+        state.drones()
+      } else {
+        state.completed.get(unit_type).copied().unwrap_or(0)
+      };
+      completed as i32 >= *count
+    }
+    BuildOrderTrigger::EnemySeen(_) => {
+      let assumed = SimulationIssue::AssumedTrigger(trigger.to_string());
+      if !issues.contains(&assumed) {
+        issues.push(assumed);
+      }
+      true
+    }
+    BuildOrderTrigger::All(triggers) => triggers
+      .iter()
+      .all(|trigger| trigger_satisfied(trigger, state, issues)),
+  }
+}
+
+enum Waiting {
+  /// Trigger or tech not there yet, the bot doesn't hold resources for it
+  NotReady(String),
+  /// Ready but short on larva, drones, resources or supply, later items must not spend first
+  Blocked(String),
+}
+
+/// What keeps the item from starting this frame once its trigger holds, `None` once it can
+/// start. `reserved` is what earlier blocked items are owed.
+fn waiting_for(
+  state: &SimulationState,
+  item: &BuildOrderItem,
+  needs: &ItemNeeds,
+  reserved: (i32, i32),
+) -> Option<Waiting> {
+  if let Some(required) = needs
+    .requirements
+    .iter()
+    .find(|required| state.count(**required) == 0)
+  {
+    return Some(Waiting::NotReady(format!("{:?} to finish", required)));
+  }
+  // Upgrade levels are researched one after the other
+  if let BuildOrderItem::Upgrade { upgrade_type, .. } = item {
+    if state.is_researching(Production::Upgrade(*upgrade_type)) {
+      return Some(Waiting::NotReady(format!(
+        "the previous {:?} level to finish",
+        upgrade_type
+      )));
+    }
+  }
+  if let Some(builder) = needs.builder {
+    if state.count(builder) == 0 {
+      return Some(Waiting::Blocked(format!("a free {:?}", builder)));
+    }
+  }
+  let (reserved_minerals, reserved_gas) = reserved;
+  if (state.minerals as i32) - reserved_minerals < needs.minerals
+    || (state.gas as i32) - reserved_gas < needs.gas
+  {
+    return Some(Waiting::Blocked(format!(
+      "{} minerals and {} gas",
+      needs.minerals, needs.gas
+    )));
+  }
+  if needs.supply > 0 && state.supply_used + needs.supply > state.supply_total {
+    return Some(Waiting::Blocked("supply".to_string()));
+  }
+  None
+}
+
+/// Upgrades past their max level and tech that is already researched, the bot skips these
+fn redundant_reason(state: &SimulationState, item: &BuildOrderItem) -> Option<SimulationIssue> {
+  match item {
+    BuildOrderItem::Upgrade { upgrade_type, .. } => {
+      let level = state.upgrade_levels.get(upgrade_type).copied().unwrap_or(0);
+      (!state.is_researching(Production::Upgrade(*upgrade_type))
+        && level >= upgrade_type.max_repeats())
+      .then(|| {
+        SimulationIssue::Redundant(format!("{:?} is already at its max level", upgrade_type))
+      })
+    }
+    BuildOrderItem::Research { tech_type, .. } => (state.researched.contains(tech_type)
+      || state.is_researching(Production::Research(*tech_type)))
+    .then(|| SimulationIssue::Redundant(format!("{:?} is already researched", tech_type))),
+    _ => None,
+  }
+}
+
+/// Problems that mean the item can never start, checked before waiting on it
+fn impossible_reason(state: &SimulationState, needs: &ItemNeeds) -> Option<SimulationIssue> {
+  let missing = needs
+    .requirements
+    .iter()
+    .chain(needs.builder.iter())
+    .find(|required| !state.will_have(**required));
+  if let Some(required) = missing {
+    return Some(SimulationIssue::MissingRequirement(*required));
+  }
+
+  if needs.gas > state.gas as i32 && !state.will_have(UnitType::Zerg_Extractor) {
+    return Some(SimulationIssue::NoGasIncome(needs.gas));
+  }
+
+  if needs.supply > 0 && state.supply_used + needs.supply > state.supply_total {
+    if state.supply_total >= MAX_SUPPLY {
+      return Some(SimulationIssue::SupplyMaxed);
+    }
+    if state.supply_in_production() == 0 {
+      return Some(SimulationIssue::SupplyBlocked {
+        needed: needs.supply,
+        free: state.supply_total - state.supply_used,
+      });
+    }
+  }
+  None
+}
+
+/// Runs the build order the way the bot does: up to `window` items from the first unstarted
+/// one may start at once, squads wait until they are first, and an item only spends what the
+/// blocked items before it don't need. Upgrades and research are queued and stay in the window
+/// until they start, the first one waiting for money holds a reservation. Drones are assumed to
+/// already be at the build site.
+pub fn simulate(name: &str, items: &[BuildOrderItem], window: usize) -> SimulationReport {
+  let mut state = SimulationState::new();
+  let mut simulated: Vec<SimulatedItem> = items
+    .iter()
+    .enumerate()
+    .map(|(index, item)| SimulatedItem {
+      index,
+      item: item.describe(),
+      start_frame: None,
+      complete_frame: None,
+      benchmark_delta: None,
+      minerals: 0,
+      gas: 0,
+      supply_used: 0,
+      supply_total: 0,
+      issues: Vec::new(),
+    })
+    .collect();
+  // Started or given up on
+  let mut done = vec![false; items.len()];
+  // Upgrades and research queued by the window, with the frame they were queued
+  let mut upgrade_queue: Vec<(usize, i32)> = Vec::new();
+  let mut head = 0;
+  let mut head_wait_start = 0;
+
+  while head < items.len() || !upgrade_queue.is_empty() {
+    let mut head_waiting_for = None;
+    let mut changed = false;
+    let head_reservation = queue_head_reservation(&state, items, &upgrade_queue);
+    let mut reserved = head_reservation;
+
+    let window_end = (head + window.max(1)).min(items.len());
+    for idx in head..window_end {
+      if done[idx] {
+        continue;
+      }
+      let item = &items[idx];
+      if matches!(item, BuildOrderItem::Squad { .. }) && idx != head {
+        break;
+      }
+
+      if let Some(trigger) = item.trigger() {
+        if !trigger_satisfied(trigger, &state, &mut simulated[idx].issues) {
+          if idx == head {
+            head_waiting_for = Some(format!("`{}`", trigger));
+          }
+          continue;
+        }
+      }
+
+      if let Some(issue) = redundant_reason(&state, item) {
+        simulated[idx].issues.push(issue);
+        done[idx] = true;
+        changed = true;
+        continue;
+      }
+
+      let needs = item_needs(&state, item);
+      if idx == head || is_background(item) {
+        // Supply can also run out while waiting if nothing is on the way
+        if let Some(issue) = impossible_reason(&state, &needs) {
+          simulated[idx].issues.push(issue);
+          done[idx] = true;
+          changed = true;
+          if is_background(item) {
+            continue;
+          }
+          break;
+        }
+      }
+
+      if is_background(item) {
+        if !upgrade_queue.iter().any(|(queued, _)| *queued == idx) {
+          upgrade_queue.push((idx, state.frame));
+          changed = true;
+        }
+        continue;
+      }
+
+      match waiting_for(&state, item, &needs, reserved) {
+        None => {
+          start_and_record(&mut state, item, &needs, &mut simulated[idx]);
+          done[idx] = true;
+          changed = true;
+        }
+        Some(Waiting::NotReady(reason)) => {
+          if idx == head {
+            head_waiting_for = Some(reason);
+          }
+        }
+        Some(Waiting::Blocked(reason)) => {
+          reserved.0 += needs.minerals;
+          reserved.1 += needs.gas;
+          if idx == head {
+            head_waiting_for = Some(reason);
+          }
+        }
+      }
+    }
+
+    // The head pays from its own reservation
+    changed |= start_queued_upgrades(
+      &mut state,
+      items,
+      &mut upgrade_queue,
+      &mut simulated,
+      &mut done,
+      (
+        reserved.0 - head_reservation.0,
+        reserved.1 - head_reservation.1,
+      ),
+    );
+
+    if head < items.len() && done[head] {
+      while head < items.len() && done[head] {
+        head += 1;
+      }
+      head_wait_start = state.frame;
+    }
+    if changed {
+      // Starting something can free the window for more this frame
+      continue;
+    }
+
+    if let Some(reason) = head_waiting_for {
+      if state.frame - head_wait_start >= MAX_WAIT_FRAMES {
+        simulated[head].issues.push(SimulationIssue::Stalled {
+          waiting_for: reason,
+        });
+        done[head] = true;
+        continue;
+      }
+    }
+    state.step();
+  }
+
+  let end_frame = simulated
+    .iter()
+    .filter_map(|item| item.complete_frame)
+    .max()
+    .unwrap_or(0);
+
+  SimulationReport {
+    name: name.to_string(),
+    items: simulated,
+    end_frame,
+  }
+}
+
+/// Upgrades and research go to the bot's upgrade queue once their trigger holds
+fn is_background(item: &BuildOrderItem) -> bool {
+  matches!(
+    item,
+    BuildOrderItem::Upgrade { .. } | BuildOrderItem::Research { .. }
+  )
+}
+
+fn start_and_record(
+  state: &mut SimulationState,
+  item: &BuildOrderItem,
+  needs: &ItemNeeds,
+  report: &mut SimulatedItem,
+) {
+  let frame = state.frame;
+  let complete_frame = start_item(state, item, needs, frame);
+  report.start_frame = Some(frame);
+  report.complete_frame = Some(complete_frame);
+  report.benchmark_delta = item
+    .benchmark()
+    .and_then(|benchmark| benchmark.started)
+    .map(|benchmark| frame - benchmark);
+  report.minerals = state.minerals as i32;
+  report.gas = state.gas as i32;
+  report.supply_used = state.supply_used;
+  report.supply_total = state.supply_total;
+}
+
+/// What the first queued upgrade that only waits for money reserves, like the bot's queue head
+fn queue_head_reservation(
+  state: &SimulationState,
+  items: &[BuildOrderItem],
+  upgrade_queue: &[(usize, i32)],
+) -> (i32, i32) {
+  upgrade_queue
+    .iter()
+    .map(|(idx, _)| (&items[*idx], item_needs(state, &items[*idx])))
+    .find(|(item, needs)| {
+      !matches!(
+        waiting_for(state, item, needs, (0, 0)),
+        Some(Waiting::NotReady(_))
+      )
+    })
+    .map_or((0, 0), |(_, needs)| (needs.minerals, needs.gas))
+}
+
+/// Starts queued upgrades in the order they were queued, with only what the window's blocked
+/// items leave. Returns whether anything started or was given up on.
+fn start_queued_upgrades(
+  state: &mut SimulationState,
+  items: &[BuildOrderItem],
+  upgrade_queue: &mut Vec<(usize, i32)>,
+  simulated: &mut [SimulatedItem],
+  done: &mut [bool],
+  reserved: (i32, i32),
+) -> bool {
+  let mut reserved = reserved;
+  let mut changed = false;
+  let mut still_queued = Vec::new();
+
+  for (idx, queued_frame) in upgrade_queue.drain(..) {
+    // Given up on by the window already
+    if done[idx] {
+      continue;
+    }
+    let item = &items[idx];
+    if let Some(issue) = redundant_reason(state, item) {
+      simulated[idx].issues.push(issue);
+      done[idx] = true;
+      changed = true;
+      continue;
+    }
+
+    let needs = item_needs(state, item);
+    match waiting_for(state, item, &needs, reserved) {
+      None => {
+        start_and_record(state, item, &needs, &mut simulated[idx]);
+        done[idx] = true;
+        changed = true;
+      }
+      Some(Waiting::NotReady(reason)) | Some(Waiting::Blocked(reason))
+        if state.frame - queued_frame >= MAX_WAIT_FRAMES =>
+      {
+        simulated[idx].issues.push(SimulationIssue::Stalled {
+          waiting_for: reason,
+        });
+        done[idx] = true;
+        changed = true;
+      }
+      Some(waiting) => {
+        if let Waiting::Blocked(_) = waiting {
+          reserved.0 += needs.minerals;
+          reserved.1 += needs.gas;
+        }
+        still_queued.push((idx, queued_frame));
+      }
+    }
+  }
+
+  *upgrade_queue = still_queued;
+  changed
+}
+
+/// Pays for the item and queues its completion, returns the frame it finishes
+fn start_item(
+  state: &mut SimulationState,
+  item: &BuildOrderItem,
+  needs: &ItemNeeds,
+  frame: i32,
+) -> i32 {
+  state.minerals -= needs.minerals as f32;
+  state.gas -= needs.gas as f32;
+  state.supply_used += needs.supply;
+  if let Some(builder) = needs.builder {
+    state.consume_builder(builder);
+  }
+
+  let (production, build_time, new_base) = match item {
+    BuildOrderItem::Unit {
+      unit_type,
+      base_index,
+      ..
+    } => (
+      Production::Unit {
+        unit_type: *unit_type,
+        count: units_per_egg(*unit_type),
+      },
+      unit_type.build_time(),
+      *unit_type == UnitType::Zerg_Hatchery && base_index.is_some_and(|idx| idx > 0),
+    ),
+    BuildOrderItem::Upgrade { upgrade_type, .. } => {
+      let level = state.upgrade_levels.get(upgrade_type).copied().unwrap_or(0) + 1;
+      (
+        Production::Upgrade(*upgrade_type),
+        upgrade_type.upgrade_time(level),
+        false,
+      )
+    }
+    BuildOrderItem::Research { tech_type, .. } => (
+      Production::Research(*tech_type),
+      tech_type.research_time(),
+      false,
+    ),
+    BuildOrderItem::Squad { .. } => return frame,
+  };
+
+  state.in_progress.push(InProgress {
+    complete_frame: frame + build_time,
+    production,
+    new_base,
+  });
+  frame + build_time
+}
+
+fn format_frame(frame: Option<i32>) -> String {
+  match frame {
+    // Fastest game speed runs about 24 frames a second
+    Some(frame) => format!("{:>6} {:>2}:{:02}", frame, frame / 24 / 60, frame / 24 % 60),
+    None => format!("{:>6} {:>5}", "-", "-"),
+  }
+}
+
+/// Width that fits the header and every value of a column
+fn column_width(header: &str, values: impl Iterator<Item = String>) -> usize {
+  values
+    .map(|value| value.len())
+    .fold(header.len(), usize::max)
+}
+
+pub fn print_report(report: &SimulationReport) {
+  let supply = |item: &SimulatedItem| format!("{}/{}", item.supply_used / 2, item.supply_total / 2);
+  let bench = |item: &SimulatedItem| {
+    item
+      .benchmark_delta
+      .map(|delta| format!("{:+}", delta))
+      .unwrap_or_default()
+  };
+  // Columns grow to fit the longest value, item names and late frames don't fit a fixed width
+  let item_width = column_width("item", report.items.iter().map(|item| item.item.clone()));
+  let frame_width = column_width(
+    "start",
+    report.items.iter().flat_map(|item| {
+      [
+        format_frame(item.start_frame),
+        format_frame(item.complete_frame),
+      ]
+    }),
+  );
+  let supply_width = column_width("supply", report.items.iter().map(supply));
+  let bench_width = column_width("bench", report.items.iter().map(bench));
+
+  println!("== {} ==", report.name);
+  println!(
+    "{:>3}  {:<item_width$} {:>frame_width$} {:>frame_width$} {:>5} {:>5} {:>supply_width$} {:>bench_width$}",
+    "#", "item", "start", "done", "min", "gas", "supply", "bench"
+  );
+  for item in &report.items {
+    println!(
+      "{:>3}  {:<item_width$} {:>frame_width$} {:>frame_width$} {:>5} {:>5} {:>supply_width$} {:>bench_width$}",
+      item.index,
+      item.item,
+      format_frame(item.start_frame),
+      format_frame(item.complete_frame),
+      item.minerals,
+      item.gas,
+      supply(item),
+      bench(item),
+    );
+    for issue in &item.issues {
+      let level = if issue.is_error() { "ERROR" } else { "note" };
+      println!("     {}: {}", level, issue);
+    }
+  }
+  println!(
+    "finished at {}, {} error(s)",
+    format_frame(Some(report.end_frame)).trim(),
+    report.error_count()
+  );
+}
+
+fn load_for_cli(arg: &str) -> Result<LoadedBuildOrder, String> {
+  let path = Path::new(arg);
+  if path.exists() {
+    return build_order_loader::load_build_order_file(path).map_err(|e| e.to_string());
+  }
+
+  let dir = build_order_loader::build_order_dir();
+  build_order_loader::load_build_orders_from_dir(&dir)
+    .into_iter()
+    .find(|build_order| build_order.name == arg)
+    .ok_or_else(|| format!("no build order file or name `{}` in {}", arg, dir.display()))
+}
+
+/// Build orders to simulate for one file. Continuations can't run alone, they're appended to the
+/// opener as if the enemy race showed up once the opener was done.
+fn simulation_runs(
+  build_order: &LoadedBuildOrder,
+  all: &[LoadedBuildOrder],
+) -> Vec<Result<(String, Vec<BuildOrderItem>), String>> {
+  let find = |name: &str| all.iter().find(|other| other.name == name);
+  let with_continuation = |opener: &LoadedBuildOrder, continuation: &LoadedBuildOrder| {
+    let mut items = opener.items.clone();
+    items.extend(continuation.items.iter().cloned());
+    (format!("{} -> {}", opener.name, continuation.name), items)
+  };
+
+  if build_order.continuation_only {
+    let runs: Vec<_> = all
+      .iter()
+      .filter(|opener| {
+        opener
+          .continuations
+          .values()
+          .any(|name| *name == build_order.name)
+      })
+      .map(|opener| Ok(with_continuation(opener, build_order)))
+      .collect();
+    if !runs.is_empty() {
+      return runs;
+    }
+    return vec![Ok((build_order.name.clone(), build_order.items.clone()))];
+  }
+
+  // ZvT and ZvP often share one continuation, it only needs to run once
+  let mut continuations: Vec<_> = build_order.continuations.iter().collect();
+  continuations.sort_by_key(|(_, name)| name.as_str());
+  continuations.dedup_by_key(|(_, name)| name.as_str());
+
+  let mut runs = vec![Ok((build_order.name.clone(), build_order.items.clone()))];
+  for (matchup, name) in continuations {
+    runs.push(match find(name) {
+      Some(continuation) => Ok(with_continuation(build_order, continuation)),
+      None => Err(format!(
+        "{}: {:?} continuation `{}` not found",
+        build_order.name, matchup, name
+      )),
+    });
+  }
+  runs
+}
+
+/// `simulate [--window N] [file or name]...`, every build order in the build order
+/// directory when none are given. Returns the process exit code.
+pub fn run_cli(args: &[String]) -> i32 {
+  let mut window = DEFAULT_BUILD_ORDER_WINDOW;
+  let mut args = args.to_vec();
+  if let Some(flag) = args.iter().position(|arg| arg == "--window") {
+    let value = args
+      .get(flag + 1)
+      .and_then(|value| value.parse::<usize>().ok());
+    let Some(value) = value else {
+      println!("--window needs a number");
+      return 2;
+    };
+    window = value.max(1);
+    args.drain(flag..flag + 2);
+  }
+
+  let all = build_order_loader::load_build_orders_from_dir(&build_order_loader::build_order_dir());
+  let build_orders: Vec<Result<LoadedBuildOrder, String>> = if args.is_empty() {
+    all
+      .iter()
+      .filter(|build_order| !build_order.continuation_only)
+      .cloned()
+      .map(Ok)
+      .collect()
+  } else {
+    args.iter().map(|arg| load_for_cli(arg)).collect()
+  };
+
+  if build_orders.is_empty() {
+    println!("No build orders found");
+    return 1;
+  }
+
+  let runs: Vec<Result<(String, Vec<BuildOrderItem>), String>> = build_orders
+    .into_iter()
+    .flat_map(|build_order| match build_order {
+      Ok(build_order) => simulation_runs(&build_order, &all),
+      Err(e) => vec![Err(e)],
+    })
+    .collect();
+
+  let mut failed = false;
+  for run in runs {
+    match run {
+      Ok((name, items)) => {
+        let report = simulate(&name, &items, window);
+        print_report(&report);
+        failed |= report.error_count() > 0;
+      }
+      Err(e) => {
+        println!("{}", e);
+        failed = true;
+      }
+    }
+    println!();
+  }
+
+  if failed {
+    1
+  } else {
+    0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn drones(count: usize) -> Vec<BuildOrderItem> {
+    vec![BuildOrderItem::unit(UnitType::Zerg_Drone); count]
+  }
+
+  #[test]
+  fn mutalisk_without_spire_is_impossible() {
+    let mut items = drones(1);
+    items.push(BuildOrderItem::unit(UnitType::Zerg_Mutalisk));
+    let report = simulate("mutas", &items, 1);
+
+    assert!(report.items[0].start_frame.is_some());
+    let mutalisk = &report.items[1];
+    assert_eq!(mutalisk.start_frame, None);
+    assert_eq!(
+      mutalisk.issues,
+      vec![SimulationIssue::MissingRequirement(UnitType::Zerg_Spire)]
+    );
+  }
+
+  #[test]
+  fn supply_block_without_overlord_is_flagged() {
+    // 4 drones at the start, the hatchery and overlord make room for 5 more
+    let report = simulate("drones", &drones(6), 1);
+    assert_eq!(report.error_count(), 1);
+    assert!(report.items[4].start_frame.is_some());
+    assert_eq!(
+      report.items[5].issues,
+      vec![SimulationIssue::SupplyBlocked { needed: 2, free: 0 }]
+    );
+
+    let mut items = drones(5);
+    items.push(BuildOrderItem::unit(UnitType::Zerg_Overlord));
+    items.push(BuildOrderItem::unit(UnitType::Zerg_Drone));
+    let report = simulate("drones with overlord", &items, 1);
+    assert_eq!(report.error_count(), 0);
+    assert!(report.items[5].complete_frame.unwrap() <= report.items[6].start_frame.unwrap());
+  }
+
+  #[test]
+  fn item_whose_trigger_never_holds_stalls_and_the_rest_continue() {
+    let items = vec![
+      BuildOrderItem::unit(UnitType::Zerg_Drone)
+        .when(BuildOrderTrigger::completed(UnitType::Zerg_Spawning_Pool)),
+      BuildOrderItem::unit(UnitType::Zerg_Drone),
+    ];
+    let report = simulate("stall", &items, 1);
+
+    let stalled = &report.items[0];
+    assert_eq!(stalled.start_frame, None);
+    assert!(matches!(
+      stalled.issues.as_slice(),
+      [SimulationIssue::Stalled { waiting_for }] if waiting_for.contains("Zerg_Spawning_Pool")
+    ));
+    assert!(report.items[1].start_frame.unwrap() >= MAX_WAIT_FRAMES);
+  }
+}
@@ -0,0 +1,140 @@
+use rsbwapi::*;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::build_orders::build_order_item::{self, BuildOrderItemParseError};
+use crate::utils::game_state::GameState;
+
+/// Condition a build order item waits on before it is assigned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildOrderTrigger {
+  /// Supply used as shown in game (drones count as 1)
+  Supply(i32),
+  Minerals(i32),
+  Gas(i32),
+  Frame(i32),
+  Completed {
+    unit_type: UnitType,
+    count: i32,
+  },
+  /// Any enemy unit, or an enemy unit of the given type, has been seen
+  EnemySeen(Option<UnitType>),
+  All(Vec<BuildOrderTrigger>),
+}
+
+impl BuildOrderTrigger {
+  pub fn completed(unit_type: UnitType) -> Self {
+    BuildOrderTrigger::Completed {
+      unit_type,
+      count: 1,
+    }
+  }
+
+  pub fn is_satisfied(&self, game: &Game, game_state: &GameState) -> bool {
+    let Some(player) = game.self_() else {
+      return false;
+    };
+
+    match self {
+      BuildOrderTrigger::Supply(supply) => player.supply_used() / 2 >= *supply,
+      BuildOrderTrigger::Minerals(minerals) => player.minerals() >= *minerals,
+      BuildOrderTrigger::Gas(gas) => player.gas() >= *gas,
+      BuildOrderTrigger::Frame(frame) => game.get_frame_count() >= *frame,
+      BuildOrderTrigger::Completed { unit_type, count } => {
+        let completed = game
+          .get_all_units()
+          .iter()
+          .filter(|u| {
+            u.get_player().get_id() == player.get_id()
+              && u.get_type() == *unit_type
+              && u.is_completed()
+          })
+          .count() as i32;
+        completed >= *count
+      }
+      BuildOrderTrigger::EnemySeen(None) => !game_state.seen_enemy_unit_types.is_empty(),
+      BuildOrderTrigger::EnemySeen(Some(unit_type)) => {
+        game_state.seen_enemy_unit_types.contains(unit_type)
+      }
+      BuildOrderTrigger::All(triggers) => triggers
+        .iter()
+        .all(|trigger| trigger.is_satisfied(game, game_state)),
+    }
+  }
+}
+
+impl fmt::Display for BuildOrderTrigger {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BuildOrderTrigger::Supply(supply) => write!(f, "supply >= {}", supply),
+      BuildOrderTrigger::Minerals(minerals) => write!(f, "minerals >= {}", minerals),
+      BuildOrderTrigger::Gas(gas) => write!(f, "gas >= {}", gas),
+      BuildOrderTrigger::Frame(frame) => write!(f, "frame >= {}", frame),
+      BuildOrderTrigger::Completed {
+        unit_type,
+        count: 1,
+      } => write!(f, "completed {:?}", unit_type),
+      BuildOrderTrigger::Completed { unit_type, count } => {
+        write!(f, "completed {:?} >= {}", unit_type, count)
+      }
+      BuildOrderTrigger::EnemySeen(None) => write!(f, "enemy_seen"),
+      BuildOrderTrigger::EnemySeen(Some(unit_type)) => write!(f, "enemy_seen {:?}", unit_type),
+      BuildOrderTrigger::All(triggers) => {
+        let parts: Vec<String> = triggers.iter().map(|t| t.to_string()).collect();
+        write!(f, "{}", parts.join(" and "))
+      }
+    }
+  }
+}
+
+/// Parses the text written by `Display`, e.g. `supply >= 9`,
+/// `completed Zerg_Spawning_Pool` or `minerals >= 300 and frame > 4000`
+impl FromStr for BuildOrderTrigger {
+  type Err = BuildOrderItemParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let conditions: Vec<&str> = s.split(" and ").map(|c| c.trim()).collect();
+    if conditions.len() > 1 {
+      return conditions
+        .into_iter()
+        .map(parse_condition)
+        .collect::<Result<Vec<_>, _>>()
+        .map(BuildOrderTrigger::All);
+    }
+    parse_condition(s.trim())
+  }
+}
+
+fn parse_condition(s: &str) -> Result<BuildOrderTrigger, BuildOrderItemParseError> {
+  let invalid = || BuildOrderItemParseError::InvalidTrigger(s.to_string());
+  let tokens: Vec<&str> = s.split_whitespace().collect();
+
+  // `> n` is stored as `>= n + 1` since every condition is a whole number
+  let threshold = |op: &str, value: &str| -> Result<i32, BuildOrderItemParseError> {
+    let value = value.parse::<i32>().map_err(|_| invalid())?;
+    match op {
+      ">=" => Ok(value),
+      ">" => Ok(value + 1),
+      _ => Err(invalid()),
+    }
+  };
+
+  match tokens.as_slice() {
+    ["supply", op, value] => Ok(BuildOrderTrigger::Supply(threshold(op, value)?)),
+    ["minerals", op, value] => Ok(BuildOrderTrigger::Minerals(threshold(op, value)?)),
+    ["gas", op, value] => Ok(BuildOrderTrigger::Gas(threshold(op, value)?)),
+    ["frame", op, value] => Ok(BuildOrderTrigger::Frame(threshold(op, value)?)),
+    ["completed", name] => Ok(BuildOrderTrigger::completed(
+      build_order_item::parse_unit_type(name)?,
+    )),
+    ["completed", name, op, value] => Ok(BuildOrderTrigger::Completed {
+      unit_type: build_order_item::parse_unit_type(name)?,
+      count: threshold(op, value)?,
+    }),
+    ["enemy_seen"] => Ok(BuildOrderTrigger::EnemySeen(None)),
+    ["enemy_seen", name] => Ok(BuildOrderTrigger::EnemySeen(Some(
+      build_order_item::parse_unit_type(name)?,
+    ))),
+    _ => Err(invalid()),
+  }
+}
@@ -49,7 +49,7 @@ pub fn build_order() -> Vec<BuildOrderItem> {
     BuildOrderItem::unit_at_base(UnitType::Zerg_Creep_Colony, 1),
     BuildOrderItem::unit(UnitType::Zerg_Overlord),
     BuildOrderItem::unit(UnitType::Zerg_Drone),
-    // BuildOrderItem::Upgrade(UpgradeType::Metabolic_Boost),
+    BuildOrderItem::upgrade(UpgradeType::Metabolic_Boost),
     BuildOrderItem::unit(UnitType::Zerg_Drone),
     BuildOrderItem::unit(UnitType::Zerg_Drone),
     BuildOrderItem::unit_at_base(UnitType::Zerg_Spire, 0),
@@ -79,10 +79,10 @@ pub fn build_order() -> Vec<BuildOrderItem> {
     BuildOrderItem::unit(UnitType::Zerg_Mutalisk),
     BuildOrderItem::unit(UnitType::Zerg_Mutalisk),
     BuildOrderItem::unit(UnitType::Zerg_Overlord),
-    BuildOrderItem::Upgrade(UpgradeType::Zerg_Flyer_Attacks),
+    BuildOrderItem::upgrade(UpgradeType::Zerg_Flyer_Attacks),
     BuildOrderItem::unit(UnitType::Zerg_Mutalisk),
     BuildOrderItem::unit(UnitType::Zerg_Mutalisk),
-    // BuildOrderItem::Upgrade(UpgradeType::Zerg_Flyer_Attacks),
+    // BuildOrderItem::upgrade(UpgradeType::Zerg_Flyer_Attacks),
     BuildOrderItem::unit(UnitType::Zerg_Mutalisk),
     BuildOrderItem::unit(UnitType::Zerg_Hatchery), 
     BuildOrderItem::unit(UnitType::Zerg_Mutalisk),
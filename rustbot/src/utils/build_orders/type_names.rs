@@ -0,0 +1,164 @@
+use rsbwapi::*;
+
+// rsbwapi types only give us Debug names, so build order files are matched
+// against these tables. Enemy types are listed so triggers can refer to them.
+const UNIT_TYPES: &[UnitType] = &[
+  // Zerg units
+  UnitType::Zerg_Larva,
+  UnitType::Zerg_Egg,
+  UnitType::Zerg_Drone,
+  UnitType::Zerg_Overlord,
+  UnitType::Zerg_Zergling,
+  UnitType::Zerg_Hydralisk,
+  UnitType::Zerg_Lurker,
+  UnitType::Zerg_Lurker_Egg,
+  UnitType::Zerg_Mutalisk,
+  UnitType::Zerg_Cocoon,
+  UnitType::Zerg_Guardian,
+  UnitType::Zerg_Devourer,
+  UnitType::Zerg_Scourge,
+  UnitType::Zerg_Queen,
+  UnitType::Zerg_Broodling,
+  UnitType::Zerg_Infested_Terran,
+  UnitType::Zerg_Ultralisk,
+  UnitType::Zerg_Defiler,
+  // Zerg buildings
+  UnitType::Zerg_Hatchery,
+  UnitType::Zerg_Lair,
+  UnitType::Zerg_Hive,
+  UnitType::Zerg_Extractor,
+  UnitType::Zerg_Spawning_Pool,
+  UnitType::Zerg_Evolution_Chamber,
+  UnitType::Zerg_Hydralisk_Den,
+  UnitType::Zerg_Spire,
+  UnitType::Zerg_Greater_Spire,
+  UnitType::Zerg_Queens_Nest,
+  UnitType::Zerg_Ultralisk_Cavern,
+  UnitType::Zerg_Defiler_Mound,
+  UnitType::Zerg_Nydus_Canal,
+  UnitType::Zerg_Creep_Colony,
+  UnitType::Zerg_Sunken_Colony,
+  UnitType::Zerg_Spore_Colony,
+  UnitType::Zerg_Infested_Command_Center,
+  // Terran units
+  UnitType::Terran_SCV,
+  UnitType::Terran_Marine,
+  UnitType::Terran_Firebat,
+  UnitType::Terran_Medic,
+  UnitType::Terran_Ghost,
+  UnitType::Terran_Vulture,
+  UnitType::Terran_Vulture_Spider_Mine,
+  UnitType::Terran_Goliath,
+  UnitType::Terran_Siege_Tank_Tank_Mode,
+  UnitType::Terran_Siege_Tank_Siege_Mode,
+  UnitType::Terran_Wraith,
+  UnitType::Terran_Dropship,
+  UnitType::Terran_Science_Vessel,
+  UnitType::Terran_Valkyrie,
+  UnitType::Terran_Battlecruiser,
+  // Terran buildings
+  UnitType::Terran_Command_Center,
+  UnitType::Terran_Comsat_Station,
+  UnitType::Terran_Nuclear_Silo,
+  UnitType::Terran_Supply_Depot,
+  UnitType::Terran_Refinery,
+  UnitType::Terran_Barracks,
+  UnitType::Terran_Academy,
+  UnitType::Terran_Factory,
+  UnitType::Terran_Machine_Shop,
+  UnitType::Terran_Starport,
+  UnitType::Terran_Control_Tower,
+  UnitType::Terran_Science_Facility,
+  UnitType::Terran_Covert_Ops,
+  UnitType::Terran_Physics_Lab,
+  UnitType::Terran_Engineering_Bay,
+  UnitType::Terran_Armory,
+  UnitType::Terran_Missile_Turret,
+  UnitType::Terran_Bunker,
+  // Protoss units
+  UnitType::Protoss_Probe,
+  UnitType::Protoss_Zealot,
+  UnitType::Protoss_Dragoon,
+  UnitType::Protoss_High_Templar,
+  UnitType::Protoss_Dark_Templar,
+  UnitType::Protoss_Archon,
+  UnitType::Protoss_Dark_Archon,
+  UnitType::Protoss_Reaver,
+  UnitType::Protoss_Scarab,
+  UnitType::Protoss_Shuttle,
+  UnitType::Protoss_Observer,
+  UnitType::Protoss_Scout,
+  UnitType::Protoss_Corsair,
+  UnitType::Protoss_Carrier,
+  UnitType::Protoss_Interceptor,
+  UnitType::Protoss_Arbiter,
+  // Protoss buildings
+  UnitType::Protoss_Nexus,
+  UnitType::Protoss_Pylon,
+  UnitType::Protoss_Assimilator,
+  UnitType::Protoss_Gateway,
+  UnitType::Protoss_Forge,
+  UnitType::Protoss_Photon_Cannon,
+  UnitType::Protoss_Cybernetics_Core,
+  UnitType::Protoss_Shield_Battery,
+  UnitType::Protoss_Robotics_Facility,
+  UnitType::Protoss_Robotics_Support_Bay,
+  UnitType::Protoss_Observatory,
+  UnitType::Protoss_Stargate,
+  UnitType::Protoss_Fleet_Beacon,
+  UnitType::Protoss_Citadel_of_Adun,
+  UnitType::Protoss_Templar_Archives,
+  UnitType::Protoss_Arbiter_Tribunal,
+];
+
+const UPGRADE_TYPES: &[UpgradeType] = &[
+  UpgradeType::Zerg_Carapace,
+  UpgradeType::Zerg_Flyer_Carapace,
+  UpgradeType::Zerg_Melee_Attacks,
+  UpgradeType::Zerg_Missile_Attacks,
+  UpgradeType::Zerg_Flyer_Attacks,
+  UpgradeType::Ventral_Sacs,
+  UpgradeType::Antennae,
+  UpgradeType::Pneumatized_Carapace,
+  UpgradeType::Metabolic_Boost,
+  UpgradeType::Adrenal_Glands,
+  UpgradeType::Muscular_Augments,
+  UpgradeType::Grooved_Spines,
+  UpgradeType::Gamete_Meiosis,
+  UpgradeType::Metasynaptic_Node,
+  UpgradeType::Chitinous_Plating,
+  UpgradeType::Anabolic_Synthesis,
+];
+
+const TECH_TYPES: &[TechType] = &[
+  TechType::Burrowing,
+  TechType::Lurker_Aspect,
+  TechType::Spawn_Broodlings,
+  TechType::Ensnare,
+  TechType::Consume,
+  TechType::Plague,
+  TechType::Infestation,
+  TechType::Dark_Swarm,
+  TechType::Parasite,
+];
+
+pub fn unit_type_from_name(name: &str) -> Option<UnitType> {
+  UNIT_TYPES
+    .iter()
+    .find(|unit_type| format!("{:?}", unit_type) == name)
+    .copied()
+}
+
+pub fn upgrade_type_from_name(name: &str) -> Option<UpgradeType> {
+  UPGRADE_TYPES
+    .iter()
+    .find(|upgrade_type| format!("{:?}", upgrade_type) == name)
+    .copied()
+}
+
+pub fn tech_type_from_name(name: &str) -> Option<TechType> {
+  TECH_TYPES
+    .iter()
+    .find(|tech_type| format!("{:?}", tech_type) == name)
+    .copied()
+}
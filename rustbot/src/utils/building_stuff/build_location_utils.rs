@@ -1,41 +1,96 @@
 use rsbwapi::*;
 
+use crate::utils::game_state::GameState;
+use crate::utils::map_utils::creep_map::CreepMap;
+use crate::utils::map_utils::geometry::{footprints_overlap, pixel_distance, BASE_RESOURCE_RADIUS};
+use crate::utils::map_utils::tile_reservations::{TileReservation, TileReservations};
+
+/// Sites that only get creep later score this much lower than sites with creep already
+const FUTURE_CREEP_PENALTY: i32 = 200;
+
+/// The parts of the game state picking a build site reads, borrowed one by one so callers can
+/// still change other parts of the state, e.g. the assignment of the drone that builds
+#[derive(Clone, Copy)]
+pub struct PlacementContext<'a> {
+  pub base_locations: &'a [TilePosition],
+  pub creep_map: &'a CreepMap,
+  pub reservations: &'a TileReservations,
+}
+
+impl<'a> PlacementContext<'a> {
+  pub fn new(game_state: &'a GameState) -> Self {
+    Self {
+      base_locations: &game_state.base_locations,
+      creep_map: &game_state.creep_map,
+      reservations: &game_state.tile_reservations,
+    }
+  }
+}
+
 pub fn get_buildable_location(
   game: &Game,
   builder: &Unit,
   unit_type: UnitType,
-  base_locations: &[TilePosition],
   base_index: Option<usize>,
+  placement: &PlacementContext,
 ) -> Option<TilePosition> {
+  get_buildable_location_avoiding(game, builder, unit_type, base_index, placement, &[])
+}
+
+/// Same as `get_buildable_location`, but skips sites overlapping any of `avoid`, e.g. sites a
+/// builder already failed to place on
+pub fn get_buildable_location_avoiding(
+  game: &Game,
+  builder: &Unit,
+  unit_type: UnitType,
+  base_index: Option<usize>,
+  placement: &PlacementContext,
+  avoid: &[TilePosition],
+) -> Option<TilePosition> {
+  let overlaps_avoided = |tile: &TilePosition| {
+    avoid.iter().any(|avoided| {
+      (tile.x - avoided.x).abs() < unit_type.tile_width()
+        && (tile.y - avoided.y).abs() < unit_type.tile_height()
+    })
+  };
+
   if is_extractor_type(unit_type) {
-    return find_extractor_location(game, builder, unit_type, base_locations, base_index);
+    return find_extractor_location(
+      game,
+      builder,
+      unit_type,
+      placement.base_locations,
+      base_index,
+    )
+    .filter(|tile| !overlaps_avoided(tile));
   }
 
-  let player = game.self_()?;
   let base_index = base_index.unwrap_or(0);
-  let base_tile = base_locations.get(base_index)?;
+  let base_tile = placement.base_locations.get(base_index)?;
   let search_center_pos = Position::new(base_tile.x * 32, base_tile.y * 32);
   let search_radius = 15;
 
-  let resource_depots = collect_resource_depots(&player);
-  let all_minerals = game.get_static_minerals();
-  let all_geysers = game.get_static_geysers();
-
   let scored_locations = find_all_buildable_tiles(
     game,
     builder,
     unit_type,
     search_center_pos,
     search_radius,
-    &resource_depots,
-    &all_minerals,
-    &all_geysers,
-  );
+    placement,
+  )?;
+
+  let mut candidates: Vec<(TilePosition, i32)> = scored_locations
+    .into_iter()
+    .filter(|(pos, _)| !overlaps_avoided(pos))
+    .filter(|(pos, _)| !placement.reservations.is_reserved(*pos, unit_type, &[]))
+    .collect();
+  candidates.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
 
-  scored_locations
+  // Checking paths is a flood fill over the map, so only until a site passes
+  candidates
     .into_iter()
-    .max_by_key(|(_, score)| *score)
     .map(|(pos, _)| pos)
+    .find(|pos| placement.reservations.keeps_paths_open(*pos, unit_type))
 }
 
 fn is_extractor_type(unit_type: UnitType) -> bool {
@@ -58,10 +113,12 @@ fn find_all_buildable_tiles(
   unit_type: UnitType,
   search_center_pos: Position,
   search_radius: i32,
-  resource_depots: &[Unit],
-  all_minerals: &[Unit],
-  all_geysers: &[Unit],
-) -> Vec<(TilePosition, i32)> {
+  placement: &PlacementContext,
+) -> Option<Vec<(TilePosition, i32)>> {
+  let player = game.self_()?;
+  let resource_depots = collect_resource_depots(&player);
+  let all_minerals = game.get_static_minerals();
+  let all_geysers = game.get_static_geysers();
   let mut buildable_locations = Vec::new();
 
   for dy in -search_radius..=search_radius {
@@ -71,17 +128,19 @@ fn find_all_buildable_tiles(
         y: search_center_pos.y / 32 + dy,
       };
 
+      let mut score = score_build_location(tile_pos, &resource_depots, &all_minerals, &all_geysers);
       if !is_tile_buildable(game, builder, tile_pos, unit_type) {
-        continue;
+        if !is_buildable_on_future_creep(game, builder, tile_pos, unit_type, placement, &[]) {
+          continue;
+        }
+        score -= FUTURE_CREEP_PENALTY;
       }
 
-      let score = score_build_location(tile_pos, resource_depots, all_minerals, all_geysers);
-
       buildable_locations.push((tile_pos, score));
     }
   }
 
-  buildable_locations
+  Some(buildable_locations)
 }
 
 fn is_tile_buildable(
@@ -95,6 +154,94 @@ fn is_tile_buildable(
     .unwrap_or(false)
 }
 
+/// Whether `builder` can place `unit_type` at `tile_pos` now, or by the time it gets there once
+/// creep has spread. Reserved tiles other than `allowed` ones only count for the latter, the
+/// caller checks them for sites placeable now.
+pub fn is_site_buildable(
+  game: &Game,
+  builder: &Unit,
+  tile_pos: TilePosition,
+  unit_type: UnitType,
+  placement: &PlacementContext,
+  allowed: &[TileReservation],
+) -> bool {
+  is_tile_buildable(game, builder, tile_pos, unit_type)
+    || is_buildable_on_future_creep(game, builder, tile_pos, unit_type, placement, allowed)
+}
+
+/// Zerg buildings other than hatcheries and extractors have to go on creep
+fn needs_creep(unit_type: UnitType) -> bool {
+  unit_type.get_race() == Race::Zerg
+    && unit_type.is_building()
+    && unit_type != UnitType::Zerg_Hatchery
+    && unit_type != UnitType::Zerg_Extractor
+}
+
+/// Free, unreserved buildable ground still waiting for creep on part of it, where creep is
+/// forecast to cover the whole footprint by the time the builder gets there. A footprint that
+/// already has creep everywhere failed `can_build_here` for some other reason. Units walking
+/// over it are only found out once the drone arrives.
+fn is_buildable_on_future_creep(
+  game: &Game,
+  builder: &Unit,
+  tile_pos: TilePosition,
+  unit_type: UnitType,
+  placement: &PlacementContext,
+  allowed: &[TileReservation],
+) -> bool {
+  if !needs_creep(unit_type) {
+    return false;
+  }
+  let footprint: Vec<TilePosition> = (0..unit_type.tile_height())
+    .flat_map(|dy| {
+      (0..unit_type.tile_width()).map(move |dx| TilePosition {
+        x: tile_pos.x + dx,
+        y: tile_pos.y + dy,
+      })
+    })
+    .collect();
+  let PlacementContext {
+    creep_map,
+    reservations,
+    ..
+  } = placement;
+  if !footprint.iter().all(|tile| game.is_buildable(*tile))
+    || footprint.iter().all(|tile| creep_map.has_creep(*tile))
+    || reservations.is_reserved(tile_pos, unit_type, allowed)
+  {
+    return false;
+  }
+  let Some(creep_frame) = creep_map.footprint_creep_frame(tile_pos, unit_type) else {
+    return false;
+  };
+  let speed = builder.get_type().top_speed().max(1.0) as f32;
+  let travel_frames =
+    (pixel_distance(builder.get_position(), tile_to_pixel_position(tile_pos)) / speed) as i32;
+  creep_frame <= game.get_frame_count() + travel_frames
+    && !is_footprint_occupied(game, builder, tile_pos, unit_type)
+}
+
+/// Whether a building, mineral field or geyser other than `builder` covers part of the footprint
+fn is_footprint_occupied(
+  game: &Game,
+  builder: &Unit,
+  tile_pos: TilePosition,
+  unit_type: UnitType,
+) -> bool {
+  game.get_all_units().iter().any(|unit| {
+    let other_type = unit.get_type();
+    unit.get_id() != builder.get_id()
+      && !unit.is_flying()
+      && (other_type.is_building() || other_type.is_resource_container())
+      && footprints_overlap(
+        tile_pos,
+        (unit_type.tile_width(), unit_type.tile_height()),
+        unit.get_tile_position(),
+        (other_type.tile_width(), other_type.tile_height()),
+      )
+  })
+}
+
 fn score_build_location(
   tile_pos: TilePosition,
   resource_depots: &[Unit],
@@ -158,7 +305,7 @@ fn penalize_mineral_line_blocking(
 }
 
 fn score_depot_distance(location: Position, depot_pos: Position) -> i32 {
-  let distance = calculate_distance(location, depot_pos);
+  let distance = pixel_distance(location, depot_pos);
 
   // Prefer locations 4-12 tiles away from depot
   if distance < 4.0 * 32.0 {
@@ -199,12 +346,6 @@ fn penalize_depot_resource_paths(
   penalty
 }
 
-fn calculate_distance(pos1: Position, pos2: Position) -> f32 {
-  let dx = (pos1.x - pos2.x) as f32;
-  let dy = (pos1.y - pos2.y) as f32;
-  (dx * dx + dy * dy).sqrt()
-}
-
 /// Check if point C is roughly between points A and B within a tolerance
 fn is_between(c: Position, a: Position, b: Position, tolerance: f32) -> bool {
   // Calculate distances
@@ -276,20 +417,13 @@ pub fn find_extractor_location(
       
       // If a specific base is requested, only check that base
       if let Some(base_pos) = target_base_pos {
-        let dx = (base_pos.x - geyser_pos.x) as f32;
-        let dy = (base_pos.y - geyser_pos.y) as f32;
-        let distance = (dx * dx + dy * dy).sqrt();
-        return distance <= 12.0 * 32.0;
+        return pixel_distance(base_pos, geyser_pos) <= BASE_RESOURCE_RADIUS;
       }
       
       // Otherwise check if geyser is near any of our bases
-      resource_depots.iter().any(|depot| {
-        let depot_pos = depot.get_position();
-        let dx = (depot_pos.x - geyser_pos.x) as f32;
-        let dy = (depot_pos.y - geyser_pos.y) as f32;
-        let distance = (dx * dx + dy * dy).sqrt();
-        distance <= 12.0 * 32.0
-      })
+      resource_depots
+        .iter()
+        .any(|depot| pixel_distance(depot.get_position(), geyser_pos) <= BASE_RESOURCE_RADIUS)
     })
     .collect();
 
@@ -0,0 +1,453 @@
+use rsbwapi::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::utils::build_order_management;
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::building_stuff::build_location_utils::{self, PlacementContext};
+use crate::utils::building_stuff::defense_placement;
+use crate::utils::game_state::{GameState, WorkerAssignmentType};
+use crate::utils::map_utils::geometry::{footprint_center, pixel_distance};
+use crate::utils::military::worker_defense;
+use crate::utils::resource_reservations;
+
+/// Builders have to get this much closer to their site to count as making progress
+const PROGRESS_DISTANCE: f32 = 32.0;
+/// Frames a travelling builder may go without getting closer before it counts as stuck
+const STUCK_FRAMES: i32 = 24 * 8;
+/// Frames a builder may have the money for its item before the site is given up on
+const READY_TIMEOUT_FRAMES: i32 = 24 * 45;
+/// Frames the site may refuse the building while the builder stands next to it
+const BLOCKED_FRAMES: i32 = 24 * 3;
+/// Builders this close to their site should be able to place on it
+const AT_SITE_DISTANCE: f32 = 4.0 * 32.0;
+/// Builders hurt below this go back to mining when attacked
+const THREATENED_HP: i32 = 25;
+/// Enemies this close to a drone make it a poor pick for a builder
+const SAFE_DISTANCE: f32 = 8.0 * 32.0;
+/// Stuck builders before the site itself is given up on
+const STUCK_BEFORE_RELOCATING: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuilderState {
+  Travelling,
+  WaitingForMoney,
+  /// At the site with the money, waiting for it to be placeable
+  AtSite,
+  Placing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuilderFailure {
+  Killed,
+  Threatened,
+  Stuck,
+  TimedOut,
+  SiteBlocked,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BuilderFailureRecord {
+  pub frame: i32,
+  pub drone_id: usize,
+  pub site: Option<(i32, i32)>,
+  pub failure: BuilderFailure,
+}
+
+/// Build order index, drone, site and what went wrong, for a builder that failed this frame
+type FailedBuilder = (usize, usize, Option<(i32, i32)>, BuilderFailure);
+
+#[derive(Debug, Clone, Copy)]
+pub struct TrackedBuilder {
+  pub drone_id: usize,
+  pub state: BuilderState,
+  pub assigned_frame: i32,
+  pub closest_distance: f32,
+  pub last_progress_frame: i32,
+  /// Frames at the site with the money, since the builder last arrived
+  pub ready_frames: i32,
+  pub blocked_frames: i32,
+}
+
+/// Progress of every drone with a building assignment, and what went wrong for earlier ones
+#[derive(Debug, Clone, Default)]
+pub struct BuilderTracking {
+  /// Keyed by build order index
+  pub builders: HashMap<usize, TrackedBuilder>,
+  /// Keyed by build order index
+  pub failures: HashMap<usize, Vec<BuilderFailureRecord>>,
+  /// Sites for items whose builder was sent back, for the next builder to use
+  pub pending_sites: HashMap<usize, (i32, i32)>,
+}
+
+impl BuilderTracking {
+  /// Drones that already failed to build the item
+  pub fn failed_drones(&self, build_idx: usize) -> impl Iterator<Item = usize> + '_ {
+    self
+      .failures
+      .get(&build_idx)
+      .into_iter()
+      .flatten()
+      .map(|record| record.drone_id)
+  }
+
+  /// Follows items to their new index after a build order edit, dropping removed ones
+  pub fn remap_items(&mut self, new_index: impl Fn(usize) -> Option<usize> + Copy) {
+    build_order_management::rekey_items(&mut self.builders, new_index);
+    build_order_management::rekey_items(&mut self.failures, new_index);
+    build_order_management::rekey_items(&mut self.pending_sites, new_index);
+  }
+
+  fn record(&mut self, build_idx: usize, record: BuilderFailureRecord) {
+    self.failures.entry(build_idx).or_default().push(record);
+  }
+
+  fn sites_to_avoid(&self, build_idx: usize) -> Vec<TilePosition> {
+    self
+      .failures
+      .get(&build_idx)
+      .into_iter()
+      .flatten()
+      .filter(|record| {
+        matches!(
+          record.failure,
+          BuilderFailure::SiteBlocked | BuilderFailure::TimedOut | BuilderFailure::Stuck
+        )
+      })
+      .filter_map(|record| record.site)
+      .map(|(x, y)| TilePosition::new(x, y))
+      .collect()
+  }
+
+  fn count(&self, build_idx: usize, failure: BuilderFailure) -> usize {
+    self.failures.get(&build_idx).map_or(0, |records| {
+      records.iter().filter(|r| r.failure == failure).count()
+    })
+  }
+}
+
+/// Whether a drone is fit to be sent off building: not fighting, not being shot at and not
+/// near enemies
+pub fn is_safe_builder(game: &Game, game_state: &GameState, drone: &Unit) -> bool {
+  let Some(player) = game.self_() else {
+    return false;
+  };
+  if drone.is_under_attack() || game_state.worker_defense.is_pulled(drone.get_id()) {
+    return false;
+  }
+  !game.get_all_units().iter().any(|u| {
+    player.is_enemy(&u.get_player())
+      && worker_defense::is_threat(u)
+      && pixel_distance(u.get_position(), drone.get_position()) <= SAFE_DISTANCE
+  })
+}
+
+/// Follows every builder, and when one is killed, threatened, stuck, or can't place its building
+/// records the failure for its item. Threatened and stuck drones go back to mining so a safer
+/// drone gets picked, blocked or timed out sites are swapped for another one.
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let Some(player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+
+  let building: HashMap<usize, (usize, Option<(i32, i32)>)> = game_state
+    .worker_assignments
+    .iter()
+    .filter(|(_, a)| a.assignment_type == WorkerAssignmentType::Building)
+    .filter_map(|(drone_id, a)| Some((a.build_order_index?, (*drone_id, a.target_position))))
+    .collect();
+
+  // Builders whose assignment went away: started, released or dead
+  let gone: Vec<(usize, TrackedBuilder)> = game_state
+    .builder_tracking
+    .builders
+    .iter()
+    .filter(|(idx, tracked)| building.get(idx).map(|(id, _)| *id) != Some(tracked.drone_id))
+    .map(|(idx, tracked)| (*idx, *tracked))
+    .collect();
+  for (build_idx, tracked) in gone {
+    game_state.builder_tracking.builders.remove(&build_idx);
+    let unstarted = game_state
+      .build_order_status
+      .get(build_idx)
+      .is_some_and(|status| status.is_unstarted());
+    let dead = game
+      .get_unit(tracked.drone_id)
+      .is_none_or(|drone| !drone.exists());
+    if unstarted && dead {
+      record_failure(
+        game_state,
+        build_idx,
+        tracked.drone_id,
+        None,
+        BuilderFailure::Killed,
+        frame,
+      );
+    }
+  }
+
+  let mut failed: Vec<FailedBuilder> = Vec::new();
+
+  for (&build_idx, &(drone_id, site)) in &building {
+    let Some(drone) = game.get_unit(drone_id) else {
+      continue;
+    };
+    let Some(BuildOrderItem::Unit {
+      unit_type: building_type,
+      ..
+    }) = game_state.build_order.get(build_idx)
+    else {
+      continue;
+    };
+    let building_type = *building_type;
+
+    let distance = site.map_or(f32::MAX, |site| {
+      pixel_distance(
+        drone.get_position(),
+        footprint_center(TilePosition::new(site.0, site.1), building_type),
+      )
+    });
+    let ready = player.minerals() >= building_type.mineral_price()
+      && player.gas() >= building_type.gas_price()
+      && resource_reservations::can_spend(game, game_state, build_idx);
+    let tracked = game_state
+      .builder_tracking
+      .builders
+      .entry(build_idx)
+      .or_insert(TrackedBuilder {
+        drone_id,
+        state: BuilderState::Travelling,
+        assigned_frame: frame,
+        closest_distance: distance,
+        last_progress_frame: frame,
+        ready_frames: 0,
+        blocked_frames: 0,
+      });
+
+    let order = drone.get_order();
+    if order == Order::PlaceBuilding
+      || order == Order::ConstructingBuilding
+      || order == Order::DroneBuild
+    {
+      tracked.state = BuilderState::Placing;
+      tracked.last_progress_frame = frame;
+      continue;
+    }
+
+    if drone.is_under_attack() && drone.get_hit_points() < THREATENED_HP {
+      failed.push((build_idx, drone_id, site, BuilderFailure::Threatened));
+      continue;
+    }
+
+    if distance < tracked.closest_distance - PROGRESS_DISTANCE {
+      tracked.closest_distance = distance;
+      tracked.last_progress_frame = frame;
+    }
+
+    if distance > AT_SITE_DISTANCE {
+      tracked.state = BuilderState::Travelling;
+      if frame - tracked.last_progress_frame > STUCK_FRAMES {
+        failed.push((build_idx, drone_id, site, BuilderFailure::Stuck));
+        continue;
+      }
+    } else if !ready {
+      if tracked.state == BuilderState::Travelling {
+        tracked.ready_frames = 0;
+      }
+      tracked.state = BuilderState::WaitingForMoney;
+      tracked.last_progress_frame = frame;
+    } else {
+      // A long walk doesn't count towards the timeout, only time spent at the site
+      if tracked.state == BuilderState::Travelling {
+        tracked.ready_frames = 0;
+      }
+      tracked.state = BuilderState::AtSite;
+      tracked.last_progress_frame = frame;
+      tracked.ready_frames += 1;
+      // Only errors count, `Ok(false)` is a site the drone hasn't seen yet
+      let blocked = site.is_some_and(|(x, y)| {
+        game
+          .can_build_here(&drone, TilePosition::new(x, y), building_type, true)
+          .is_err()
+      });
+      tracked.blocked_frames = if blocked {
+        tracked.blocked_frames + 1
+      } else {
+        0
+      };
+      if tracked.blocked_frames > BLOCKED_FRAMES {
+        failed.push((build_idx, drone_id, site, BuilderFailure::SiteBlocked));
+        continue;
+      }
+    }
+
+    if tracked.ready_frames > READY_TIMEOUT_FRAMES {
+      failed.push((build_idx, drone_id, site, BuilderFailure::TimedOut));
+    }
+  }
+
+  for (build_idx, drone_id, site, failure) in failed {
+    game_state.builder_tracking.builders.remove(&build_idx);
+    record_failure(game_state, build_idx, drone_id, site, failure, frame);
+
+    let relocate = match failure {
+      BuilderFailure::SiteBlocked | BuilderFailure::TimedOut => true,
+      BuilderFailure::Stuck => {
+        game_state
+          .builder_tracking
+          .count(build_idx, BuilderFailure::Stuck)
+          >= STUCK_BEFORE_RELOCATING
+      }
+      BuilderFailure::Killed | BuilderFailure::Threatened => false,
+    };
+    let keeps_drone = matches!(
+      failure,
+      BuilderFailure::SiteBlocked | BuilderFailure::TimedOut
+    );
+
+    if relocate {
+      relocate_site(game, game_state, build_idx, drone_id);
+    }
+    if !keeps_drone {
+      // Back to mining, a safer drone is picked for the item on the next frame
+      let site = game_state
+        .worker_assignments
+        .remove(&drone_id)
+        .and_then(|a| a.target_position);
+      if let Some(drone) = game.get_unit(drone_id) {
+        let _ = drone.stop();
+      }
+      if let (Some(site), false) = (site, relocate) {
+        game_state
+          .builder_tracking
+          .pending_sites
+          .insert(build_idx, site);
+      }
+    }
+  }
+}
+
+fn record_failure(
+  game_state: &mut GameState,
+  build_idx: usize,
+  drone_id: usize,
+  site: Option<(i32, i32)>,
+  failure: BuilderFailure,
+  frame: i32,
+) {
+  println!(
+    "[BUILDERS] item {} ({}) builder {} {:?} at site {:?}, frame {}",
+    build_idx,
+    game_state
+      .build_order
+      .get(build_idx)
+      .map_or("?".to_string(), |item| item.describe()),
+    drone_id,
+    failure,
+    site,
+    frame
+  );
+  game_state.builder_tracking.record(
+    build_idx,
+    BuilderFailureRecord {
+      frame,
+      drone_id,
+      site,
+      failure,
+    },
+  );
+}
+
+/// Moves the item's site somewhere no builder failed yet. Hatcheries meant for a base go to the
+/// closest free base instead, and the item's `base_index` moves with them.
+fn relocate_site(game: &Game, game_state: &mut GameState, build_idx: usize, drone_id: usize) {
+  let Some(BuildOrderItem::Unit {
+    unit_type,
+    base_index,
+    ..
+  }) = game_state.build_order.get(build_idx).cloned()
+  else {
+    return;
+  };
+  let Some(drone) = game.get_unit(drone_id) else {
+    return;
+  };
+  let avoid = game_state.builder_tracking.sites_to_avoid(build_idx);
+
+  let new_site = if unit_type == UnitType::Zerg_Hatchery && base_index.is_some() {
+    let failed_at = avoid.last().copied().unwrap_or(drone.get_tile_position());
+    let new_base = game_state
+      .bases
+      .iter()
+      .filter(|base| base.is_free())
+      .filter(|base| !avoid.contains(&base.tile))
+      .min_by_key(|base| {
+        let dx = base.tile.x - failed_at.x;
+        let dy = base.tile.y - failed_at.y;
+        dx * dx + dy * dy
+      })
+      .map(|base| (base.index, base.tile));
+    if let Some((new_index, _)) = new_base {
+      move_item_to_base(game_state, build_idx, new_index);
+    }
+    new_base.map(|(_, tile)| tile)
+  } else {
+    let planned_site = if unit_type == UnitType::Zerg_Creep_Colony {
+      let purpose = defense_placement::plan_colony_purpose(game_state, build_idx);
+      defense_placement::next_site(
+        game,
+        game_state,
+        &drone,
+        base_index.unwrap_or(0),
+        purpose,
+        &avoid,
+      )
+    } else {
+      None
+    };
+    planned_site.or_else(|| {
+      build_location_utils::get_buildable_location_avoiding(
+        game,
+        &drone,
+        unit_type,
+        base_index,
+        &PlacementContext::new(game_state),
+        &avoid,
+      )
+    })
+  };
+
+  let Some(new_site) = new_site else {
+    println!(
+      "[BUILDERS] item {} has no other site for {:?}, keeping the old one",
+      build_idx, unit_type
+    );
+    return;
+  };
+  println!(
+    "[BUILDERS] item {} moves to site ({}, {})",
+    build_idx, new_site.x, new_site.y
+  );
+  game_state
+    .builder_tracking
+    .pending_sites
+    .insert(build_idx, (new_site.x, new_site.y));
+  if let Some(assignment) = game_state.worker_assignments.get_mut(&drone_id) {
+    assignment.target_position = Some((new_site.x, new_site.y));
+  }
+}
+
+/// Points a base-tied item at the base its hatchery moved to, so a builder that has to find the
+/// site again doesn't go back to the old base
+fn move_item_to_base(game_state: &mut GameState, build_idx: usize, new_index: usize) {
+  if let Some(BuildOrderItem::Unit { base_index, .. }) = game_state.build_order.get_mut(build_idx) {
+    if *base_index != Some(new_index) {
+      println!(
+        "[BUILDERS] item {} moves from base {:?} to base {}",
+        build_idx, base_index, new_index
+      );
+      *base_index = Some(new_index);
+    }
+  }
+}
@@ -1,10 +1,42 @@
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
 use crate::utils::game_state::*;
+use crate::utils::map_utils::geometry::pixel_distance;
+use crate::utils::rally_points;
 use rsbwapi::*;
+use std::cmp::Reverse;
+use std::collections::HashMap;
 
-pub fn assign_larva_to_build_current_index(
+/// A hatchery stops making larvae once it has this many
+const MAX_BANKED_LARVAE: usize = 3;
+
+/// Where a unit is wanted once it hatches: drones at the base short of the most drones, army
+/// at the rally point of the squad it will join. `None` when any hatchery will do.
+fn larva_destination(game_state: &GameState, unit_type: UnitType) -> Option<(Position, String)> {
+  if unit_type.is_worker() {
+    let base = game_state
+      .base_saturation
+      .iter()
+      .filter(|saturation| saturation.unmet() > 0)
+      .max_by_key(|saturation| saturation.unmet())?;
+    let center = game_state.bases.get(base.base_index)?.center();
+    return Some((
+      center,
+      format!("base {} is {} drones short", base.base_index, base.unmet()),
+    ));
+  }
+  if unit_type == UnitType::Zerg_Overlord {
+    return None;
+  }
+  let squad = game_state.military_squads.last()?;
+  let rally = rally_points::army_rally_point(game_state)?;
+  Some((rally, format!("squad {} rally", squad.name)))
+}
+
+pub fn assign_larva_to_build_index(
   game: &Game,
   game_state: &mut GameState,
   player: &Player,
+  build_idx: usize,
 ) {
   let larva_units: Vec<Unit> = game
     .get_all_units()
@@ -17,33 +49,97 @@ pub fn assign_larva_to_build_current_index(
     return;
   }
 
-  // Find a larva that doesn't have a responsibility yet
-  let available_larva = larva_units.iter().find(|larva| {
-    !game_state
-      .larva_responsibilities
-      .contains_key(&larva.get_id())
-  });
+  // Larvae that don't have a responsibility yet, with the hatchery they belong to
+  let available_larvae: Vec<(&Unit, Option<Unit>)> = larva_units
+    .iter()
+    .filter(|larva| {
+      !game_state
+        .larva_responsibilities
+        .contains_key(&larva.get_id())
+    })
+    .map(|larva| (larva, larva.get_hatchery()))
+    .collect();
 
-  let Some(larva) = available_larva else {
+  if available_larvae.is_empty() {
     game.draw_text_screen((0, 30), "all larva are assigned tasks");
     return;
+  }
+
+  // Every larva counts towards the bank, a hatchery stops at 3 whether they are assigned or not
+  let mut banked: HashMap<Option<usize>, usize> = HashMap::new();
+  for larva in &larva_units {
+    *banked
+      .entry(larva.get_hatchery().map(|h| h.get_id()))
+      .or_insert(0) += 1;
+  }
+
+  let unit_type = match game_state.build_order.get(build_idx) {
+    Some(BuildOrderItem::Unit { unit_type, .. }) => Some(*unit_type),
+    _ => None,
+  };
+  let destination = unit_type.and_then(|unit_type| larva_destination(game_state, unit_type));
+  let threatened = |hatchery: &Option<Unit>| {
+    hatchery.as_ref().is_some_and(|hatchery| {
+      game_state
+        .bases
+        .iter()
+        .find(|base| base.depot_id == Some(hatchery.get_id()))
+        .is_some_and(|base| game_state.worker_defense.threats.contains_key(&base.index))
+    })
+  };
+
+  // Away from fighting first, then full hatcheries so no larva is wasted, then closest to
+  // where the unit is going, then the hatchery with the most larvae
+  let Some((larva, hatchery)) = available_larvae.iter().min_by_key(|(larva, hatchery)| {
+    let hatchery_id = hatchery.as_ref().map(|h| h.get_id());
+    let larvae = banked.get(&hatchery_id).copied().unwrap_or(0);
+    let distance = destination.as_ref().map_or(0, |(position, _)| {
+      pixel_distance(larva.get_position(), *position) as i32
+    });
+    (
+      threatened(hatchery),
+      larvae < MAX_BANKED_LARVAE,
+      distance,
+      Reverse(larvae),
+    )
+  }) else {
+    return;
   };
 
   let larva_id = larva.get_id();
-  let current_build_idx = game_state.build_order_index;
+  let hatchery_id = hatchery.as_ref().map(|h| h.get_id());
+  let reason = format!(
+    "hatchery {} with {} larvae{}",
+    hatchery_id.map_or("?".to_string(), |id| id.to_string()),
+    banked.get(&hatchery_id).copied().unwrap_or(0),
+    destination
+      .as_ref()
+      .map_or(String::new(), |(_, why)| format!(", {}", why))
+  );
 
   game_state
     .larva_responsibilities
-    .insert(larva_id, current_build_idx);
+    .insert(larva_id, build_idx);
+  if let (Some(hatchery_id), Some(unit_type)) = (hatchery_id, unit_type) {
+    rally_points::note_larva_use(game_state, hatchery_id, unit_type);
+  }
+  let responsibilities = &game_state.larva_responsibilities;
+  game_state
+    .larva_selection_reasons
+    .retain(|id, _| responsibilities.contains_key(id));
+  game_state
+    .larva_selection_reasons
+    .insert(larva_id, reason.clone());
   
   println!(
-    "Assigned larva {} to build order index {}",
-    larva_id, current_build_idx
+    "Assigned larva {} to build order index {} ({})",
+    larva_id, build_idx, reason
   );
 }
 
 pub fn remove_larva_responsibility(game_state: &mut GameState, unit: &Unit) {
   let unit_id = unit.get_id();
+  game_state.larva_selection_reasons.remove(&unit_id);
   if game_state.larva_responsibilities.remove(&unit_id).is_some() {
     println!(
       "Removed larva responsibility for unit {} (finished morphing into {:?})",
@@ -58,4 +154,3 @@ pub fn remove_larva_responsibility(game_state: &mut GameState, unit: &Unit) {
     );
   }
 }
-
@@ -0,0 +1,410 @@
+use rsbwapi::*;
+use std::collections::HashMap;
+
+use crate::utils::base_registry::Base;
+use crate::utils::build_order_management;
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::building_stuff::build_location_utils::{self, PlacementContext};
+use crate::utils::game_state::GameState;
+use crate::utils::map_utils::geometry::{
+  base_center, footprint_center, footprints_overlap, pixel_distance,
+};
+use crate::utils::map_utils::region_stuff;
+use crate::utils::map_utils::tile_reservations::{TileReservation, TileReservations};
+use crate::utils::scouting;
+
+/// Sunken sites planned per base
+const MAX_SUNKENS: usize = 4;
+/// Spore sites planned per base
+const MAX_SPORES: usize = 2;
+/// How far from the hatchery towards the entrance the sunkens are centred, close enough that
+/// they also cover the hatchery
+const SUNKEN_ANCHOR_DISTANCE: f32 = 5.0 * 32.0;
+/// Sites are picked this many tiles around their anchor at most
+const SITE_SEARCH_RADIUS: i32 = 4;
+/// Both sunkens and spores reach 7 tiles
+const COLONY_RANGE: f32 = 7.0 * 32.0;
+/// Chokes further than this from the base don't tell which way attacks come from
+const MAX_ENTRANCE_DISTANCE: f32 = 24.0 * 32.0;
+/// With no choke, sunkens face this far from the hatchery towards where the enemy is
+const FALLBACK_ENTRANCE_DISTANCE: f32 = 10.0 * 32.0;
+
+/// Where a base's static defense goes
+#[derive(Debug, Clone)]
+pub struct BaseDefensePlan {
+  pub base_index: usize,
+  /// Where attacks are expected to come in from, the sunkens sit between this and the hatchery
+  pub entrance: Position,
+  /// In the order they should be built, the first closest to the entrance path
+  pub sunken_sites: Vec<TilePosition>,
+  /// In the mineral line, covering the drones and the hatchery
+  pub spore_sites: Vec<TilePosition>,
+}
+
+/// Sunken and spore sites for every base, redone once the enemy main is known
+#[derive(Debug, Clone, Default)]
+pub struct DefensePlacement {
+  /// Indexed like `bases`
+  pub plans: Vec<BaseDefensePlan>,
+  planned_for_enemy_main: Option<TilePosition>,
+  /// What each creep colony item turns into, by build order index, kept from when its site was
+  /// first planned
+  colony_purposes: HashMap<usize, UnitType>,
+}
+
+impl DefensePlacement {
+  pub fn plan(&self, base_index: usize) -> Option<&BaseDefensePlan> {
+    self.plans.iter().find(|plan| plan.base_index == base_index)
+  }
+
+  /// Follows items to their new index after a build order edit, forgetting removed ones
+  pub fn remap_items(&mut self, new_index: impl Fn(usize) -> Option<usize>) {
+    build_order_management::rekey_items(&mut self.colony_purposes, new_index);
+  }
+}
+
+/// Middle of a colony placed at `tile`
+fn colony_center(tile: TilePosition) -> Position {
+  footprint_center(tile, UnitType::Zerg_Creep_Colony)
+}
+
+/// Hatchery, mineral and geyser footprints of the base, which colonies can't go on
+fn base_footprints(game: &Game, base: &Base) -> Vec<(TilePosition, (i32, i32))> {
+  let hatchery = UnitType::Zerg_Hatchery;
+  // A tile of room around the hatchery so units can still get out
+  let mut footprints = vec![(
+    TilePosition {
+      x: base.tile.x - 1,
+      y: base.tile.y - 1,
+    },
+    (hatchery.tile_width() + 2, hatchery.tile_height() + 2),
+  )];
+  // Static resources, the base may not have been seen yet
+  footprints.extend(
+    game
+      .get_static_minerals()
+      .into_iter()
+      .chain(game.get_static_geysers())
+      .filter(|resource| {
+        base.mineral_ids.contains(&resource.get_id())
+          || base.geyser_ids.contains(&resource.get_id())
+      })
+      .map(|resource| {
+        let unit_type = resource.get_type();
+        (
+          resource.get_tile_position(),
+          (unit_type.tile_width(), unit_type.tile_height()),
+        )
+      }),
+  );
+  footprints
+}
+
+/// Middle of the base's minerals, the hatchery itself if it has none
+fn mineral_line(game: &Game, base: &Base) -> Position {
+  let minerals: Vec<Position> = game
+    .get_static_minerals()
+    .into_iter()
+    .filter(|mineral| base.mineral_ids.contains(&mineral.get_id()))
+    .map(|mineral| mineral.get_position())
+    .collect();
+  if minerals.is_empty() {
+    return base.center();
+  }
+  let count = minerals.len() as i32;
+  Position::new(
+    minerals.iter().map(|p| p.x).sum::<i32>() / count,
+    minerals.iter().map(|p| p.y).sum::<i32>() / count,
+  )
+}
+
+/// The choke guarding the base, or a point towards the enemy main, or the map centre, when
+/// there is no choke near enough
+fn base_entrance(game: &Game, base: &Base, enemy_main: Option<TilePosition>) -> Position {
+  let center = base.center();
+  if let Some(choke) = region_stuff::chokepoint_to_guard_base(game, &center) {
+    if pixel_distance(choke, center) <= MAX_ENTRANCE_DISTANCE {
+      return choke;
+    }
+  }
+  let towards = enemy_main
+    .map(|tile| base_center(&tile))
+    .unwrap_or(Position::new(game.map_width() * 16, game.map_height() * 16));
+  let distance = pixel_distance(towards, center).max(1.0);
+  let scale = FALLBACK_ENTRANCE_DISTANCE.min(distance) / distance;
+  Position::new(
+    center.x + ((towards.x - center.x) as f32 * scale) as i32,
+    center.y + ((towards.y - center.y) as f32 * scale) as i32,
+  )
+}
+
+/// Spores are meant to sit in the mineral line, everything else stays off reserved tiles
+fn allowed_reservations(purpose: UnitType) -> &'static [TileReservation] {
+  if purpose == UnitType::Zerg_Spore_Colony {
+    &[TileReservation::MineralLine]
+  } else {
+    &[]
+  }
+}
+
+/// Colony sites around `anchor` on buildable ground clear of the base, nearest to the anchor
+/// first
+fn candidate_sites(
+  game: &Game,
+  anchor: Position,
+  footprints: &[(TilePosition, (i32, i32))],
+  reservations: &TileReservations,
+  purpose: UnitType,
+) -> Vec<TilePosition> {
+  let colony = UnitType::Zerg_Creep_Colony;
+  let size = (colony.tile_width(), colony.tile_height());
+  let anchor_tile = TilePosition {
+    x: anchor.x / 32 - 1,
+    y: anchor.y / 32 - 1,
+  };
+  let mut sites: Vec<TilePosition> = Vec::new();
+  for dy in -SITE_SEARCH_RADIUS..=SITE_SEARCH_RADIUS {
+    for dx in -SITE_SEARCH_RADIUS..=SITE_SEARCH_RADIUS {
+      let tile = TilePosition {
+        x: anchor_tile.x + dx,
+        y: anchor_tile.y + dy,
+      };
+      let buildable = (0..size.1).all(|y| {
+        (0..size.0).all(|x| {
+          game.is_buildable(TilePosition {
+            x: tile.x + x,
+            y: tile.y + y,
+          })
+        })
+      });
+      if buildable
+        && !reservations.is_reserved(tile, colony, allowed_reservations(purpose))
+        && footprints
+          .iter()
+          .all(|(other, other_size)| !footprints_overlap(tile, size, *other, *other_size))
+      {
+        sites.push(tile);
+      }
+    }
+  }
+  sites.sort_by(|a, b| {
+    pixel_distance(colony_center(*a), anchor).total_cmp(&pixel_distance(colony_center(*b), anchor))
+  });
+  sites
+}
+
+/// Takes sites from `candidates` in order while each new one is in range of every site taken
+/// so far and of `covered`
+fn pick_covering_sites(
+  candidates: &[TilePosition],
+  covered: &[Position],
+  count: usize,
+  footprints: &mut Vec<(TilePosition, (i32, i32))>,
+) -> Vec<TilePosition> {
+  let colony = UnitType::Zerg_Creep_Colony;
+  let size = (colony.tile_width(), colony.tile_height());
+  let mut picked: Vec<TilePosition> = Vec::new();
+  for site in candidates {
+    if picked.len() >= count {
+      break;
+    }
+    let center = colony_center(*site);
+    let in_range = picked
+      .iter()
+      .map(|other| colony_center(*other))
+      .chain(covered.iter().copied())
+      .all(|other| pixel_distance(center, other) <= COLONY_RANGE);
+    if in_range
+      && footprints
+        .iter()
+        .all(|(other, other_size)| !footprints_overlap(*site, size, *other, *other_size))
+    {
+      picked.push(*site);
+      footprints.push((*site, size));
+    }
+  }
+  picked
+}
+
+fn plan_base(
+  game: &Game,
+  base: &Base,
+  enemy_main: Option<TilePosition>,
+  reservations: &TileReservations,
+) -> BaseDefensePlan {
+  let center = base.center();
+  let mut footprints = base_footprints(game, base);
+
+  let entrance = base_entrance(game, base, enemy_main);
+  let distance = pixel_distance(entrance, center).max(1.0);
+  let scale = SUNKEN_ANCHOR_DISTANCE.min(distance) / distance;
+  let sunken_anchor = Position::new(
+    center.x + ((entrance.x - center.x) as f32 * scale) as i32,
+    center.y + ((entrance.y - center.y) as f32 * scale) as i32,
+  );
+  let sunken_candidates = candidate_sites(
+    game,
+    sunken_anchor,
+    &footprints,
+    reservations,
+    UnitType::Zerg_Sunken_Colony,
+  );
+  let sunken_sites =
+    pick_covering_sites(&sunken_candidates, &[center], MAX_SUNKENS, &mut footprints);
+
+  let minerals = mineral_line(game, base);
+  let spore_anchor = Position::new((center.x + minerals.x) / 2, (center.y + minerals.y) / 2);
+  let spore_candidates = candidate_sites(
+    game,
+    spore_anchor,
+    &footprints,
+    reservations,
+    UnitType::Zerg_Spore_Colony,
+  );
+  let spore_sites = pick_covering_sites(
+    &spore_candidates,
+    &[center, minerals],
+    MAX_SPORES,
+    &mut footprints,
+  );
+
+  BaseDefensePlan {
+    base_index: base.index,
+    entrance,
+    sunken_sites,
+    spore_sites,
+  }
+}
+
+fn plan_all(game: &Game, game_state: &mut GameState) {
+  let enemy_main = scouting::enemy_main(game_state);
+  let plans: Vec<BaseDefensePlan> = game_state
+    .bases
+    .iter()
+    .map(|base| plan_base(game, base, enemy_main, &game_state.tile_reservations))
+    .collect();
+  println!(
+    "[DEFENSE] planned {} sunken and {} spore sites over {} bases",
+    plans
+      .iter()
+      .map(|plan| plan.sunken_sites.len())
+      .sum::<usize>(),
+    plans
+      .iter()
+      .map(|plan| plan.spore_sites.len())
+      .sum::<usize>(),
+    plans.len()
+  );
+  game_state.defense_placement.plans = plans;
+  game_state.defense_placement.planned_for_enemy_main = enemy_main;
+}
+
+/// Plans every base from the chokes, run after `base_registry::init` and
+/// `tile_reservations::init`
+pub fn init(game: &Game, game_state: &mut GameState) {
+  plan_all(game, game_state);
+}
+
+/// Plans again once the enemy main is found, for bases whose entrance was a guess
+pub fn update(game: &Game, game_state: &mut GameState) {
+  if scouting::enemy_main(game_state) != game_state.defense_placement.planned_for_enemy_main {
+    plan_all(game, game_state);
+  }
+}
+
+/// What the creep colony made for `build_idx` turns into. Decided when its site is first planned
+/// and recorded, so relocating the drone or editing later items doesn't send it to the other
+/// kind of site. Creep colony items are paired with the sunken and spore morphs in build order
+/// order, sunken when there is no morph left for it.
+pub fn plan_colony_purpose(game_state: &mut GameState, build_idx: usize) -> UnitType {
+  if let Some(purpose) = game_state.defense_placement.colony_purposes.get(&build_idx) {
+    return *purpose;
+  }
+
+  let unit_types = || {
+    game_state.build_order.iter().map(|item| match item {
+      BuildOrderItem::Unit { unit_type, .. } => Some(*unit_type),
+      _ => None,
+    })
+  };
+  let colonies_before = unit_types()
+    .take(build_idx)
+    .filter(|unit_type| *unit_type == Some(UnitType::Zerg_Creep_Colony))
+    .count();
+  let purpose = unit_types()
+    .flatten()
+    .filter(|unit_type| {
+      *unit_type == UnitType::Zerg_Sunken_Colony || *unit_type == UnitType::Zerg_Spore_Colony
+    })
+    .nth(colonies_before)
+    .unwrap_or(UnitType::Zerg_Sunken_Colony);
+
+  println!(
+    "[DEFENSE] creep colony for build order item {} becomes {:?}",
+    build_idx, purpose
+  );
+  game_state
+    .defense_placement
+    .colony_purposes
+    .insert(build_idx, purpose);
+  purpose
+}
+
+fn sites_for(plan: &BaseDefensePlan, purpose: UnitType) -> &[TilePosition] {
+  if purpose == UnitType::Zerg_Spore_Colony {
+    &plan.spore_sites
+  } else {
+    &plan.sunken_sites
+  }
+}
+
+/// First planned site at the base for a colony meant to become `purpose` that `builder` can
+/// place on by the time it gets there without cutting off a path, skipping `avoid` and sites
+/// another drone is headed to
+pub fn next_site(
+  game: &Game,
+  game_state: &GameState,
+  builder: &Unit,
+  base_index: usize,
+  purpose: UnitType,
+  avoid: &[TilePosition],
+) -> Option<TilePosition> {
+  let plan = game_state.defense_placement.plan(base_index)?;
+  sites_for(plan, purpose).iter().copied().find(|site| {
+    !avoid.contains(site)
+      && !game_state
+        .worker_assignments
+        .values()
+        .any(|assignment| assignment.target_position == Some((site.x, site.y)))
+      && build_location_utils::is_site_buildable(
+        game,
+        builder,
+        *site,
+        UnitType::Zerg_Creep_Colony,
+        &PlacementContext::new(game_state),
+        allowed_reservations(purpose),
+      )
+      && game_state.tile_reservations.can_place(
+        *site,
+        UnitType::Zerg_Creep_Colony,
+        allowed_reservations(purpose),
+      )
+  })
+}
+
+/// The colony out of `colonies` that sits on a site planned for `unit_type`, so spores end up
+/// in the mineral line and sunkens at the front
+pub fn colony_to_morph<'a>(
+  game_state: &GameState,
+  colonies: &[&'a Unit],
+  unit_type: UnitType,
+) -> Option<&'a Unit> {
+  colonies.iter().copied().find(|colony| {
+    let tile = colony.get_tile_position();
+    game_state
+      .defense_placement
+      .plans
+      .iter()
+      .any(|plan| sites_for(plan, unit_type).contains(&tile))
+  })
+}
@@ -1,148 +1,67 @@
 use crate::utils::build_order_management;
-use crate::utils::build_orders::build_order_item::BuildOrderItem;
-use crate::utils::game_state::{BuildingAssignment, GameState};
+use crate::utils::game_state::GameState;
 use rsbwapi::*;
+use std::collections::HashSet;
 
-pub fn check_and_advance_upgrade_if_started(game: &Game, game_state: &mut GameState) {
-  if has_started_current_upgrade(game, game_state) {
-    let upgrade_name = if let Some(BuildOrderItem::Upgrade(upgrade_type)) =
-      game_state.build_order.get(game_state.build_order_index)
-    {
-      format!("{:?}", upgrade_type)
-    } else {
-      "Unknown".to_string()
-    };
-    build_order_management::advance_build_order(
-      game,
-      game_state,
-      &format!("Upgrade {:} started", upgrade_name),
-    );
-  }
-}
-
-fn has_started_current_upgrade(game: &Game, game_state: &GameState) -> bool {
-  // Check if the current build order item is an upgrade
-  let Some(current_item) = game_state.build_order.get(game_state.build_order_index) else {
-    return false;
-  };
-
-  let upgrade_type = match current_item {
-    BuildOrderItem::Upgrade(upgrade) => upgrade,
-    _ => return false,
-  };
-  // Find the building assigned to this build order index
-  let Some(building_id) =
-    game_state
-      .building_assignments
-      .iter()
-      .find_map(|(&building_id, assignment)| {
-        if assignment.build_order_index == game_state.build_order_index {
-          Some(building_id)
-        } else {
-          None
-        }
-      })
-  else {
-    return false;
-  };
-
-  // Check if the building is actually researching the upgrade
-  let Some(building_unit) = game.get_unit(building_id) else {
-    return false;
-  };
-
-  // Check if the building is upgrading and if it's the right upgrade
-  building_unit.is_upgrading() && building_unit.get_upgrade() == *upgrade_type
+/// Level the next research of `upgrade_type` would reach
+pub fn next_upgrade_level(player: &Player, upgrade_type: UpgradeType) -> i32 {
+  player.get_upgrade_level(upgrade_type) + 1
 }
 
-pub fn assign_building_to_research_upgrade(
+/// A finished building of `building_type` (or one morphed from it, e.g. a Lair for a Hatchery)
+/// that isn't researching anything and isn't assigned to another item or in `exclude`
+pub fn find_idle_researcher(
   game: &Game,
-  game_state: &mut GameState,
+  game_state: &GameState,
   player: &Player,
-  upgrade: UpgradeType,
-) {
-  let building_type = upgrade.what_upgrades();
-
-  // Find a building of this type that can research the upgrade
-  let Some(building) = game.get_all_units().into_iter().find(|u| {
+  building_type: UnitType,
+  exclude: &HashSet<usize>,
+) -> Option<Unit> {
+  game.get_all_units().into_iter().find(|u| {
     u.get_player().get_id() == player.get_id()
-      && u.get_type() == building_type
+      && build_order_management::satisfies_requirement(u.get_type(), building_type)
       && u.is_completed()
+      && !u.is_morphing()
       && !u.is_upgrading()
-  }) else {
-    println!(
-      "No available {:?} found to research upgrade {:?}",
-      building_type, upgrade
-    );
-    return;
-  };
-
-  let building_id = building.get_id();
-  game_state.building_assignments.insert(
-    building_id,
-    BuildingAssignment::new_upgrade(upgrade, game_state.build_order_index),
-  );
-  println!(
-    "Assigned building {} ({:?}) to research {:?} for build order index {}",
-    building_id, building_type, upgrade, game_state.build_order_index
-  );
+      && !u.is_researching()
+      && !game_state.building_assignments.contains_key(&u.get_id())
+      && !exclude.contains(&u.get_id())
+  })
 }
 
-pub fn enforce_research_assignment(
-  game: &Game,
-  game_state: &mut GameState,
-  player: &Player,
-  upgrade_to_build: UpgradeType,
-) {
-  let current_level = player.get_upgrade_level(upgrade_to_build);
-  let needed_minerals = upgrade_to_build.mineral_price(current_level);
-  let needed_gas = upgrade_to_build.gas_price(current_level);
-  let current_gas = player.gas();
-
-  game.draw_text_screen(
-    (0, 60),
-    &format!(
-      "next {:?}, {}/{} minerals, {}/{} gas",
-      upgrade_to_build,
-      player.minerals(),
-      needed_minerals,
-      current_gas,
-      needed_gas
-    ),
-  );
+/// Returns a list of upgrades that would benefit the given unit type
+pub fn get_relevant_upgrades_for_unit(unit_type: UnitType) -> Vec<UpgradeType> {
+  let mut upgrades = Vec::new();
 
-  if player.minerals() < needed_minerals || current_gas < needed_gas {
-    return;
+  // Check if unit is a flyer
+  if unit_type.is_flyer() {
+    upgrades.push(UpgradeType::Zerg_Flyer_Attacks);
+    upgrades.push(UpgradeType::Zerg_Flyer_Carapace);
   }
 
-  let Some(building_id) =
-    game_state
-      .building_assignments
-      .iter()
-      .find_map(|(&building_id, assignment)| {
-        if assignment.build_order_index == game_state.build_order_index {
-          Some(building_id)
-        } else {
-          None
-        }
-      })
-  else {
-    game.draw_text_screen((0, 80), "No building assigned for this upgrade");
-    return;
-  };
+  // Check weapon types for ground units
+  let ground_weapon = unit_type.ground_weapon();
+  let air_weapon = unit_type.air_weapon();
 
-  let Some(building_unit) = game.get_unit(building_id) else {
-    game.draw_text_screen((0, 80), "Assigned building unit not found");
-    return;
-  };
+  // For non-flyers with weapons
+  if !unit_type.is_flyer() {
+    // Check for melee attacks (range <= 32 pixels, which is close range)
+    if ground_weapon != WeaponType::None && ground_weapon.max_range() <= 32 {
+      upgrades.push(UpgradeType::Zerg_Melee_Attacks);
+    }
 
-  if building_unit.upgrade(upgrade_to_build).is_ok() {
-    // println!("Started researching upgrade {:?}", upgrade_to_build);
-    // game_state.build_order_index += 1;
-  } else {
-    game.draw_text_screen(
-      (0, 80),
-      &format!("Failed to start researching upgrade {:?}", upgrade_to_build),
-    );
+    // Check for ranged attacks, on the ground or against air
+    if (ground_weapon != WeaponType::None && ground_weapon.max_range() > 32)
+      || air_weapon != WeaponType::None
+    {
+      upgrades.push(UpgradeType::Zerg_Missile_Attacks);
+    }
+
+    // Add carapace for all non-flying combat units
+    if ground_weapon != WeaponType::None || air_weapon != WeaponType::None {
+      upgrades.push(UpgradeType::Zerg_Carapace);
+    }
   }
+
+  upgrades
 }
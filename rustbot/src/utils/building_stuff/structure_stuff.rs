@@ -1,43 +1,54 @@
-use crate::utils::{building_stuff::build_location_utils, game_state::*};
+use crate::utils::{
+  building_stuff::{
+    build_location_utils::{self, PlacementContext},
+    builder_tracking, defense_placement,
+  },
+  game_state::*,
+  map_utils::geometry::base_center,
+};
 use rsbwapi::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-fn choose_drone_to_build(game: &Game, game_state: &GameState) -> Option<Unit> {
-  let mineral_patch_with_most_workers = game_state
+/// A drone from the most crowded patch that is safe to send off, skipping drones that already
+/// failed to build this item
+fn choose_drone_to_build(game: &Game, game_state: &GameState, build_idx: usize) -> Option<Unit> {
+  let failed: HashSet<usize> = game_state
+    .builder_tracking
+    .failed_drones(build_idx)
+    .collect();
+  let candidates: Vec<(Unit, usize)> = game_state
     .worker_assignments
     .iter()
-    .filter_map(|(_, assignment)| {
+    .filter(|(worker_id, _)| !failed.contains(worker_id))
+    .filter_map(|(&worker_id, assignment)| {
       if let WorkerAssignmentType::Gathering = assignment.assignment_type {
-        assignment.target_unit
+        Some((game.get_unit(worker_id)?, assignment.target_unit?))
       } else {
         None
       }
     })
-    .fold(HashMap::new(), |mut acc, mineral_id| {
-      *acc.entry(mineral_id).or_insert(0) += 1;
-      acc
-    })
-    .into_iter()
-    .max_by_key(|&(_, count)| count)
-    .map(|(mineral_id, _)| mineral_id);
+    .filter(|(drone, _)| builder_tracking::is_safe_builder(game, game_state, drone))
+    .collect();
 
-  game_state
-    .worker_assignments
+  let workers_per_patch = candidates
     .iter()
-    .find_map(|(&worker_id, assignment)| {
-      if let WorkerAssignmentType::Gathering = assignment.assignment_type {
-        if let Some(mineral_id) = mineral_patch_with_most_workers {
-          if assignment.target_unit == Some(mineral_id) {
-            return game.get_unit(worker_id);
-          }
-        }
-      }
-      None
-    })
+    .fold(HashMap::new(), |mut acc, (_, mineral_id)| {
+      *acc.entry(*mineral_id).or_insert(0) += 1;
+      acc
+    });
+
+  candidates
+    .into_iter()
+    .max_by_key(|(_, mineral_id)| workers_per_patch.get(mineral_id).copied().unwrap_or(0))
+    .map(|(drone, _)| drone)
 }
 
-pub fn make_building_assignment(game: &Game, game_state: &mut GameState, unit_type: UnitType) {
-  let current_build_idx = game_state.build_order_index;
+pub fn make_building_assignment(
+  game: &Game,
+  game_state: &mut GameState,
+  unit_type: UnitType,
+  current_build_idx: usize,
+) {
   let (builder_type, _) = unit_type.what_builds();
   if builder_type.is_building() {
     assign_building_to_morph_into_building(
@@ -108,7 +119,7 @@ fn assign_building_to_morph_into_building(
   // Helper function to find closest building to a base location
   let find_closest_to_base = |buildings: Vec<&Unit>, base_idx: usize| -> Option<Unit> {
     let base_tile = game_state.base_locations.get(base_idx)?;
-    let base_pos = Position::new(base_tile.x * 32 + 64, base_tile.y * 32 + 48); // Center of hatchery (4x3 tiles)
+    let base_pos = base_center(base_tile);
     
     buildings
       .into_iter()
@@ -122,6 +133,18 @@ fn assign_building_to_morph_into_building(
       .cloned()
   };
 
+  // Colonies sitting on a site planned for this kind of defense go first
+  let planned_colony = if builder_type == UnitType::Zerg_Creep_Colony {
+    let unassigned: Vec<&Unit> = completed_buildings
+      .iter()
+      .copied()
+      .filter(|u| !game_state.building_assignments.contains_key(&u.get_id()))
+      .collect();
+    defense_placement::colony_to_morph(game_state, &unassigned, unit_type).cloned()
+  } else {
+    None
+  };
+
   // First try to find a completed, unassigned building at the preferred base
   let building_of_type = if let Some(idx) = base_index {
     println!("  Looking for building at base {}", idx);
@@ -174,7 +197,7 @@ fn assign_building_to_morph_into_building(
     }
   });
 
-  let Some(building_of_type) = building_of_type else {
+  let Some(building_of_type) = planned_colony.or(building_of_type) else {
     println!(
       "ERROR: No available {:?} found to morph into {:?}",
       builder_type, unit_type
@@ -210,7 +233,7 @@ fn assign_drone_to_build_building(
   unit_type: UnitType,
   current_build_idx: usize,
 ) {
-  let Some(drone) = choose_drone_to_build(game, game_state) else {
+  let Some(drone) = choose_drone_to_build(game, game_state, current_build_idx) else {
     game.draw_text_screen((10, 10), "No available drone to build building");
     return;
   };
@@ -226,7 +249,15 @@ fn assign_drone_to_build_building(
       }
     });
 
-  let build_position = if unit_type == UnitType::Zerg_Hatchery {
+  // A site already picked for the item by an earlier builder that was sent back
+  let pending_site = game_state
+    .builder_tracking
+    .pending_sites
+    .remove(&current_build_idx);
+
+  let build_position = if let Some(site) = pending_site {
+    site
+  } else if unit_type == UnitType::Zerg_Hatchery {
     if let Some(idx) = base_index {
       if let Some(base_tile) = game_state.base_locations.get(idx) {
         (base_tile.x, base_tile.y)
@@ -243,8 +274,8 @@ fn assign_drone_to_build_building(
         game,
         &drone,
         unit_type,
-        &game_state.base_locations,
         base_index,
+        &PlacementContext::new(game_state),
       ) else {
         println!(
           "No valid build location found for {:?} at base_index {:?} (build_order_index {})",
@@ -255,13 +286,29 @@ fn assign_drone_to_build_building(
       (build_location.x, build_location.y)
     }
   } else {
-    let Some(build_location) = build_location_utils::get_buildable_location(
-      game,
-      &drone,
-      unit_type,
-      &game_state.base_locations,
-      base_index,
-    ) else {
+    // Creep colonies go on the base's planned sunken or spore sites while there are free ones
+    let planned_site = if unit_type == UnitType::Zerg_Creep_Colony {
+      let purpose = defense_placement::plan_colony_purpose(game_state, current_build_idx);
+      defense_placement::next_site(
+        game,
+        game_state,
+        &drone,
+        base_index.unwrap_or(0),
+        purpose,
+        &[],
+      )
+    } else {
+      None
+    };
+    let Some(build_location) = planned_site.or_else(|| {
+      build_location_utils::get_buildable_location(
+        game,
+        &drone,
+        unit_type,
+        base_index,
+        &PlacementContext::new(game_state),
+      )
+    }) else {
       println!(
         "No valid build location found for {:?} at base_index {:?} (build_order_index {})",
         unit_type, base_index, current_build_idx
@@ -284,13 +331,11 @@ fn assign_drone_to_build_building(
   );
 }
 
-pub fn enforce_structure_assignment(game: &Game, game_state: &mut GameState) {
-  let Some(player) = game.self_() else {
-    return;
-  };
-
-  let current_build_idx = game_state.build_order_index;
-  
+pub fn enforce_structure_assignment(
+  game: &Game,
+  game_state: &mut GameState,
+  current_build_idx: usize,
+) {
   // Get the building type we're trying to build
   let unit_type = match game_state.build_order.get(current_build_idx) {
     Some(crate::utils::build_orders::build_order_item::BuildOrderItem::Unit { unit_type, .. }) => *unit_type,
@@ -312,20 +357,18 @@ pub fn enforce_structure_assignment(game: &Game, game_state: &mut GameState) {
   if let Some((_building_id, Some(building_unit))) = building_assigned_for_current_index {
     let needed_minerals = unit_type.mineral_price();
     let needed_gas = unit_type.gas_price();
+    let (available_minerals, available_gas) =
+      crate::utils::resource_reservations::available_for(game, game_state, Some(current_build_idx));
 
     game.draw_text_screen(
       (0, 60),
       &format!(
         "Morphing {:?}, {}/{} minerals, {}/{} gas",
-        unit_type,
-        player.minerals(),
-        needed_minerals,
-        player.gas(),
-        needed_gas
+        unit_type, available_minerals, needed_minerals, available_gas, needed_gas
       ),
     );
 
-    if player.minerals() >= needed_minerals && player.gas() >= needed_gas {
+    if crate::utils::resource_reservations::can_spend(game, game_state, current_build_idx) {
       if building_unit.is_completed() && !building_unit.is_morphing() {
         if let Err(e) = building_unit.morph(unit_type) {
           game.draw_text_screen(
@@ -359,6 +402,10 @@ pub fn enforce_structure_assignment(game: &Game, game_state: &mut GameState) {
       )
       .as_str(),
     );
-    crate::utils::build_order_management::make_assignment_for_current_build_order_item(game, game_state);
+    crate::utils::build_order_management::make_assignment_for_build_order_item(
+      game,
+      game_state,
+      current_build_idx,
+    );
   }
 }
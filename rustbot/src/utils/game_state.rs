@@ -3,20 +3,85 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
+use crate::utils::base_registry::Base;
 use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::build_orders::build_order_selector::Matchup;
+use crate::utils::building_stuff::builder_tracking::BuilderTracking;
+use crate::utils::building_stuff::defense_placement::DefensePlacement;
+use crate::utils::gas_demand::GasPlan;
+use crate::utils::income_tracker::IncomeTracker;
+use crate::utils::macro_planner::MacroPlan;
+use crate::utils::map_utils::creep_map::CreepMap;
+use crate::utils::map_utils::tile_reservations::TileReservations;
+use crate::utils::military::overlord_management::OverlordManager;
 use crate::utils::military::squad_models::MilitarySquad;
+use crate::utils::military::worker_defense::WorkerDefense;
+use crate::utils::rally_points::RallyPoints;
+use crate::utils::scouting::Scouting;
+use crate::utils::upgrade_queue::UpgradeQueue;
+use crate::utils::worker_management::BaseSaturation;
+
+/// Items from `build_order_index` on that may be worked on at once, unless changed at runtime
+pub const DEFAULT_BUILD_ORDER_WINDOW: usize = 3;
 
 pub struct GameState {
   pub worker_assignments: HashMap<usize, WorkerAssignment>,
   pub building_assignments: HashMap<usize, BuildingAssignment>,
   pub base_locations: Vec<TilePosition>,
+  /// Owner, resources and state of every base, same order as `base_locations`
+  pub bases: Vec<Base>,
+  /// Where the enemy main is and the units sent to find it
+  pub scouting: Scouting,
+  /// Creep now and where it is expected, for placing buildings ahead of it
+  pub creep_map: CreepMap,
+  /// Tiles kept free of buildings, and what ground paths have to stay open
+  pub tile_reservations: TileReservations,
+  /// Sunken and spore sites planned for every base
+  pub defense_placement: DefensePlacement,
+  /// Gatherers against targets for every base with a finished hatchery
+  pub base_saturation: Vec<BaseSaturation>,
+  /// Set when a hatchery finishes, drones are moved between bases on the next assignment pass
+  pub worker_transfer_pending: bool,
+  /// Drones pulled off mining to fight in a mineral line
+  pub worker_defense: WorkerDefense,
+  /// Progress and failures of drones sent to build
+  pub builder_tracking: BuilderTracking,
   pub debug_lines: Vec<(Position, Position, Color)>,
   pub game_speed: i32,
+  pub build_order_name: String,
   pub build_order: Vec<BuildOrderItem>,
+  /// First build order item that has not started yet
   pub build_order_index: usize,
+  /// Status of every build order item, kept the same length as `build_order`
+  pub build_order_status: Vec<BuildOrderItemStatus>,
+  /// Frames each build order item was assigned, started and finished, same length as `build_order`
+  pub build_order_timings: Vec<BuildOrderItemTiming>,
+  /// How many items from `build_order_index` on may be worked on at once
+  pub build_order_window: usize,
+  /// Units started for a build order item, until they complete
+  pub build_order_units: HashMap<usize, usize>,
+  /// Resources promised to assigned build order items, keyed by build order index
+  pub resource_reservations: HashMap<usize, ResourceReservation>,
+  /// Named continuations to switch to once a Random enemy's race is revealed
+  pub build_order_continuations: HashMap<Matchup, (String, Vec<BuildOrderItem>)>,
+  /// Goals and reasoning used once the scripted build order runs out
+  pub macro_plan: MacroPlan,
+  /// Upgrades and research waiting for an idle building, apart from the build order
+  pub upgrade_queue: UpgradeQueue,
+  /// Drones wanted per extractor, from the gas still needed
+  pub gas_plan: GasPlan,
+  /// Mining rates over time, per base and per patch
+  pub income: IncomeTracker,
   pub larva_responsibilities: HashMap<usize, usize>,
+  /// Why each assigned larva was picked over the others
+  pub larva_selection_reasons: HashMap<usize, String>,
+  /// Where each hatchery sends the units it makes
+  pub rally_points: RallyPoints,
   pub military_squads: Vec<MilitarySquad>,
+  /// Vision and detection spots of overlords not out scouting
+  pub overlords: OverlordManager,
   pub debug_flags: HashSet<DebugFlag>,
+  pub seen_enemy_unit_types: HashSet<UnitType>,
 }
 
 impl Default for GameState {
@@ -25,13 +90,36 @@ impl Default for GameState {
       worker_assignments: HashMap::new(),
       building_assignments: HashMap::new(),
       base_locations: vec![],
+      bases: vec![],
+      scouting: Scouting::default(),
+      creep_map: CreepMap::default(),
+      tile_reservations: TileReservations::default(),
+      defense_placement: DefensePlacement::default(),
+      base_saturation: vec![],
+      worker_transfer_pending: false,
+      worker_defense: WorkerDefense::default(),
+      builder_tracking: BuilderTracking::default(),
       debug_lines: vec![],
       // game_speed: -1,
       game_speed: 1,
+      build_order_name: String::new(),
       build_order: vec![],
       build_order_index: 0,
+      build_order_status: vec![],
+      build_order_timings: vec![],
+      build_order_window: DEFAULT_BUILD_ORDER_WINDOW,
+      build_order_units: HashMap::new(),
+      resource_reservations: HashMap::new(),
+      build_order_continuations: HashMap::new(),
+      macro_plan: MacroPlan::default(),
+      upgrade_queue: UpgradeQueue::default(),
+      gas_plan: GasPlan::default(),
+      income: IncomeTracker::default(),
       larva_responsibilities: HashMap::new(),
+      larva_selection_reasons: HashMap::new(),
+      rally_points: RallyPoints::default(),
       military_squads: vec![],
+      overlords: OverlordManager::default(),
       debug_flags: [
         DebugFlag::ShowWorkerAssignments,
         DebugFlag::ShowMilitaryAssignments,
@@ -39,6 +127,7 @@ impl Default for GameState {
       ]
       .into_iter()
       .collect(),
+      seen_enemy_unit_types: HashSet::new(),
     }
   }
 }
@@ -49,6 +138,8 @@ pub type SharedGameState = Arc<Mutex<GameState>>;
 pub enum WorkerAssignmentType {
   Gathering,
   Building,
+  /// Sent to look for the enemy main, `scouting` moves it
+  Scouting,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -81,12 +172,67 @@ impl WorkerAssignment {
       build_order_index: Some(build_order_index),
     }
   }
+
+  pub fn scouting(target_position: (i32, i32)) -> Self {
+    Self {
+      assignment_type: WorkerAssignmentType::Scouting,
+      target_unit: None,
+      target_position: Some(target_position),
+      build_order_index: None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildOrderItemStatus {
+  Pending,
+  Assigned,
+  Started,
+  Completed,
+  Failed,
+  /// Passed over without being built, e.g. skipped from the status server
+  Skipped,
+}
+
+impl BuildOrderItemStatus {
+  /// Whether the item still needs a larva, drone or building to act on it
+  pub fn is_unstarted(&self) -> bool {
+    matches!(
+      self,
+      BuildOrderItemStatus::Pending | BuildOrderItemStatus::Assigned
+    )
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildOrderItemTiming {
+  pub assigned_frame: Option<i32>,
+  pub started_frame: Option<i32>,
+  pub completed_frame: Option<i32>,
+  pub failed_frame: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReservationKind {
+  Structure,
+  Larva,
+  Research,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceReservation {
+  pub build_order_index: usize,
+  pub kind: ReservationKind,
+  pub minerals: i32,
+  pub gas: i32,
+  pub reserved_at_frame: i32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BuildingAssignmentType {
   TrainUnit(UnitType),
   ResearchUpgrade(UpgradeType),
+  ResearchTech(TechType),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -109,6 +255,13 @@ impl BuildingAssignment {
       build_order_index,
     }
   }
+
+  pub fn new_research(tech_type: TechType, build_order_index: usize) -> Self {
+    Self {
+      assignment_type: BuildingAssignmentType::ResearchTech(tech_type),
+      build_order_index,
+    }
+  }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -0,0 +1,158 @@
+use rsbwapi::*;
+
+use crate::utils::build_order_management;
+use crate::utils::game_state::GameState;
+
+/// Rough gas income of one drone on an extractor
+const GAS_PER_DRONE_FRAME: f32 = 0.035;
+/// Gas still needed is planned to be mined within this many frames
+const GAS_HORIZON_FRAMES: f32 = 24.0 * 75.0;
+const FRAMES_PER_MINUTE: f32 = 24.0 * 60.0;
+/// Unstarted build order items from the head that count towards the demand
+const BUILD_ORDER_LOOKAHEAD: usize = 12;
+const MAX_WORKERS_PER_EXTRACTOR: usize = 3;
+/// Frames between recalculations, so drones aren't moved back and forth every frame
+const REPLAN_FRAMES: i32 = 48;
+
+/// How many drones mine gas, from the gas the next build order items, queued upgrades and the
+/// planner's army mix still need
+#[derive(Debug, Clone)]
+pub struct GasPlan {
+  /// Drones wanted on each finished extractor
+  pub workers_per_extractor: usize,
+  pub build_order_gas: i32,
+  pub upgrade_gas: i32,
+  pub army_gas: i32,
+  pub reason: String,
+  pub updated_frame: Option<i32>,
+}
+
+impl Default for GasPlan {
+  fn default() -> Self {
+    Self {
+      workers_per_extractor: MAX_WORKERS_PER_EXTRACTOR,
+      build_order_gas: 0,
+      upgrade_gas: 0,
+      army_gas: 0,
+      reason: String::new(),
+      updated_frame: None,
+    }
+  }
+}
+
+impl GasPlan {
+  pub fn demand(&self) -> i32 {
+    self.build_order_gas + self.upgrade_gas + self.army_gas
+  }
+}
+
+/// Gas for the next unstarted build order items
+fn build_order_gas(player: &Player, game_state: &GameState) -> i32 {
+  (game_state.build_order_index..game_state.build_order.len())
+    .filter(|&idx| {
+      game_state
+        .build_order_status
+        .get(idx)
+        .is_some_and(|status| status.is_unstarted())
+    })
+    .take(BUILD_ORDER_LOOKAHEAD)
+    .map(|idx| build_order_management::item_cost(player, &game_state.build_order[idx]).1)
+    .sum()
+}
+
+/// Gas for upgrades and research waiting in the queue
+fn upgrade_gas(game_state: &GameState) -> i32 {
+  game_state
+    .upgrade_queue
+    .entries
+    .iter()
+    .filter(|entry| entry.started.is_none())
+    .map(|entry| entry.kind.cost(entry.level).1)
+    .sum()
+}
+
+/// Gas the planner's army mix spends over the horizon, once the scripted build order is done
+fn army_gas(game_state: &GameState) -> i32 {
+  if game_state.build_order_index < game_state.build_order.len() {
+    return 0;
+  }
+  let mix = &game_state.macro_plan.goals.army_composition;
+  let total_weight: u32 = mix.iter().map(|goal| goal.weight).sum();
+  if total_weight == 0 {
+    return 0;
+  }
+  let gas_per_larva = mix
+    .iter()
+    .map(|goal| goal.unit_type.gas_price() as f32 * goal.weight as f32)
+    .sum::<f32>()
+    / total_weight as f32;
+  let larva =
+    game_state.macro_plan.status.larva_per_minute * GAS_HORIZON_FRAMES / FRAMES_PER_MINUTE;
+  (gas_per_larva * larva) as i32
+}
+
+/// Recalculates the drones wanted per extractor every few frames and logs each change
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let Some(player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+  if game_state
+    .gas_plan
+    .updated_frame
+    .is_some_and(|updated| frame - updated < REPLAN_FRAMES)
+  {
+    return;
+  }
+
+  let extractors = player
+    .get_units()
+    .iter()
+    .filter(|u| u.get_type() == UnitType::Zerg_Extractor && u.is_completed())
+    .count();
+
+  let build_order_gas = build_order_gas(&player, game_state);
+  let upgrade_gas = upgrade_gas(game_state);
+  let army_gas = army_gas(game_state);
+  let banked_gas = player.gas();
+  let demand = build_order_gas + upgrade_gas + army_gas;
+  let gas_needed = (demand - banked_gas).max(0);
+
+  let drones_needed =
+    (gas_needed as f32 / (GAS_HORIZON_FRAMES * GAS_PER_DRONE_FRAME)).ceil() as usize;
+  let workers_per_extractor = if extractors == 0 {
+    MAX_WORKERS_PER_EXTRACTOR
+  } else {
+    drones_needed
+      .div_ceil(extractors)
+      .min(MAX_WORKERS_PER_EXTRACTOR)
+  };
+
+  let reason = format!(
+    "{} gas wanted in the next {}s ({} build order, {} upgrades, {} army), {} banked, {} drones on {} extractors",
+    demand,
+    (GAS_HORIZON_FRAMES / 24.0) as i32,
+    build_order_gas,
+    upgrade_gas,
+    army_gas,
+    banked_gas,
+    drones_needed,
+    extractors
+  );
+
+  let plan = &mut game_state.gas_plan;
+  if extractors > 0 && workers_per_extractor != plan.workers_per_extractor {
+    println!(
+      "[GAS] {} -> {} drones per extractor at frame {} | {}",
+      plan.workers_per_extractor, workers_per_extractor, frame, reason
+    );
+  }
+  *plan = GasPlan {
+    workers_per_extractor,
+    build_order_gas,
+    upgrade_gas,
+    army_gas,
+    reason,
+    updated_frame: Some(frame),
+  };
+}
@@ -4,15 +4,18 @@ use std::sync::{Arc, Mutex};
 use super::game_state::GameState;
 
 pub type StatusCallback = Box<dyn FnOnce(&Game, &GameState) + Send>;
+pub type MutationCallback = Box<dyn FnOnce(&Game, &mut GameState) + Send>;
 
 pub struct HttpStatusCallbacks {
   callbacks: Vec<StatusCallback>,
+  mutations: Vec<MutationCallback>,
 }
 
 impl HttpStatusCallbacks {
   pub fn new() -> Self {
     Self {
       callbacks: Vec::new(),
+      mutations: Vec::new(),
     }
   }
 
@@ -20,7 +23,18 @@ impl HttpStatusCallbacks {
     self.callbacks.push(callback);
   }
 
-  pub fn process_all(&mut self, game: &Game, state: &GameState) {
+  /// Queues a change to the game state, applied on the game thread before status callbacks
+  /// so they already see it
+  pub fn add_mutation(&mut self, mutation: MutationCallback) {
+    self.mutations.push(mutation);
+  }
+
+  pub fn process_all(&mut self, game: &Game, state: &mut GameState) {
+    let mutations = std::mem::take(&mut self.mutations);
+    for mutation in mutations {
+      mutation(game, state);
+    }
+
     let callbacks = std::mem::take(&mut self.callbacks);
     for callback in callbacks {
       callback(game, state);
@@ -28,7 +42,7 @@ impl HttpStatusCallbacks {
   }
 
   pub fn has_pending(&self) -> bool {
-    !self.callbacks.is_empty()
+    !self.callbacks.is_empty() || !self.mutations.is_empty()
   }
 }
 
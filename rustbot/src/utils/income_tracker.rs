@@ -0,0 +1,333 @@
+use rsbwapi::*;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::utils::game_state::GameState;
+use crate::utils::map_utils::geometry::pixel_distance;
+
+/// Frames covered by one sample of the time series
+const SAMPLE_FRAMES: i32 = 24 * 10;
+/// Samples kept, an hour of game time
+const MAX_SAMPLES: usize = 360;
+const FRAMES_PER_MINUTE: f32 = 24.0 * 60.0;
+/// Minerals or gas a drone brings back per trip
+const RESOURCE_PER_TRIP: f32 = 8.0;
+/// Patches further than this from our closest finished hatchery are long distance mined
+const LONG_DISTANCE_PIXELS: f32 = 10.0 * 32.0;
+/// Mineral trip time of a close patch, until one has been measured
+const DEFAULT_NEAR_TRIP_FRAMES: f32 = 170.0;
+/// Weight of the newest trip in a patch's average trip time
+const TRIP_SMOOTHING: f32 = 0.2;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BaseIncome {
+  pub base_index: usize,
+  pub minerals_per_minute: f32,
+  pub gas_per_minute: f32,
+  pub trips: u32,
+}
+
+/// Mining over one `SAMPLE_FRAMES` window
+#[derive(Debug, Clone, Serialize)]
+pub struct IncomeSample {
+  /// Frame the window ended
+  pub frame: i32,
+  /// From the player's gathered totals, so exact
+  pub minerals_per_minute: f32,
+  pub gas_per_minute: f32,
+  /// From counted trips, split by the base the resource belongs to
+  pub bases: Vec<BaseIncome>,
+  pub workers: usize,
+  /// Summed over all drones, so 10 drones idle for the whole window is 100 seconds
+  pub idle_worker_seconds: f32,
+  pub long_distance_workers: usize,
+  /// Minerals per minute the long distance drones would gain on a close patch
+  pub long_distance_loss_per_minute: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct PatchStats {
+  pub resource_id: usize,
+  pub base_index: Option<usize>,
+  pub is_gas: bool,
+  /// Pixels to our closest finished hatchery, `None` without one
+  pub depot_distance: Option<f32>,
+  pub trips: u32,
+  /// Frames between two deliveries from this resource by the same drone
+  pub average_trip_frames: Option<f32>,
+  pub workers: usize,
+}
+
+impl PatchStats {
+  pub fn is_long_distance(&self) -> bool {
+    self
+      .depot_distance
+      .is_none_or(|distance| distance > LONG_DISTANCE_PIXELS)
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+struct WorkerTrip {
+  carrying: bool,
+  /// Mineral field or extractor the drone last went to
+  resource_id: Option<usize>,
+  /// Frame and resource of the last delivery
+  last_delivery: Option<(i32, usize)>,
+}
+
+/// Running totals for the sample being collected
+#[derive(Debug, Clone, Default)]
+struct SampleWindow {
+  start_frame: i32,
+  gathered_minerals: i32,
+  gathered_gas: i32,
+  base_trips: HashMap<usize, (u32, u32)>,
+  idle_frames: u32,
+}
+
+/// Mining rates over time, per base and per patch, to compare worker management changes
+#[derive(Debug, Clone, Default)]
+pub struct IncomeTracker {
+  pub samples: Vec<IncomeSample>,
+  /// Keyed by resource id
+  pub patches: HashMap<usize, PatchStats>,
+  workers: HashMap<usize, WorkerTrip>,
+  window: Option<SampleWindow>,
+}
+
+impl IncomeTracker {
+  /// Average trip time of close mineral patches, what long distance trips are compared against
+  pub fn near_trip_frames(&self) -> f32 {
+    let near: Vec<f32> = self
+      .patches
+      .values()
+      .filter(|patch| !patch.is_gas && !patch.is_long_distance())
+      .filter_map(|patch| patch.average_trip_frames)
+      .collect();
+    if near.is_empty() {
+      DEFAULT_NEAR_TRIP_FRAMES
+    } else {
+      near.iter().sum::<f32>() / near.len() as f32
+    }
+  }
+}
+
+fn per_minute(amount: f32, frames: i32) -> f32 {
+  if frames <= 0 {
+    0.0
+  } else {
+    amount * FRAMES_PER_MINUTE / frames as f32
+  }
+}
+
+fn base_of_resource(game_state: &GameState, resource_id: usize) -> Option<usize> {
+  game_state
+    .bases
+    .iter()
+    .find(|base| base.mineral_ids.contains(&resource_id) || base.geyser_ids.contains(&resource_id))
+    .map(|base| base.index)
+}
+
+/// Counts deliveries and idle drones every frame and closes a sample every `SAMPLE_FRAMES`
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let Some(player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+  let workers: Vec<Unit> = player
+    .get_units()
+    .into_iter()
+    .filter(|u| u.get_type().is_worker() && u.is_completed())
+    .collect();
+
+  let mut window = game_state
+    .income
+    .window
+    .take()
+    .unwrap_or_else(|| SampleWindow {
+      start_frame: frame,
+      gathered_minerals: player.gathered_minerals(),
+      gathered_gas: player.gathered_gas(),
+      ..Default::default()
+    });
+
+  for worker in &workers {
+    let worker_id = worker.get_id();
+    if worker.is_idle() && !game_state.worker_defense.is_pulled(worker_id) {
+      window.idle_frames += 1;
+    }
+
+    let carrying = worker.is_carrying_minerals() || worker.is_carrying_gas();
+    let resource = worker
+      .get_order_target()
+      .filter(|target| target.get_type().is_mineral_field() || target.get_type().is_refinery());
+
+    let trip = game_state.income.workers.entry(worker_id).or_default();
+    if let Some(resource) = &resource {
+      if !carrying {
+        trip.resource_id = Some(resource.get_id());
+      }
+    }
+
+    let delivered = trip.carrying && !carrying;
+    trip.carrying = carrying;
+    if !delivered || !(worker.is_gathering_minerals() || worker.is_gathering_gas()) {
+      continue;
+    }
+    let Some(resource_id) = trip.resource_id else {
+      continue;
+    };
+    let trip_frames = match trip.last_delivery {
+      Some((last_frame, last_resource)) if last_resource == resource_id => Some(frame - last_frame),
+      _ => None,
+    };
+    trip.last_delivery = Some((frame, resource_id));
+
+    let is_gas = worker.is_gathering_gas();
+    let base_index = base_of_resource(game_state, resource_id);
+    let patch = game_state
+      .income
+      .patches
+      .entry(resource_id)
+      .or_insert_with(|| PatchStats {
+        resource_id,
+        base_index,
+        is_gas,
+        depot_distance: None,
+        trips: 0,
+        average_trip_frames: None,
+        workers: 0,
+      });
+    patch.trips += 1;
+    if let Some(trip_frames) = trip_frames {
+      patch.average_trip_frames = Some(match patch.average_trip_frames {
+        Some(average) => average + (trip_frames as f32 - average) * TRIP_SMOOTHING,
+        None => trip_frames as f32,
+      });
+    }
+
+    if let Some(base_index) = base_index {
+      let (minerals, gas) = window.base_trips.entry(base_index).or_default();
+      if is_gas {
+        *gas += 1;
+      } else {
+        *minerals += 1;
+      }
+    }
+  }
+
+  let alive: Vec<usize> = workers.iter().map(|w| w.get_id()).collect();
+  game_state
+    .income
+    .workers
+    .retain(|worker_id, _| alive.contains(worker_id));
+
+  let frames = frame - window.start_frame;
+  if frames < SAMPLE_FRAMES {
+    game_state.income.window = Some(window);
+    return;
+  }
+
+  let sample = close_sample(game, game_state, &player, &window, workers.len());
+  println!(
+    "[INCOME] frame {}: {:.0} minerals/min, {:.0} gas/min, {:.0}s idle, {} long distance drones losing {:.0}/min",
+    sample.frame,
+    sample.minerals_per_minute,
+    sample.gas_per_minute,
+    sample.idle_worker_seconds,
+    sample.long_distance_workers,
+    sample.long_distance_loss_per_minute
+  );
+
+  let income = &mut game_state.income;
+  income.samples.push(sample);
+  if income.samples.len() > MAX_SAMPLES {
+    income.samples.remove(0);
+  }
+  income.window = Some(SampleWindow {
+    start_frame: frame,
+    gathered_minerals: player.gathered_minerals(),
+    gathered_gas: player.gathered_gas(),
+    ..Default::default()
+  });
+}
+
+/// Refreshes patch distances and worker counts and turns the window into a sample
+fn close_sample(
+  game: &Game,
+  game_state: &mut GameState,
+  player: &Player,
+  window: &SampleWindow,
+  worker_count: usize,
+) -> IncomeSample {
+  let frame = game.get_frame_count();
+  let frames = frame - window.start_frame;
+  let depots: Vec<Position> = player
+    .get_units()
+    .iter()
+    .filter(|u| u.get_type().is_resource_depot() && u.is_completed())
+    .map(|u| u.get_position())
+    .collect();
+
+  let mut patch_workers: HashMap<usize, usize> = HashMap::new();
+  for trip in game_state.income.workers.values() {
+    if let Some(resource_id) = trip.resource_id {
+      *patch_workers.entry(resource_id).or_insert(0) += 1;
+    }
+  }
+
+  let income = &mut game_state.income;
+  income
+    .patches
+    .retain(|resource_id, _| game.get_unit(*resource_id).is_some_and(|u| u.exists()));
+  for patch in income.patches.values_mut() {
+    patch.workers = patch_workers.get(&patch.resource_id).copied().unwrap_or(0);
+    patch.depot_distance = game.get_unit(patch.resource_id).and_then(|resource| {
+      depots
+        .iter()
+        .map(|depot| pixel_distance(*depot, resource.get_position()))
+        .min_by(|a, b| a.total_cmp(b))
+    });
+  }
+
+  let near_rate = per_minute(RESOURCE_PER_TRIP, income.near_trip_frames().round() as i32);
+  let long_distance: Vec<&PatchStats> = income
+    .patches
+    .values()
+    .filter(|patch| !patch.is_gas && patch.workers > 0 && patch.is_long_distance())
+    .collect();
+  let long_distance_workers = long_distance.iter().map(|patch| patch.workers).sum();
+  let long_distance_loss_per_minute = long_distance
+    .iter()
+    .filter_map(|patch| {
+      let rate = per_minute(RESOURCE_PER_TRIP, patch.average_trip_frames?.round() as i32);
+      Some((near_rate - rate).max(0.0) * patch.workers as f32)
+    })
+    .sum();
+
+  let mut bases: Vec<BaseIncome> = window
+    .base_trips
+    .iter()
+    .map(|(&base_index, &(mineral_trips, gas_trips))| BaseIncome {
+      base_index,
+      minerals_per_minute: per_minute(mineral_trips as f32 * RESOURCE_PER_TRIP, frames),
+      gas_per_minute: per_minute(gas_trips as f32 * RESOURCE_PER_TRIP, frames),
+      trips: mineral_trips + gas_trips,
+    })
+    .collect();
+  bases.sort_by_key(|base| base.base_index);
+
+  IncomeSample {
+    frame,
+    minerals_per_minute: per_minute(
+      (player.gathered_minerals() - window.gathered_minerals) as f32,
+      frames,
+    ),
+    gas_per_minute: per_minute((player.gathered_gas() - window.gathered_gas) as f32, frames),
+    bases,
+    workers: worker_count,
+    idle_worker_seconds: window.idle_frames as f32 / 24.0,
+    long_distance_workers,
+    long_distance_loss_per_minute,
+  }
+}
@@ -0,0 +1,638 @@
+use rsbwapi::*;
+use std::collections::{HashMap, VecDeque};
+
+use crate::utils::base_registry::{self, BaseState};
+use crate::utils::build_order_management;
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::game_state::{GameState, WorkerAssignmentType};
+use crate::utils::resource_reservations;
+use crate::utils::upgrade_queue::ResearchKind;
+
+/// Frames between larva spawns at one hatchery
+const LARVA_SPAWN_FRAMES: f32 = 342.0;
+/// Rough mineral income of one drone on a mineral patch
+const MINERALS_PER_DRONE_FRAME: f32 = 0.045;
+const FRAMES_PER_MINUTE: f32 = 24.0 * 60.0;
+/// Supply is counted in half supply by BWAPI, 200 shown supply
+const MAX_SUPPLY: i32 = 400;
+/// Banked minerals before another hatchery is worth it for larva
+const MACRO_HATCH_MINERALS: i32 = 300;
+/// Share of a base's drones at which the next base is taken
+const EXPAND_SATURATION: f32 = 0.75;
+const MAX_DECISIONS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArmyGoal {
+  pub unit_type: UnitType,
+  /// Share of army supply relative to the other goals
+  pub weight: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TechGoal {
+  pub unit_type: UnitType,
+  pub min_drones: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpgradeGoal {
+  pub upgrade_type: UpgradeType,
+  pub min_drones: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct MacroGoals {
+  pub army_composition: Vec<ArmyGoal>,
+  /// Army supply kept per drone while still droning
+  pub army_supply_per_drone: f32,
+  pub drones_per_base: usize,
+  pub drones_per_extractor: usize,
+  pub max_drones: usize,
+  pub tech: Vec<TechGoal>,
+  pub upgrades: Vec<UpgradeGoal>,
+}
+
+impl Default for MacroGoals {
+  fn default() -> Self {
+    Self {
+      army_composition: vec![ArmyGoal {
+        unit_type: UnitType::Zerg_Zergling,
+        weight: 1,
+      }],
+      army_supply_per_drone: 0.25,
+      drones_per_base: 16,
+      drones_per_extractor: 3,
+      max_drones: 60,
+      tech: vec![
+        TechGoal {
+          unit_type: UnitType::Zerg_Extractor,
+          min_drones: 14,
+        },
+        TechGoal {
+          unit_type: UnitType::Zerg_Evolution_Chamber,
+          min_drones: 24,
+        },
+        TechGoal {
+          unit_type: UnitType::Zerg_Lair,
+          min_drones: 30,
+        },
+      ],
+      upgrades: vec![
+        UpgradeGoal {
+          upgrade_type: UpgradeType::Metabolic_Boost,
+          min_drones: 0,
+        },
+        UpgradeGoal {
+          upgrade_type: UpgradeType::Zerg_Carapace,
+          min_drones: 24,
+        },
+      ],
+    }
+  }
+}
+
+impl MacroGoals {
+  /// Default goals with the army mix taken from the army units in the scripted build order
+  pub fn for_build_order(items: &[BuildOrderItem]) -> Self {
+    let mut weights: Vec<ArmyGoal> = Vec::new();
+    for item in items {
+      let BuildOrderItem::Unit { unit_type, .. } = item else {
+        continue;
+      };
+      if !is_army_unit(*unit_type) {
+        continue;
+      }
+      match weights.iter_mut().find(|goal| goal.unit_type == *unit_type) {
+        Some(goal) => goal.weight += 1,
+        None => weights.push(ArmyGoal {
+          unit_type: *unit_type,
+          weight: 1,
+        }),
+      }
+    }
+
+    let mut goals = Self::default();
+    if !weights.is_empty() {
+      goals.army_composition = weights;
+    }
+    if !goals
+      .army_composition
+      .iter()
+      .any(|goal| goal.unit_type == UnitType::Zerg_Zergling)
+    {
+      goals
+        .upgrades
+        .retain(|goal| goal.upgrade_type != UpgradeType::Metabolic_Boost);
+    }
+    goals
+  }
+}
+
+/// Economy numbers the last decision was based on
+#[derive(Debug, Clone, Default)]
+pub struct MacroStatus {
+  pub drones: usize,
+  /// Gatherers the bases' patches and extractors want, plus drones about to become buildings
+  pub drone_target: usize,
+  /// Gatherers still missing across all bases, eggs not counted
+  pub unmet_saturation: usize,
+  pub bases: usize,
+  pub hatcheries: usize,
+  pub hatcheries_needed: usize,
+  pub larva_per_minute: f32,
+  pub supply_used: i32,
+  pub supply_total: i32,
+  /// Supply from overlords and hatcheries still morphing
+  pub supply_in_production: i32,
+  /// Supply the larva can use up before a new overlord would finish
+  pub supply_forecast: i32,
+  pub army_supply: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct MacroDecision {
+  pub frame: i32,
+  pub item: String,
+  pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MacroPlan {
+  pub goals: MacroGoals,
+  pub status: MacroStatus,
+  /// Why each goal did or did not produce an item on the last pass
+  pub reasoning: Vec<String>,
+  /// Most recent items queued by the planner, newest first
+  pub decisions: VecDeque<MacroDecision>,
+}
+
+/// Own unit counts, eggs and cocoons count as what they morph into
+struct UnitCounts {
+  by_type: HashMap<UnitType, usize>,
+  larva: usize,
+}
+
+impl UnitCounts {
+  fn from_player(player: &Player) -> Self {
+    let mut by_type = HashMap::new();
+    let mut larva = 0;
+    for unit in player.get_units() {
+      let unit_type = unit.get_type();
+      match unit_type {
+        UnitType::Zerg_Larva => larva += 1,
+        UnitType::Zerg_Egg | UnitType::Zerg_Cocoon | UnitType::Zerg_Lurker_Egg => {
+          let build_type = unit.get_build_type();
+          *by_type.entry(build_type).or_insert(0) += units_per_egg(build_type);
+        }
+        _ => *by_type.entry(unit_type).or_insert(0) += 1,
+      }
+    }
+    Self { by_type, larva }
+  }
+
+  fn count(&self, unit_type: UnitType) -> usize {
+    self.by_type.get(&unit_type).copied().unwrap_or(0)
+  }
+
+  /// Counts morphed versions too, a Hive still gives Lair tech
+  fn has(&self, unit_type: UnitType) -> bool {
+    let upgraded: &[UnitType] = match unit_type {
+      UnitType::Zerg_Hatchery => &[
+        UnitType::Zerg_Hatchery,
+        UnitType::Zerg_Lair,
+        UnitType::Zerg_Hive,
+      ],
+      UnitType::Zerg_Lair => &[UnitType::Zerg_Lair, UnitType::Zerg_Hive],
+      UnitType::Zerg_Spire => &[UnitType::Zerg_Spire, UnitType::Zerg_Greater_Spire],
+      _ => &[],
+    };
+    if upgraded.is_empty() {
+      self.count(unit_type) > 0
+    } else {
+      upgraded.iter().any(|t| self.count(*t) > 0)
+    }
+  }
+
+  fn hatcheries(&self) -> usize {
+    self.count(UnitType::Zerg_Hatchery)
+      + self.count(UnitType::Zerg_Lair)
+      + self.count(UnitType::Zerg_Hive)
+  }
+}
+
+fn units_per_egg(unit_type: UnitType) -> usize {
+  match unit_type {
+    UnitType::Zerg_Zergling | UnitType::Zerg_Scourge => 2,
+    _ => 1,
+  }
+}
+
+fn is_army_unit(unit_type: UnitType) -> bool {
+  !unit_type.is_building()
+    && !unit_type.is_worker()
+    && unit_type != UnitType::Zerg_Overlord
+    && unit_type != UnitType::Zerg_Larva
+}
+
+/// Queues the next item once the scripted build order has run out
+pub fn plan(game: &Game, game_state: &mut GameState) {
+  let Some(player) = game.self_() else {
+    println!("Failed to get self player in macro_planner::plan");
+    return;
+  };
+
+  let counts = UnitCounts::from_player(&player);
+  let status = economy_status(&player, game_state, &counts);
+  let mut reasoning = Vec::new();
+
+  let next = choose_next_item(game, &player, game_state, &counts, &status, &mut reasoning);
+
+  let Some((item, reason)) = next else {
+    game_state.macro_plan.status = status;
+    game_state.macro_plan.reasoning = reasoning;
+    return;
+  };
+
+  reasoning.push(format!("Queued {}: {}", item.describe(), reason));
+  game_state.macro_plan.status = status;
+  game_state.macro_plan.reasoning = reasoning;
+
+  println!("[MACRO] queuing {} because {}", item.describe(), reason);
+  let decisions = &mut game_state.macro_plan.decisions;
+  decisions.push_front(MacroDecision {
+    frame: game.get_frame_count(),
+    item: item.describe(),
+    reason,
+  });
+  decisions.truncate(MAX_DECISIONS);
+
+  game_state.build_order.push(item);
+  build_order_management::sync_build_order_status(game_state);
+}
+
+fn economy_status(player: &Player, game_state: &GameState, counts: &UnitCounts) -> MacroStatus {
+  let goals = &game_state.macro_plan.goals;
+  let drones = counts.count(UnitType::Zerg_Drone);
+  let hatcheries = counts.hatcheries();
+  let bases = owned_base_count(player, game_state).max(1);
+  let extractors = counts.count(UnitType::Zerg_Extractor);
+
+  // Saturation of the patches and extractors we have, guessed until the first assignment pass
+  let saturation = &game_state.base_saturation;
+  let (mineral_target, saturation_target) = if saturation.is_empty() {
+    (
+      bases * goals.drones_per_base,
+      bases * goals.drones_per_base + extractors * goals.drones_per_extractor,
+    )
+  } else {
+    (
+      saturation.iter().map(|base| base.mineral_target()).sum(),
+      saturation
+        .iter()
+        .map(|base| base.mineral_target() + base.gas_target())
+        .sum(),
+    )
+  };
+  let unmet_saturation = saturation.iter().map(|base| base.unmet()).sum();
+  // Drones on their way to build are used up by the building, they won't gather
+  let builders = game_state
+    .worker_assignments
+    .values()
+    .filter(|assignment| assignment.assignment_type == WorkerAssignmentType::Building)
+    .count();
+  let drone_target = (saturation_target + builders).min(goals.max_drones);
+
+  // Enough hatcheries that their larva can spend the mineral income on the planned mix
+  let mining_drones = drones.min(mineral_target);
+  let income_per_frame = mining_drones as f32 * MINERALS_PER_DRONE_FRAME;
+  let larva_needed_per_frame = income_per_frame / average_larva_cost(goals) as f32;
+  let hatcheries_needed = ((larva_needed_per_frame * LARVA_SPAWN_FRAMES).ceil() as usize).max(1);
+
+  let supply_in_production = player
+    .get_units()
+    .iter()
+    .map(|u| match u.get_type() {
+      UnitType::Zerg_Egg => u.get_build_type().supply_provided(),
+      UnitType::Zerg_Hatchery if !u.is_completed() => UnitType::Zerg_Hatchery.supply_provided(),
+      _ => 0,
+    })
+    .sum::<i32>();
+
+  // Larva banked now plus what spawns while an overlord is morphing
+  let overlord_frames = UnitType::Zerg_Overlord.build_time() as f32;
+  let larva_before_overlord =
+    counts.larva as f32 + hatcheries as f32 * overlord_frames / LARVA_SPAWN_FRAMES;
+  let supply_forecast = (larva_before_overlord * average_larva_supply(goals)).ceil() as i32;
+
+  let army_supply = counts
+    .by_type
+    .iter()
+    .filter(|(unit_type, _)| is_army_unit(**unit_type))
+    .map(|(unit_type, count)| unit_type.supply_required() * *count as i32)
+    .sum::<i32>();
+
+  MacroStatus {
+    drones,
+    drone_target,
+    unmet_saturation,
+    bases,
+    hatcheries,
+    hatcheries_needed,
+    larva_per_minute: hatcheries as f32 * FRAMES_PER_MINUTE / LARVA_SPAWN_FRAMES,
+    supply_used: player.supply_used(),
+    supply_total: player.supply_total(),
+    supply_in_production,
+    supply_forecast,
+    army_supply,
+  }
+}
+
+/// Minerals spent per larva on the planned mix, drones included
+fn average_larva_cost(goals: &MacroGoals) -> i32 {
+  let total_weight: u32 = goals.army_composition.iter().map(|g| g.weight).sum();
+  if total_weight == 0 {
+    return UnitType::Zerg_Drone.mineral_price();
+  }
+  let army_cost = goals
+    .army_composition
+    .iter()
+    .map(|goal| goal.unit_type.mineral_price() * goal.weight as i32)
+    .sum::<i32>()
+    / total_weight as i32;
+  ((army_cost + UnitType::Zerg_Drone.mineral_price()) / 2).max(1)
+}
+
+/// Half supply used per larva on the planned mix, drones included
+fn average_larva_supply(goals: &MacroGoals) -> f32 {
+  let total_weight: u32 = goals.army_composition.iter().map(|g| g.weight).sum();
+  let drone_supply = UnitType::Zerg_Drone.supply_required() as f32;
+  if total_weight == 0 {
+    return drone_supply;
+  }
+  let army_supply = goals
+    .army_composition
+    .iter()
+    .map(|goal| {
+      (goal.unit_type.supply_required() as usize * units_per_egg(goal.unit_type)) as f32
+        * goal.weight as f32
+    })
+    .sum::<f32>()
+    / total_weight as f32;
+  (army_supply + drone_supply) / 2.0
+}
+
+/// Our bases with minerals left, counting hatcheries still morphing
+fn owned_base_count(player: &Player, game_state: &GameState) -> usize {
+  if game_state.bases.is_empty() {
+    return player
+      .get_units()
+      .iter()
+      .filter(|u| u.get_type().is_resource_depot())
+      .count();
+  }
+
+  base_registry::own_bases(game_state)
+    .filter(|base| base.state != BaseState::MinedOut)
+    .count()
+}
+
+/// Closest base nobody holds and no hatchery is planned for
+fn next_free_base(game_state: &GameState) -> Option<usize> {
+  game_state
+    .bases
+    .iter()
+    .find(|base| base.is_free())
+    .map(|base| base.index)
+}
+
+fn choose_next_item(
+  game: &Game,
+  player: &Player,
+  game_state: &GameState,
+  counts: &UnitCounts,
+  status: &MacroStatus,
+  reasoning: &mut Vec<String>,
+) -> Option<(BuildOrderItem, String)> {
+  let goals = &game_state.macro_plan.goals;
+  let has_extractor = counts.count(UnitType::Zerg_Extractor) > 0;
+  // What is left once the items already in flight are paid for
+  let (spare_minerals, spare_gas) = resource_reservations::spare_resources(game, game_state);
+
+  // Supply: enough overlords for what the larva can make before another one finishes
+  let free_supply = status.supply_total + status.supply_in_production - status.supply_used;
+  if status.supply_total + status.supply_in_production < MAX_SUPPLY
+    && free_supply < status.supply_forecast
+  {
+    return Some((
+      BuildOrderItem::unit(UnitType::Zerg_Overlord),
+      format!(
+        "{} free supply is below the {} the larva can use before an overlord finishes",
+        free_supply / 2,
+        status.supply_forecast / 2
+      ),
+    ));
+  }
+  reasoning.push(format!(
+    "Supply: {} free covers the forecast of {}",
+    free_supply / 2,
+    status.supply_forecast / 2
+  ));
+
+  // Tech: buildings the army mix needs, then tech goals once there are enough drones
+  let composition_tech = goals
+    .army_composition
+    .iter()
+    .filter_map(|goal| missing_tech(goal.unit_type, counts).map(|tech| (tech, goal.unit_type)));
+  for (tech, needed_by) in composition_tech {
+    if tech.gas_price() > 0 && !has_extractor {
+      reasoning.push(format!(
+        "Tech: {:?} for {:?} waits for an extractor",
+        tech, needed_by
+      ));
+      continue;
+    }
+    return Some((
+      BuildOrderItem::unit(tech),
+      format!("{:?} is needed for {:?} in the army mix", tech, needed_by),
+    ));
+  }
+  for goal in &goals.tech {
+    if counts.has(goal.unit_type) {
+      continue;
+    }
+    if status.drones < goal.min_drones {
+      reasoning.push(format!(
+        "Tech: {:?} waits for {} drones",
+        goal.unit_type, goal.min_drones
+      ));
+      continue;
+    }
+    let tech = missing_tech(goal.unit_type, counts).unwrap_or(goal.unit_type);
+    if tech.gas_price() > 0 && !has_extractor {
+      reasoning.push(format!("Tech: {:?} waits for an extractor", tech));
+      continue;
+    }
+    return Some((
+      BuildOrderItem::unit(tech),
+      format!(
+        "tech goal {:?} at {} drones, have {}",
+        goal.unit_type, goal.min_drones, status.drones
+      ),
+    ));
+  }
+
+  // Production: expand when the bases are nearly saturated, add hatcheries when larva is short
+  let hatchery_in_progress = player
+    .get_units()
+    .iter()
+    .any(|u| u.get_type() == UnitType::Zerg_Hatchery && !u.is_completed());
+  if !hatchery_in_progress {
+    let saturated_at = (status.bases * goals.drones_per_base) as f32 * EXPAND_SATURATION;
+    if status.drones as f32 >= saturated_at {
+      if let Some(base_index) = next_free_base(game_state) {
+        return Some((
+          BuildOrderItem::unit_at_base(UnitType::Zerg_Hatchery, base_index),
+          format!(
+            "{} drones nearly saturate {} bases",
+            status.drones, status.bases
+          ),
+        ));
+      }
+    }
+    if status.hatcheries < status.hatcheries_needed && spare_minerals >= MACRO_HATCH_MINERALS {
+      return Some((
+        BuildOrderItem::unit(UnitType::Zerg_Hatchery),
+        format!(
+          "{} hatcheries can't spend the income, {} needed",
+          status.hatcheries, status.hatcheries_needed
+        ),
+      ));
+    }
+  }
+  reasoning.push(format!(
+    "Production: {} of {} hatcheries for the income, {} bases",
+    status.hatcheries, status.hatcheries_needed, status.bases
+  ));
+
+  // Upgrades once their building is done and the gas is there
+  for goal in &goals.upgrades {
+    let upgrade_type = goal.upgrade_type;
+    if player.get_upgrade_level(upgrade_type) > 0
+      || player.is_upgrading(upgrade_type)
+      || game_state
+        .upgrade_queue
+        .contains(ResearchKind::Upgrade(upgrade_type))
+    {
+      continue;
+    }
+    if status.drones < goal.min_drones {
+      reasoning.push(format!(
+        "Upgrade: {:?} waits for {} drones",
+        upgrade_type, goal.min_drones
+      ));
+      continue;
+    }
+    let item = BuildOrderItem::upgrade(upgrade_type);
+    if !build_order_management::prerequisites_met(game, &item) {
+      reasoning.push(format!(
+        "Upgrade: {:?} waits for {:?}",
+        upgrade_type,
+        upgrade_type.what_upgrades()
+      ));
+      continue;
+    }
+    let (_, gas) = build_order_management::item_cost(player, &item);
+    if spare_gas < gas {
+      reasoning.push(format!("Upgrade: {:?} waits for {} gas", upgrade_type, gas));
+      continue;
+    }
+    return Some((item, format!("upgrade goal {:?}", upgrade_type)));
+  }
+
+  // Drones up to the target, keeping some army alongside
+  let wanted_army_supply = (status.drones as f32
+    * goals.army_supply_per_drone
+    * UnitType::Zerg_Drone.supply_required() as f32) as i32;
+  let army_unit = choose_army_unit(game, goals, counts, has_extractor);
+
+  if status.drones < status.drone_target
+    && (status.army_supply >= wanted_army_supply || army_unit.is_none())
+  {
+    return Some((
+      BuildOrderItem::unit(UnitType::Zerg_Drone),
+      format!(
+        "{} of {} drones, bases miss {} gatherers",
+        status.drones, status.drone_target, status.unmet_saturation
+      ),
+    ));
+  }
+
+  let Some(unit_type) = army_unit else {
+    reasoning.push("Army: no unit in the mix can be made yet".to_string());
+    return None;
+  };
+  Some((
+    BuildOrderItem::unit(unit_type),
+    if status.drones < status.drone_target {
+      format!(
+        "army supply {} is below {} for {} drones",
+        status.army_supply / 2,
+        wanted_army_supply / 2,
+        status.drones
+      )
+    } else {
+      format!(
+        "{:?} is furthest below its share of the army mix",
+        unit_type
+      )
+    },
+  ))
+}
+
+/// First building on the way to `unit_type` we don't have, if any
+fn missing_tech(unit_type: UnitType, counts: &UnitCounts) -> Option<UnitType> {
+  unit_type
+    .required_units()
+    .keys()
+    .copied()
+    .filter(|required| {
+      *required != UnitType::Zerg_Larva
+        && *required != UnitType::Zerg_Drone
+        && required.is_building()
+    })
+    .find(|required| !counts.has(*required))
+    .map(|required| missing_tech(required, counts).unwrap_or(required))
+}
+
+/// The army unit furthest below its share of the army mix that can be made now
+fn choose_army_unit(
+  game: &Game,
+  goals: &MacroGoals,
+  counts: &UnitCounts,
+  has_extractor: bool,
+) -> Option<UnitType> {
+  let total_weight: u32 = goals.army_composition.iter().map(|g| g.weight).sum();
+  let army_supply_of =
+    |unit_type: UnitType| unit_type.supply_required() as f32 * counts.count(unit_type) as f32;
+  let total_army: f32 = goals
+    .army_composition
+    .iter()
+    .map(|goal| army_supply_of(goal.unit_type))
+    .sum();
+
+  goals
+    .army_composition
+    .iter()
+    .filter(|goal| has_extractor || goal.unit_type.gas_price() == 0)
+    .filter(|goal| {
+      build_order_management::prerequisites_met(game, &BuildOrderItem::unit(goal.unit_type))
+    })
+    .map(|goal| {
+      let share = goal.weight as f32 / total_weight.max(1) as f32;
+      let deficit = share * (total_army + goal.unit_type.supply_required() as f32)
+        - army_supply_of(goal.unit_type);
+      (goal.unit_type, deficit)
+    })
+    .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+    .map(|(unit_type, _)| unit_type)
+}
@@ -0,0 +1,204 @@
+use rsbwapi::*;
+
+use crate::utils::game_state::GameState;
+
+/// The grid is rescanned this often
+const UPDATE_FRAMES: i32 = 24;
+/// Tiles around a hatchery, lair or hive that end up with creep, on the low side so planned
+/// sites don't come up short
+const HATCHERY_CREEP_RADIUS: i32 = 8;
+/// Same for creep colonies, and the sunkens and spores morphed from them
+const COLONY_CREEP_RADIUS: i32 = 6;
+/// Rough time creep takes to grow one more tile out
+const CREEP_SPREAD_FRAMES_PER_TILE: i32 = 24;
+
+/// Something of ours that spreads creep
+#[derive(Debug, Clone, Copy)]
+pub struct CreepSource {
+  pub center: TilePosition,
+  pub radius: i32,
+  /// Frame it finishes, creep starts spreading from then on
+  pub ready_frame: i32,
+}
+
+/// Where creep is now, from `Game::has_creep`, and the frame it is expected on tiles that
+/// don't have it yet
+#[derive(Debug, Clone, Default)]
+pub struct CreepMap {
+  pub width: i32,
+  pub height: i32,
+  creep: Vec<bool>,
+  forecast: Vec<Option<i32>>,
+  pub sources: Vec<CreepSource>,
+  pub updated_frame: Option<i32>,
+}
+
+impl CreepMap {
+  fn index(&self, tile: TilePosition) -> Option<usize> {
+    if tile.x < 0 || tile.y < 0 || tile.x >= self.width || tile.y >= self.height {
+      return None;
+    }
+    Some((tile.y * self.width + tile.x) as usize)
+  }
+
+  /// Whether the last scan found creep on the tile
+  pub fn has_creep(&self, tile: TilePosition) -> bool {
+    self.index(tile).is_some_and(|index| self.creep[index])
+  }
+
+  /// Frame creep is expected on the tile, the last scan's frame if it is already there
+  pub fn creep_frame(&self, tile: TilePosition) -> Option<i32> {
+    let index = self.index(tile)?;
+    if self.creep[index] {
+      return self.updated_frame;
+    }
+    self.forecast[index]
+  }
+
+  /// Frame the whole footprint of `unit_type` placed at `tile` is expected to be on creep
+  pub fn footprint_creep_frame(&self, tile: TilePosition, unit_type: UnitType) -> Option<i32> {
+    let mut latest = self.updated_frame?;
+    for dy in 0..unit_type.tile_height() {
+      for dx in 0..unit_type.tile_width() {
+        let frame = self.creep_frame(TilePosition {
+          x: tile.x + dx,
+          y: tile.y + dy,
+        })?;
+        latest = latest.max(frame);
+      }
+    }
+    Some(latest)
+  }
+
+  /// Tiles with creep as of the last scan
+  pub fn creep_tiles(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+    self
+      .creep
+      .iter()
+      .enumerate()
+      .filter(|(_, creep)| **creep)
+      .map(|(index, _)| (index as i32 % self.width, index as i32 / self.width))
+  }
+
+  /// Tiles without creep yet that are expected to get it, with the frame
+  pub fn forecast_tiles(&self) -> impl Iterator<Item = (i32, i32, i32)> + '_ {
+    self
+      .forecast
+      .iter()
+      .enumerate()
+      .filter(|(index, _)| !self.creep[*index])
+      .filter_map(|(index, frame)| {
+        let frame = (*frame)?;
+        Some((index as i32 % self.width, index as i32 / self.width, frame))
+      })
+  }
+}
+
+fn creep_radius(unit_type: UnitType) -> Option<i32> {
+  match unit_type {
+    UnitType::Zerg_Hatchery | UnitType::Zerg_Lair | UnitType::Zerg_Hive => {
+      Some(HATCHERY_CREEP_RADIUS)
+    }
+    UnitType::Zerg_Creep_Colony | UnitType::Zerg_Sunken_Colony | UnitType::Zerg_Spore_Colony => {
+      Some(COLONY_CREEP_RADIUS)
+    }
+    _ => None,
+  }
+}
+
+/// Sizes the grid to the map
+pub fn init(game: &Game, game_state: &mut GameState) {
+  let width = game.map_width();
+  let height = game.map_height();
+  let tiles = (width * height) as usize;
+  game_state.creep_map = CreepMap {
+    width,
+    height,
+    creep: vec![false; tiles],
+    forecast: vec![None; tiles],
+    sources: Vec::new(),
+    updated_frame: None,
+  };
+}
+
+/// Rescans creep and redoes the forecast from our hatcheries and colonies, finished or not
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let Some(self_player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+  let creep_map = &mut game_state.creep_map;
+  if creep_map
+    .updated_frame
+    .is_some_and(|updated| frame - updated < UPDATE_FRAMES)
+  {
+    return;
+  }
+
+  for y in 0..creep_map.height {
+    for x in 0..creep_map.width {
+      let index = (y * creep_map.width + x) as usize;
+      creep_map.creep[index] = game.has_creep(TilePosition { x, y });
+    }
+  }
+
+  creep_map.sources = self_player
+    .get_units()
+    .into_iter()
+    .filter_map(|unit| {
+      let unit_type = unit.get_type();
+      let radius = creep_radius(unit_type)?;
+      let tile = unit.get_tile_position();
+      // A lair, hive, sunken or spore still morphing already had creep under what it was
+      let spreading = unit.is_completed()
+        || matches!(
+          unit_type,
+          UnitType::Zerg_Lair
+            | UnitType::Zerg_Hive
+            | UnitType::Zerg_Sunken_Colony
+            | UnitType::Zerg_Spore_Colony
+        );
+      Some(CreepSource {
+        center: TilePosition {
+          x: tile.x + unit_type.tile_width() / 2,
+          y: tile.y + unit_type.tile_height() / 2,
+        },
+        radius,
+        ready_frame: if spreading {
+          frame
+        } else {
+          frame + unit.get_remaining_build_time()
+        },
+      })
+    })
+    .collect();
+
+  creep_map.forecast.iter_mut().for_each(|tile| *tile = None);
+  for source in creep_map.sources.clone() {
+    for dy in -source.radius..=source.radius {
+      for dx in -source.radius..=source.radius {
+        let distance_squared = dx * dx + dy * dy;
+        if distance_squared > source.radius * source.radius {
+          continue;
+        }
+        let tile = TilePosition {
+          x: source.center.x + dx,
+          y: source.center.y + dy,
+        };
+        // Creep only spreads over buildable ground
+        if !game.is_buildable(tile) {
+          continue;
+        }
+        let Some(index) = creep_map.index(tile) else {
+          continue;
+        };
+        let expected = source.ready_frame
+          + (distance_squared as f32).sqrt() as i32 * CREEP_SPREAD_FRAMES_PER_TILE;
+        let forecast = &mut creep_map.forecast[index];
+        *forecast = Some(forecast.map_or(expected, |current| current.min(expected)));
+      }
+    }
+  }
+
+  creep_map.updated_frame = Some(frame);
+}
@@ -0,0 +1,34 @@
+use rsbwapi::*;
+
+/// Minerals and geysers this close to a base's hatchery belong to the base
+pub const BASE_RESOURCE_RADIUS: f32 = 12.0 * 32.0;
+
+/// Straight line distance in pixels
+pub fn pixel_distance(a: Position, b: Position) -> f32 {
+  let dx = (a.x - b.x) as f32;
+  let dy = (a.y - b.y) as f32;
+  (dx * dx + dy * dy).sqrt()
+}
+
+/// Middle of `unit_type` placed with its top left tile at `tile`
+pub fn footprint_center(tile: TilePosition, unit_type: UnitType) -> Position {
+  Position::new(
+    tile.x * 32 + unit_type.tile_width() * 16,
+    tile.y * 32 + unit_type.tile_height() * 16,
+  )
+}
+
+/// Middle of a hatchery placed at `tile`
+pub fn base_center(tile: &TilePosition) -> Position {
+  Position::new(tile.x * 32 + 64, tile.y * 32 + 48)
+}
+
+/// Whether two tile footprints, given by top left tile and size in tiles, share a tile
+pub fn footprints_overlap(
+  a: TilePosition,
+  a_size: (i32, i32),
+  b: TilePosition,
+  b_size: (i32, i32),
+) -> bool {
+  a.x < b.x + b_size.0 && b.x < a.x + a_size.0 && a.y < b.y + b_size.1 && b.y < a.y + a_size.1
+}
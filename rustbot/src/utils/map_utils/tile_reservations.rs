@@ -0,0 +1,395 @@
+use rsbwapi::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::utils::base_registry::{self, Base};
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::game_state::{GameState, WorkerAssignmentType};
+use crate::utils::map_utils::region_stuff;
+
+/// Buildings and planned sites are picked up again this often
+const UPDATE_FRAMES: i32 = 24;
+/// Walk tiles out of a build tile's 16 that have to be walkable for ground units to cross it
+const MIN_WALKABLE_WALK_TILES: usize = 8;
+/// Tiles around a base's choke kept clear
+const RAMP_CLEAR_RADIUS: i32 = 2;
+/// Steps along a hatchery to resource line at which tiles are kept clear
+const RESOURCE_PATH_STEP: f32 = 16.0;
+
+/// Why a build tile can't be built on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileReservation {
+  /// A drone is on its way to build there
+  PlannedBuilding,
+  /// Between a hatchery and its minerals
+  MineralLine,
+  /// Between a hatchery and its geyser
+  GeyserPath,
+  /// At the choke into a base
+  Ramp,
+  /// Where a hatchery goes when the base is taken
+  Expansion,
+}
+
+/// Spots that have to stay reachable from each other by ground
+#[derive(Debug, Clone)]
+struct ConnectivityNode {
+  /// Any of these being reached counts
+  tiles: Vec<TilePosition>,
+}
+
+/// Build tiles kept free of our buildings, and what ground units can walk over right now
+#[derive(Debug, Clone, Default)]
+pub struct TileReservations {
+  pub width: i32,
+  pub height: i32,
+  /// Terrain and map resources, set up once. Reservations for spaces kept clear never change.
+  static_reservations: Vec<Option<TileReservation>>,
+  /// Static ones plus the sites drones are headed to
+  reservations: Vec<Option<TileReservation>>,
+  walkable: Vec<bool>,
+  /// Walkable terrain without a building on it
+  open: Vec<bool>,
+  /// Each base's choke, found once
+  base_chokes: Vec<Option<TilePosition>>,
+  nodes: Vec<ConnectivityNode>,
+  updated_frame: Option<i32>,
+}
+
+impl TileReservations {
+  fn index(&self, tile: TilePosition) -> Option<usize> {
+    if tile.x < 0 || tile.y < 0 || tile.x >= self.width || tile.y >= self.height {
+      return None;
+    }
+    Some((tile.y * self.width + tile.x) as usize)
+  }
+
+  pub fn reservation(&self, tile: TilePosition) -> Option<TileReservation> {
+    self.index(tile).and_then(|index| self.reservations[index])
+  }
+
+  /// Every reserved tile, for the status page
+  pub fn reserved_tiles(&self) -> impl Iterator<Item = (i32, i32, TileReservation)> + '_ {
+    self
+      .reservations
+      .iter()
+      .enumerate()
+      .filter_map(|(index, reservation)| {
+        let reservation = (*reservation)?;
+        Some((
+          index as i32 % self.width,
+          index as i32 / self.width,
+          reservation,
+        ))
+      })
+  }
+
+  /// Whether anything but `allowed` is reserved under `unit_type` placed at `tile`
+  pub fn is_reserved(
+    &self,
+    tile: TilePosition,
+    unit_type: UnitType,
+    allowed: &[TileReservation],
+  ) -> bool {
+    footprint(tile, unit_type).any(|tile| {
+      self
+        .reservation(tile)
+        .is_some_and(|reservation| !allowed.contains(&reservation))
+    })
+  }
+
+  /// Whether putting `unit_type` at `tile` leaves every base, its choke and the other bases
+  /// that can reach each other now still reachable
+  pub fn keeps_paths_open(&self, tile: TilePosition, unit_type: UnitType) -> bool {
+    if self.nodes.is_empty() {
+      return true;
+    }
+    let before = self.reached_nodes(&[]);
+    let blocked: Vec<usize> = footprint(tile, unit_type)
+      .filter_map(|tile| self.index(tile))
+      .collect();
+    let after = self.reached_nodes(&blocked);
+    before
+      .iter()
+      .zip(after.iter())
+      .all(|(was_reached, is_reached)| !was_reached || *is_reached)
+  }
+
+  /// Nothing but `allowed` reserved under the footprint and no path cut off
+  pub fn can_place(
+    &self,
+    tile: TilePosition,
+    unit_type: UnitType,
+    allowed: &[TileReservation],
+  ) -> bool {
+    !self.is_reserved(tile, unit_type, allowed) && self.keeps_paths_open(tile, unit_type)
+  }
+
+  /// Which nodes a ground unit starting at the first node reaches, with `extra_blocked`
+  /// tiles closed too
+  fn reached_nodes(&self, extra_blocked: &[usize]) -> Vec<bool> {
+    let mut visited = vec![false; self.open.len()];
+    for &index in extra_blocked {
+      visited[index] = true;
+    }
+    let mut queue: VecDeque<TilePosition> = VecDeque::new();
+    for tile in &self.nodes[0].tiles {
+      if let Some(index) = self.index(*tile) {
+        if self.open[index] && !visited[index] {
+          visited[index] = true;
+          queue.push_back(*tile);
+        }
+      }
+    }
+    let mut reached = vec![false; self.open.len()];
+    while let Some(tile) = queue.pop_front() {
+      if let Some(index) = self.index(tile) {
+        reached[index] = true;
+      }
+      for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+        let next = TilePosition {
+          x: tile.x + dx,
+          y: tile.y + dy,
+        };
+        let Some(index) = self.index(next) else {
+          continue;
+        };
+        if self.open[index] && !visited[index] {
+          visited[index] = true;
+          queue.push_back(next);
+        }
+      }
+    }
+    self
+      .nodes
+      .iter()
+      .map(|node| {
+        node
+          .tiles
+          .iter()
+          .any(|tile| self.index(*tile).is_some_and(|index| reached[index]))
+      })
+      .collect()
+  }
+}
+
+fn footprint(tile: TilePosition, unit_type: UnitType) -> impl Iterator<Item = TilePosition> {
+  (0..unit_type.tile_height()).flat_map(move |dy| {
+    (0..unit_type.tile_width()).map(move |dx| TilePosition {
+      x: tile.x + dx,
+      y: tile.y + dy,
+    })
+  })
+}
+
+fn reserve(reservations: &mut TileReservations, tile: TilePosition, reservation: TileReservation) {
+  if let Some(index) = reservations.index(tile) {
+    reservations.static_reservations[index].get_or_insert(reservation);
+  }
+}
+
+/// Reserves every tile on the straight line from `from` to `to`
+fn reserve_line(
+  reservations: &mut TileReservations,
+  from: Position,
+  to: Position,
+  reservation: TileReservation,
+) {
+  let dx = (to.x - from.x) as f32;
+  let dy = (to.y - from.y) as f32;
+  let steps = ((dx * dx + dy * dy).sqrt() / RESOURCE_PATH_STEP).ceil() as i32;
+  for step in 0..=steps {
+    let t = step as f32 / steps.max(1) as f32;
+    let tile = TilePosition {
+      x: (from.x as f32 + dx * t) as i32 / 32,
+      y: (from.y as f32 + dy * t) as i32 / 32,
+    };
+    reserve(reservations, tile, reservation);
+  }
+}
+
+/// Reads walkability and keeps clear every base's hatchery spot, mineral line, geyser path
+/// and choke. Run after `base_registry::init`.
+pub fn init(game: &Game, game_state: &mut GameState) {
+  let width = game.map_width();
+  let height = game.map_height();
+  let tiles = (width * height) as usize;
+  let mut reservations = TileReservations {
+    width,
+    height,
+    static_reservations: vec![None; tiles],
+    reservations: vec![None; tiles],
+    walkable: vec![false; tiles],
+    open: vec![false; tiles],
+    base_chokes: Vec::new(),
+    nodes: Vec::new(),
+    updated_frame: None,
+  };
+
+  for y in 0..height {
+    for x in 0..width {
+      let walkable_walk_tiles = (0..16)
+        .filter(|i| {
+          game.is_walkable(WalkPosition {
+            x: x * 4 + i % 4,
+            y: y * 4 + i / 4,
+          })
+        })
+        .count();
+      reservations.walkable[(y * width + x) as usize] =
+        walkable_walk_tiles >= MIN_WALKABLE_WALK_TILES;
+    }
+  }
+
+  let minerals = game.get_static_minerals();
+  let geysers = game.get_static_geysers();
+  for base in &game_state.bases {
+    let center = base.center();
+    for tile in footprint(base.tile, UnitType::Zerg_Hatchery) {
+      reserve(&mut reservations, tile, TileReservation::Expansion);
+    }
+    for mineral in minerals
+      .iter()
+      .filter(|mineral| base.mineral_ids.contains(&mineral.get_id()))
+    {
+      reserve_line(
+        &mut reservations,
+        center,
+        mineral.get_position(),
+        TileReservation::MineralLine,
+      );
+    }
+    for geyser in geysers
+      .iter()
+      .filter(|geyser| base.geyser_ids.contains(&geyser.get_id()))
+    {
+      reserve_line(
+        &mut reservations,
+        center,
+        geyser.get_position(),
+        TileReservation::GeyserPath,
+      );
+    }
+
+    let choke = region_stuff::chokepoint_to_guard_base(game, &center).map(|choke| TilePosition {
+      x: choke.x / 32,
+      y: choke.y / 32,
+    });
+    if let Some(choke) = choke {
+      for dy in -RAMP_CLEAR_RADIUS..=RAMP_CLEAR_RADIUS {
+        for dx in -RAMP_CLEAR_RADIUS..=RAMP_CLEAR_RADIUS {
+          let tile = TilePosition {
+            x: choke.x + dx,
+            y: choke.y + dy,
+          };
+          if reservations
+            .index(tile)
+            .is_some_and(|index| reservations.walkable[index])
+          {
+            reserve(&mut reservations, tile, TileReservation::Ramp);
+          }
+        }
+      }
+    }
+    reservations.base_chokes.push(choke);
+  }
+
+  reservations.reservations = reservations.static_reservations.clone();
+  println!(
+    "[RESERVATIONS] {} tiles kept clear over {} bases",
+    reservations.reserved_tiles().count(),
+    game_state.bases.len()
+  );
+  game_state.tile_reservations = reservations;
+}
+
+/// Picks up buildings, the sites drones are headed to and which bases have to stay connected
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let frame = game.get_frame_count();
+  if game_state
+    .tile_reservations
+    .updated_frame
+    .is_some_and(|updated| frame - updated < UPDATE_FRAMES)
+  {
+    return;
+  }
+
+  let planned_sites: Vec<(TilePosition, UnitType)> = game_state
+    .worker_assignments
+    .values()
+    .filter(|assignment| assignment.assignment_type == WorkerAssignmentType::Building)
+    .filter_map(|assignment| {
+      let (x, y) = assignment.target_position?;
+      match game_state.build_order.get(assignment.build_order_index?)? {
+        BuildOrderItem::Unit { unit_type, .. } => Some((TilePosition { x, y }, *unit_type)),
+        _ => None,
+      }
+    })
+    .collect();
+
+  // Every base and its choke, ours first since the search starts from the first one
+  let own: Vec<&Base> = base_registry::own_bases(game_state).collect();
+  let others = game_state
+    .bases
+    .iter()
+    .filter(|base| !own.iter().any(|own_base| own_base.index == base.index));
+  let mut nodes: Vec<ConnectivityNode> = Vec::new();
+  for base in own.iter().copied().chain(others) {
+    // The tiles right around the hatchery
+    let hatchery = UnitType::Zerg_Hatchery;
+    let mut ring: Vec<TilePosition> = Vec::new();
+    for dy in -1..=hatchery.tile_height() {
+      for dx in -1..=hatchery.tile_width() {
+        let inside =
+          (0..hatchery.tile_width()).contains(&dx) && (0..hatchery.tile_height()).contains(&dy);
+        if !inside {
+          ring.push(TilePosition {
+            x: base.tile.x + dx,
+            y: base.tile.y + dy,
+          });
+        }
+      }
+    }
+    nodes.push(ConnectivityNode { tiles: ring });
+    if let Some(Some(choke)) = game_state.tile_reservations.base_chokes.get(base.index) {
+      let tiles = (-1..=1)
+        .flat_map(|dy| {
+          (-1..=1).map(move |dx| TilePosition {
+            x: choke.x + dx,
+            y: choke.y + dy,
+          })
+        })
+        .collect();
+      nodes.push(ConnectivityNode { tiles });
+    }
+  }
+
+  if own.is_empty() {
+    nodes.clear();
+  }
+
+  let reservations = &mut game_state.tile_reservations;
+  reservations.reservations = reservations.static_reservations.clone();
+  reservations.open = reservations.walkable.clone();
+  for unit in game.get_all_units() {
+    let unit_type = unit.get_type();
+    if !(unit_type.is_building() || unit_type.is_resource_container()) || unit.is_flying() {
+      continue;
+    }
+    for tile in footprint(unit.get_tile_position(), unit_type) {
+      if let Some(index) = reservations.index(tile) {
+        reservations.open[index] = false;
+      }
+    }
+  }
+  for (site, unit_type) in planned_sites {
+    for tile in footprint(site, unit_type) {
+      if let Some(index) = reservations.index(tile) {
+        reservations.reservations[index] = Some(TileReservation::PlannedBuilding);
+        reservations.open[index] = false;
+      }
+    }
+  }
+  reservations.nodes = nodes;
+  reservations.updated_frame = Some(frame);
+}
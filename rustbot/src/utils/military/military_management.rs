@@ -8,6 +8,7 @@ use crate::utils::{
     squad_models::{MilitarySquad, SquadRole, SquadStatus},
     squad_mutas,
   },
+  scouting,
 };
 use rsbwapi::*;
 
@@ -88,19 +89,43 @@ pub fn create_squad(
         unit_path_assignments: std::collections::HashMap::new(),
       }
     }
-    SquadRole::AttackWorkers => squad_attack_workers::attack_workers_squad(game, &self_player),
+    SquadRole::AttackWorkers => squad_attack_workers::attack_workers_squad(
+      game,
+      &self_player,
+      scouting::enemy_main(game_state),
+    ),
   };
 }
 
 fn update_squads(game: &Game, game_state: &mut GameState) {
+  let enemy_main = scouting::enemy_main(game_state);
+  // Defend squads made before the enemy main was found get their point once it is
+  let defense_point = if enemy_main.is_some()
+    && game_state
+      .military_squads
+      .iter()
+      .any(|squad| squad.role == SquadRole::Defend && squad.target_position.is_none())
+  {
+    game
+      .self_()
+      .and_then(|self_player| squad_defend::calculate_defense_point(game, game_state, &self_player))
+  } else {
+    None
+  };
+
   for squad in game_state.military_squads.iter_mut() {
     match squad.role {
       SquadRole::AttackAsMutas => {
-        squad_mutas::muta_squad_control(game, squad);
+        squad_mutas::muta_squad_control(game, squad, enemy_main);
+      }
+      SquadRole::Defend => {
+        if squad.target_position.is_none() && defense_point.is_some() {
+          println!("Defend squad {} now holds {:?}", squad.name, defense_point);
+          squad.target_position = defense_point;
+        }
       }
-      SquadRole::Defend => {}
       SquadRole::AttackWorkers => {
-        squad_attack_workers::update_attack_workers_squad(game, squad);
+        squad_attack_workers::update_attack_workers_squad(game, squad, enemy_main);
       }
     }
   }
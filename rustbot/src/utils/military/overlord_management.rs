@@ -0,0 +1,342 @@
+use rsbwapi::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::utils::base_registry::{self, BaseOwner, BaseState};
+use crate::utils::game_state::GameState;
+use crate::utils::map_utils::geometry::{base_center, pixel_distance, BASE_RESOURCE_RADIUS};
+use crate::utils::map_utils::region_stuff;
+use crate::utils::scouting;
+
+/// Spots are handed out again this often
+const REASSIGN_FRAMES: i32 = 24;
+/// Kept this far outside an anti-air unit's range
+const ANTI_AIR_MARGIN: f32 = 3.0 * 32.0;
+/// Keeps retreating this long after the last anti-air unit was in range
+const RETREAT_FRAMES: i32 = 24 * 3;
+/// A spot anti-air was seen at is left empty this long
+const UNSAFE_SPOT_FRAMES: i32 = 24 * 45;
+/// Map edge spots are this far apart
+const EDGE_SPACING: i32 = 16 * 32;
+/// Map edge spots are this far in from the edge
+const EDGE_INSET: i32 = 2 * 32;
+/// Close enough to the spot to stop moving
+const ARRIVED_DISTANCE: f32 = 32.0;
+
+/// Where an overlord is posted, in the order spots are filled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum OverlordSpotKind {
+  /// Watching the enemy's natural for an early expansion
+  EnemyNatural,
+  /// Watching a choke into our main or natural for attacks
+  OurChoke,
+  /// Over one of our bases, to spot cloaked units
+  BaseDetection,
+  /// Along the map edge for vision of drops and expansions
+  MapEdge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverlordSpot {
+  pub kind: OverlordSpotKind,
+  pub position: Position,
+  pub base_index: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OverlordJob {
+  pub spot: Option<OverlordSpot>,
+  /// Set while running from anti-air
+  pub retreating_until: Option<i32>,
+}
+
+/// Where every overlord not out scouting is posted
+#[derive(Debug, Clone, Default)]
+pub struct OverlordManager {
+  /// Keyed by overlord id
+  pub jobs: HashMap<usize, OverlordJob>,
+  /// Spots that don't move, our chokes and the map edges
+  static_spots: Vec<OverlordSpot>,
+  /// Frame each spot position is avoided until, after anti-air was seen there
+  unsafe_spots: HashMap<(i32, i32), i32>,
+  last_assigned_frame: Option<i32>,
+}
+
+/// Visible enemy units and buildings that can shoot at air
+fn anti_air_units(game: &Game, self_player: &Player) -> Vec<Unit> {
+  game
+    .get_all_units()
+    .into_iter()
+    .filter(|u| self_player.is_enemy(&u.get_player()))
+    .filter(|u| u.get_type().air_weapon() != WeaponType::None)
+    .collect()
+}
+
+/// An anti-air unit that has `position` within its range plus `ANTI_AIR_MARGIN`
+fn anti_air_threat(anti_air: &[Unit], position: Position) -> Option<&Unit> {
+  anti_air.iter().find(|enemy| {
+    let range = enemy.get_type().air_weapon().max_range() as f32 + ANTI_AIR_MARGIN;
+    pixel_distance(enemy.get_position(), position) <= range
+  })
+}
+
+/// Works out the chokes into our main and natural and the map edge spots, nearest to our
+/// main first
+pub fn init(game: &Game, game_state: &mut GameState) {
+  let Some(self_player) = game.self_() else {
+    return;
+  };
+  let home = base_center(&self_player.get_start_location());
+
+  let mut spots: Vec<OverlordSpot> = Vec::new();
+  for base in game_state.bases.iter().take(2) {
+    let Some(choke) = region_stuff::chokepoint_to_guard_base(game, &base.center()) else {
+      continue;
+    };
+    if spots.iter().all(|spot| spot.position != choke) {
+      spots.push(OverlordSpot {
+        kind: OverlordSpotKind::OurChoke,
+        position: choke,
+        base_index: Some(base.index),
+      });
+    }
+  }
+
+  let width = game.map_width() * 32;
+  let height = game.map_height() * 32;
+  let mut edges: Vec<Position> = Vec::new();
+  for x in (EDGE_SPACING / 2..width).step_by(EDGE_SPACING as usize) {
+    edges.push(Position::new(x, EDGE_INSET));
+    edges.push(Position::new(x, height - EDGE_INSET));
+  }
+  for y in (EDGE_SPACING / 2..height).step_by(EDGE_SPACING as usize) {
+    edges.push(Position::new(EDGE_INSET, y));
+    edges.push(Position::new(width - EDGE_INSET, y));
+  }
+  edges.sort_by(|a, b| pixel_distance(*a, home).total_cmp(&pixel_distance(*b, home)));
+  spots.extend(edges.into_iter().map(|position| OverlordSpot {
+    kind: OverlordSpotKind::MapEdge,
+    position,
+    base_index: None,
+  }));
+
+  println!("[OVERLORDS] {} static spots", spots.len());
+  game_state.overlords.static_spots = spots;
+}
+
+/// Every spot that should have an overlord right now, most important first
+fn wanted_spots(game_state: &GameState, frame: i32) -> Vec<OverlordSpot> {
+  let mut spots: Vec<OverlordSpot> = Vec::new();
+
+  if let Some(enemy_main) = scouting::enemy_main(game_state) {
+    let enemy_center = base_center(&enemy_main);
+    let natural = game_state
+      .bases
+      .iter()
+      .filter(|base| !base.is_start_location)
+      .min_by(|a, b| {
+        pixel_distance(a.center(), enemy_center)
+          .total_cmp(&pixel_distance(b.center(), enemy_center))
+      });
+    if let Some(natural) = natural {
+      spots.push(OverlordSpot {
+        kind: OverlordSpotKind::EnemyNatural,
+        position: natural.center(),
+        base_index: Some(natural.index),
+      });
+    }
+  }
+
+  spots.extend(
+    game_state
+      .overlords
+      .static_spots
+      .iter()
+      .filter(|spot| spot.kind == OverlordSpotKind::OurChoke)
+      .copied(),
+  );
+
+  spots.extend(
+    base_registry::own_bases(game_state)
+      .filter(|base| {
+        matches!(
+          base.state,
+          BaseState::Building | BaseState::Active | BaseState::MinedOut
+        )
+      })
+      .map(|base| OverlordSpot {
+        kind: OverlordSpotKind::BaseDetection,
+        position: base.center(),
+        base_index: Some(base.index),
+      }),
+  );
+
+  // Edge spots over an enemy base would just get the overlord shot
+  spots.extend(
+    game_state
+      .overlords
+      .static_spots
+      .iter()
+      .filter(|spot| spot.kind == OverlordSpotKind::MapEdge)
+      .filter(|spot| {
+        !game_state.bases.iter().any(|base| {
+          base.owner == BaseOwner::Enemy
+            && pixel_distance(base.center(), spot.position) < BASE_RESOURCE_RADIUS
+        })
+      })
+      .copied(),
+  );
+
+  spots.retain(|spot| {
+    game_state
+      .overlords
+      .unsafe_spots
+      .get(&(spot.position.x, spot.position.y))
+      .is_none_or(|&until| until <= frame)
+  });
+  spots
+}
+
+/// Pulls overlords away from anti-air, hands out spots and moves overlords onto them
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let Some(self_player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+  let overlords: Vec<Unit> = self_player
+    .get_units()
+    .into_iter()
+    .filter(|u| u.get_type() == UnitType::Zerg_Overlord && u.is_completed())
+    .filter(|u| {
+      !game_state
+        .scouting
+        .scouts
+        .iter()
+        .any(|scout| scout.unit_id == u.get_id())
+    })
+    .collect();
+
+  let alive: Vec<usize> = overlords.iter().map(|u| u.get_id()).collect();
+  game_state
+    .overlords
+    .jobs
+    .retain(|overlord_id, _| alive.contains(overlord_id));
+  game_state
+    .overlords
+    .unsafe_spots
+    .retain(|_, until| *until > frame);
+
+  let anti_air = anti_air_units(game, &self_player);
+  let home = base_center(&self_player.get_start_location());
+  let safe_bases: Vec<Position> = base_registry::own_bases(game_state)
+    .map(|base| base.center())
+    .filter(|center| anti_air_threat(&anti_air, *center).is_none())
+    .collect();
+
+  for overlord in &overlords {
+    let overlord_id = overlord.get_id();
+    let position = overlord.get_position();
+    let job = game_state.overlords.jobs.entry(overlord_id).or_default();
+
+    if let Some(threat) = anti_air_threat(&anti_air, position) {
+      if job.retreating_until.is_none() {
+        println!(
+          "[OVERLORDS] overlord {} retreating from {:?}",
+          overlord_id,
+          threat.get_type()
+        );
+      }
+      if let Some(spot) = job.spot.take() {
+        game_state.overlords.unsafe_spots.insert(
+          (spot.position.x, spot.position.y),
+          frame + UNSAFE_SPOT_FRAMES,
+        );
+      }
+      job.retreating_until = Some(frame + RETREAT_FRAMES);
+      let retreat_to = safe_bases
+        .iter()
+        .min_by(|a, b| pixel_distance(**a, position).total_cmp(&pixel_distance(**b, position)))
+        .copied()
+        .unwrap_or(home);
+      if overlord.get_order_target_position() != Some(retreat_to) {
+        let _ = overlord.move_(retreat_to);
+      }
+    }
+  }
+
+  for job in game_state.overlords.jobs.values_mut() {
+    if job.retreating_until.is_some_and(|until| until <= frame) {
+      job.retreating_until = None;
+    }
+  }
+
+  if game_state
+    .overlords
+    .last_assigned_frame
+    .is_none_or(|last| frame - last >= REASSIGN_FRAMES)
+  {
+    assign_spots(game_state, &overlords, frame);
+    game_state.overlords.last_assigned_frame = Some(frame);
+  }
+
+  for overlord in &overlords {
+    let Some(job) = game_state.overlords.jobs.get(&overlord.get_id()) else {
+      continue;
+    };
+    if job.retreating_until.is_some() {
+      continue;
+    }
+    let Some(spot) = job.spot else {
+      continue;
+    };
+    if pixel_distance(overlord.get_position(), spot.position) > ARRIVED_DISTANCE
+      && overlord.get_order_target_position() != Some(spot.position)
+    {
+      let _ = overlord.move_(spot.position);
+    }
+  }
+}
+
+/// Frees overlords whose spot isn't wanted any more, then fills the wanted spots in order with
+/// the closest free overlord
+fn assign_spots(game_state: &mut GameState, overlords: &[Unit], frame: i32) {
+  let wanted = wanted_spots(game_state, frame);
+  let manager = &mut game_state.overlords;
+
+  for job in manager.jobs.values_mut() {
+    if job.spot.is_some_and(|spot| !wanted.contains(&spot)) {
+      job.spot = None;
+    }
+  }
+
+  for spot in wanted {
+    if manager.jobs.values().any(|job| job.spot == Some(spot)) {
+      continue;
+    }
+    let closest = overlords
+      .iter()
+      .filter(|overlord| {
+        manager
+          .jobs
+          .get(&overlord.get_id())
+          .is_some_and(|job| job.spot.is_none() && job.retreating_until.is_none())
+      })
+      .min_by(|a, b| {
+        pixel_distance(a.get_position(), spot.position)
+          .total_cmp(&pixel_distance(b.get_position(), spot.position))
+      });
+    let Some(overlord) = closest else {
+      break;
+    };
+    println!(
+      "[OVERLORDS] overlord {} to {:?} at ({}, {})",
+      overlord.get_id(),
+      spot.kind,
+      spot.position.x,
+      spot.position.y
+    );
+    if let Some(job) = manager.jobs.get_mut(&overlord.get_id()) {
+      job.spot = Some(spot);
+    }
+  }
+}
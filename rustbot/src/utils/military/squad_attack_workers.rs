@@ -79,8 +79,12 @@ fn get_average_resource_position_near_location(
   }
 }
 
-pub fn attack_workers_squad(game: &Game, self_player: &Player) -> MilitarySquad {
-  let start_locations: Vec<ScaledPosition<32>> = game.get_start_locations();
+/// Without a known `enemy_main` the squad gathers until scouting finds it
+pub fn attack_workers_squad(
+  game: &Game,
+  self_player: &Player,
+  enemy_main: Option<TilePosition>,
+) -> MilitarySquad {
   let Some(my_starting_position) = get_player_start_location(game, self_player) else {
     return MilitarySquad {
       name: "Main Squad".to_string(),
@@ -96,10 +100,7 @@ pub fn attack_workers_squad(game: &Game, self_player: &Player) -> MilitarySquad
     };
   };
 
-  let Some(enemy_location) = start_locations
-    .iter()
-    .find(|&&loc| loc != my_starting_position)
-  else {
+  let Some(enemy_location) = enemy_main else {
     return MilitarySquad {
       name: "Main Squad".to_string(),
       role: SquadRole::AttackWorkers,
@@ -115,7 +116,7 @@ pub fn attack_workers_squad(game: &Game, self_player: &Player) -> MilitarySquad
   };
 
   let average_position_of_minerals_near_enemy_location =
-    get_average_resource_position_near_location(game, enemy_location);
+    get_average_resource_position_near_location(game, &enemy_location);
 
   let my_pos = (my_starting_position.x * 32, my_starting_position.y * 32);
   let enemy_pos = average_position_of_minerals_near_enemy_location;
@@ -390,27 +391,28 @@ fn create_building_patrol_path_for_workers(
   Some(patrol_path)
 }
 
-pub fn update_attack_workers_squad(game: &Game, squad: &mut MilitarySquad) {
+pub fn update_attack_workers_squad(
+  game: &Game,
+  squad: &mut MilitarySquad,
+  enemy_main: Option<TilePosition>,
+) {
   // First ensure we have a path
   if squad.target_path.is_none() {
     let Some(self_player) = game.self_() else {
       return;
     };
 
-    let start_locations: Vec<ScaledPosition<32>> = game.get_start_locations();
     let Some(my_starting_position) = get_player_start_location(game, &self_player) else {
       return;
     };
 
-    let Some(enemy_location) = start_locations
-      .iter()
-      .find(|&&loc| loc != my_starting_position)
-    else {
+    // Not scouted yet, the path is worked out once it is
+    let Some(enemy_location) = enemy_main else {
       return;
     };
 
     let average_position_of_minerals_near_enemy_location =
-      get_average_resource_position_near_location(game, enemy_location);
+      get_average_resource_position_near_location(game, &enemy_location);
 
     let my_pos = (my_starting_position.x * 32, my_starting_position.y * 32);
     let enemy_pos = average_position_of_minerals_near_enemy_location;
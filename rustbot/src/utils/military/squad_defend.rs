@@ -1,9 +1,10 @@
 use rsbwapi::*;
 
 use crate::utils::{
-  build_orders::build_order_item::BuildOrderItem,
+  base_registry,
   game_state::GameState,
   map_utils::{pathing, region_stuff},
+  scouting,
 };
 
 pub fn defend_unit_control(game: &Game, unit: &Unit, defense_point: (i32, i32)) {
@@ -55,35 +56,12 @@ pub fn calculate_defense_point(
   game_state: &GameState,
   _self_player: &Player,
 ) -> Option<(i32, i32)> {
-  let largest_hatchery_base_index = game_state
-    .build_order
-    .iter()
-    .take(game_state.build_order_index)
-    .filter_map(|item| {
-      if let BuildOrderItem::Unit {
-        unit_type,
-        base_index,
-      } = item
-      {
-        if *unit_type == UnitType::Zerg_Hatchery {
-          return *base_index;
-        }
-      }
-      None
-    })
-    .max()?;
-
-  println!("defending from base index: {}", largest_hatchery_base_index);
-  let furthest_base_with_hatchery = game_state.base_locations.get(largest_hatchery_base_index)?;
-
-  let start_locations = game.get_start_locations();
-  let Some(self_player) = game.self_() else {
-    return None;
-  };
-  let my_start = start_locations.get(self_player.get_id() as usize)?;
-  let enemy_base = start_locations
-    .iter()
-    .find(|&loc| loc != my_start)?;
+  let furthest_base = base_registry::own_bases(game_state).max_by_key(|base| base.index)?;
+
+  println!("defending from base index: {}", furthest_base.index);
+  let furthest_base_with_hatchery = furthest_base.tile;
+
+  let enemy_base = scouting::enemy_main(game_state)?;
 
   let path_to_enemy = pathing::get_path_between_points(
     game,
@@ -8,18 +8,25 @@ use crate::utils::{
   },
 };
 
-pub fn muta_squad_control(game: &Game, squad: &mut MilitarySquad) {
-  ensure_path_to_enemy(game, squad);
+pub fn muta_squad_control(
+  game: &Game,
+  squad: &mut MilitarySquad,
+  enemy_main: Option<TilePosition>,
+) {
+  ensure_path_to_enemy(game, squad, enemy_main);
   initialize_target_position(squad);
   update_target_position(game, squad);
 }
 
-fn ensure_path_to_enemy(game: &Game, squad: &mut MilitarySquad) {
+fn ensure_path_to_enemy(game: &Game, squad: &mut MilitarySquad, enemy_main: Option<TilePosition>) {
   if squad.target_path.is_some() {
     return;
   }
 
-  println!("Calculating muta squad path to enemy base");
+  // Not scouted yet, the path is worked out once it is
+  let Some(enemy_location) = enemy_main else {
+    return;
+  };
   let Some(self_player) = game.self_() else {
     return;
   };
@@ -29,17 +36,17 @@ fn ensure_path_to_enemy(game: &Game, squad: &mut MilitarySquad) {
     return;
   };
 
-  let Some(enemy_location) = start_locations
-    .iter()
-    .find(|&loc| loc != my_starting_position)
-  else {
-    return;
-  };
-
   let my_pos = (my_starting_position.x * 32, my_starting_position.y * 32);
   let enemy_pos = (enemy_location.x * 32, enemy_location.y * 32);
 
   let path_to_enemy = pathing::get_path_between_points(game, my_pos, enemy_pos, Some(true));
+  if let Some(path) = &path_to_enemy {
+    println!(
+      "Calculated muta squad path to enemy base at {:?} ({} steps)",
+      enemy_location,
+      path.len()
+    );
+  }
   squad.target_path = path_to_enemy;
 }
 
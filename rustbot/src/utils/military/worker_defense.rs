@@ -0,0 +1,377 @@
+use rsbwapi::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::utils::base_registry::{self, Base};
+use crate::utils::game_state::{GameState, WorkerAssignmentType};
+use crate::utils::map_utils::geometry::pixel_distance;
+
+/// Enemies this close to a hatchery are in its mineral line
+const MINERAL_LINE_RADIUS: f32 = 10.0 * 32.0;
+/// Defenders don't chase further than this from the hatchery
+const CHASE_RADIUS: f32 = 14.0 * 32.0;
+/// Frames without an enemy near the base before the drones go back to work
+const THREAT_CLEAR_FRAMES: i32 = 48;
+/// Drones this hurt mineral walk away instead of fighting
+const DRONE_RETREAT_HP: i32 = 12;
+/// Share of a base's drones that may be pulled, more needed than this and they all run instead
+const MAX_PULLED_SHARE: f32 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DefenseRole {
+  Fighting,
+  /// Mineral walking away, through the attackers if need be
+  Escaping,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PulledDrone {
+  pub base_index: usize,
+  pub role: DefenseRole,
+}
+
+#[derive(Debug, Clone)]
+pub struct BaseThreat {
+  pub enemy_ids: Vec<usize>,
+  pub drones_needed: usize,
+  pub outnumbered: bool,
+  pub first_seen_frame: i32,
+  pub last_seen_frame: i32,
+}
+
+/// Drones pulled off mining to fight enemies in a mineral line. Their `WorkerAssignment` is left
+/// alone while they fight and they go back to it once the threat is gone.
+#[derive(Debug, Clone, Default)]
+pub struct WorkerDefense {
+  /// Keyed by base index
+  pub threats: HashMap<usize, BaseThreat>,
+  /// Keyed by drone id
+  pub pulled: HashMap<usize, PulledDrone>,
+}
+
+impl WorkerDefense {
+  pub fn is_pulled(&self, worker_id: usize) -> bool {
+    self.pulled.contains_key(&worker_id)
+  }
+
+  fn pulled_at(&self, base_index: usize, role: DefenseRole) -> usize {
+    self
+      .pulled
+      .values()
+      .filter(|drone| drone.base_index == base_index && drone.role == role)
+      .count()
+  }
+}
+
+/// Drones it takes to beat one of these in a straight fight
+fn drones_to_answer(unit_type: UnitType) -> usize {
+  if unit_type.is_worker() {
+    1
+  } else {
+    ((unit_type.supply_required() * 3 + 1) / 2).max(1) as usize
+  }
+}
+
+/// Enemy ground units that can hurt drones
+pub fn is_threat(unit: &Unit) -> bool {
+  let unit_type = unit.get_type();
+  !unit_type.is_building() && !unit_type.is_flyer() && unit_type.ground_weapon() != WeaponType::None
+}
+
+/// Finds enemies in our mineral lines, pulls enough drones to fight them or sends every drone
+/// there mineral walking when there are too many, and lets them go once the base is clear.
+/// Drones taken for a build order item are let go at once.
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let Some(player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+
+  let drones: Vec<Unit> = player
+    .get_units()
+    .into_iter()
+    .filter(|u| u.get_type().is_worker() && u.is_completed())
+    .collect();
+  let enemies: Vec<Unit> = game
+    .get_all_units()
+    .into_iter()
+    .filter(|u| player.is_enemy(&u.get_player()) && is_threat(u))
+    .collect();
+  let bases: Vec<Base> = base_registry::own_bases(game_state).cloned().collect();
+
+  // Dead drones, and drones a build order item needs, are no longer ours to move
+  let drone_ids: Vec<usize> = drones.iter().map(|d| d.get_id()).collect();
+  let assignments = &game_state.worker_assignments;
+  game_state.worker_defense.pulled.retain(|drone_id, _| {
+    drone_ids.contains(drone_id)
+      && assignments
+        .get(drone_id)
+        .is_none_or(|a| a.assignment_type == WorkerAssignmentType::Gathering)
+  });
+
+  for base in &bases {
+    let center = base.center();
+    let enemies_here: Vec<&Unit> = enemies
+      .iter()
+      .filter(|e| pixel_distance(e.get_position(), center) <= CHASE_RADIUS)
+      .collect();
+    let in_mineral_line =
+      |u: &&Unit| pixel_distance(u.get_position(), center) <= MINERAL_LINE_RADIUS;
+    let base_drones: Vec<&Unit> = drones
+      .iter()
+      .filter(in_mineral_line)
+      .filter(|d| {
+        game_state
+          .worker_assignments
+          .get(&d.get_id())
+          .is_none_or(|a| a.assignment_type == WorkerAssignmentType::Gathering)
+      })
+      .collect();
+
+    let already_threatened = game_state.worker_defense.threats.contains_key(&base.index);
+    let attacked = enemies_here
+      .iter()
+      .any(|e| in_mineral_line(e) && e.is_attacking())
+      || base_drones.iter().any(|d| d.is_under_attack());
+
+    if !enemies_here.is_empty() && (already_threatened || attacked) {
+      let pullable = (base_drones.len() as f32 * MAX_PULLED_SHARE).floor() as usize;
+      let drones_needed = enemies_here
+        .iter()
+        .map(|e| drones_to_answer(e.get_type()))
+        .sum::<usize>()
+        + 1;
+      let outnumbered = drones_needed > pullable;
+
+      let threat = game_state
+        .worker_defense
+        .threats
+        .entry(base.index)
+        .or_insert_with(|| {
+          println!(
+            "[WORKER DEFENSE] base {} attacked by {} enemies at frame {}",
+            base.index,
+            enemies_here.len(),
+            frame
+          );
+          BaseThreat {
+            enemy_ids: Vec::new(),
+            drones_needed,
+            outnumbered,
+            first_seen_frame: frame,
+            last_seen_frame: frame,
+          }
+        });
+      if threat.outnumbered != outnumbered {
+        println!(
+          "[WORKER DEFENSE] base {} {} ({} drones needed, {} can be pulled)",
+          base.index,
+          if outnumbered {
+            "outnumbered, mineral walking"
+          } else {
+            "back to fighting"
+          },
+          drones_needed,
+          pullable
+        );
+      }
+      threat.enemy_ids = enemies_here.iter().map(|e| e.get_id()).collect();
+      threat.drones_needed = drones_needed;
+      threat.outnumbered = outnumbered;
+      threat.last_seen_frame = frame;
+
+      if outnumbered {
+        for drone in &base_drones {
+          game_state.worker_defense.pulled.insert(
+            drone.get_id(),
+            PulledDrone {
+              base_index: base.index,
+              role: DefenseRole::Escaping,
+            },
+          );
+        }
+      } else {
+        pull_defenders(
+          game,
+          game_state,
+          base.index,
+          &base_drones,
+          &enemies_here,
+          drones_needed,
+        );
+      }
+    } else if game_state
+      .worker_defense
+      .threats
+      .get(&base.index)
+      .is_some_and(|threat| frame - threat.last_seen_frame > THREAT_CLEAR_FRAMES)
+    {
+      println!(
+        "[WORKER DEFENSE] base {} clear at frame {}",
+        base.index, frame
+      );
+      game_state.worker_defense.threats.remove(&base.index);
+      release_drones(game, game_state, base.index, usize::MAX);
+    }
+  }
+
+  // Threats at bases we no longer hold
+  let stale: Vec<usize> = game_state
+    .worker_defense
+    .threats
+    .keys()
+    .filter(|base_index| !bases.iter().any(|base| base.index == **base_index))
+    .copied()
+    .collect();
+  for base_index in stale {
+    game_state.worker_defense.threats.remove(&base_index);
+    release_drones(game, game_state, base_index, usize::MAX);
+  }
+
+  control_pulled_drones(game, game_state, &bases, &enemies);
+}
+
+/// Tops fighters at a base up to `drones_needed` with the healthiest drones closest to the
+/// enemies, or sends extras back to work when the threat shrank
+fn pull_defenders(
+  game: &Game,
+  game_state: &mut GameState,
+  base_index: usize,
+  base_drones: &[&Unit],
+  enemies: &[&Unit],
+  drones_needed: usize,
+) {
+  // Drones escaping from when the base was outnumbered may fight now
+  for drone in game_state.worker_defense.pulled.values_mut() {
+    if drone.base_index == base_index && drone.role == DefenseRole::Escaping {
+      drone.role = DefenseRole::Fighting;
+    }
+  }
+
+  let fighting = game_state
+    .worker_defense
+    .pulled_at(base_index, DefenseRole::Fighting);
+  if fighting >= drones_needed {
+    release_drones(game, game_state, base_index, fighting - drones_needed);
+    return;
+  }
+
+  let defense = &mut game_state.worker_defense;
+  let enemy_distance = |drone: &Unit| {
+    enemies
+      .iter()
+      .map(|e| pixel_distance(e.get_position(), drone.get_position()) as i32)
+      .min()
+      .unwrap_or(i32::MAX)
+  };
+  let mut candidates: Vec<&&Unit> = base_drones
+    .iter()
+    .filter(|d| !defense.is_pulled(d.get_id()) && d.get_hit_points() > DRONE_RETREAT_HP)
+    .collect();
+  candidates.sort_by_key(|d| (-d.get_hit_points(), enemy_distance(d)));
+
+  for drone in candidates.into_iter().take(drones_needed - fighting) {
+    defense.pulled.insert(
+      drone.get_id(),
+      PulledDrone {
+        base_index,
+        role: DefenseRole::Fighting,
+      },
+    );
+  }
+}
+
+/// Sends up to `count` drones pulled for a base back to their assignment
+fn release_drones(game: &Game, game_state: &mut GameState, base_index: usize, count: usize) {
+  let released: Vec<usize> = game_state
+    .worker_defense
+    .pulled
+    .iter()
+    .filter(|(_, drone)| drone.base_index == base_index)
+    .map(|(id, _)| *id)
+    .take(count)
+    .collect();
+
+  for drone_id in released {
+    game_state.worker_defense.pulled.remove(&drone_id);
+    let Some(drone) = game.get_unit(drone_id) else {
+      continue;
+    };
+    let target = game_state
+      .worker_assignments
+      .get(&drone_id)
+      .and_then(|a| a.target_unit)
+      .and_then(|id| game.get_unit(id));
+    // Without an assignment the drone is left idle for the next assignment pass
+    let _ = match target {
+      Some(resource) => drone.gather(&resource),
+      None => drone.stop(),
+    };
+  }
+}
+
+fn control_pulled_drones(game: &Game, game_state: &GameState, bases: &[Base], enemies: &[Unit]) {
+  let defense = &game_state.worker_defense;
+
+  for (drone_id, pulled) in &defense.pulled {
+    let Some(drone) = game.get_unit(*drone_id) else {
+      continue;
+    };
+    let Some(base) = bases.iter().find(|base| base.index == pulled.base_index) else {
+      continue;
+    };
+    let center = base.center();
+
+    let nearest_enemy = enemies
+      .iter()
+      .filter(|e| pixel_distance(e.get_position(), center) <= CHASE_RADIUS)
+      .min_by_key(|e| pixel_distance(e.get_position(), drone.get_position()) as i32);
+
+    match (pulled.role, nearest_enemy) {
+      (DefenseRole::Fighting, Some(enemy)) if drone.get_hit_points() > DRONE_RETREAT_HP => {
+        let already_attacking = drone.get_order() == Order::AttackUnit
+          && drone
+            .get_target()
+            .is_some_and(|t| t.get_id() == enemy.get_id());
+        if !already_attacking {
+          let _ = drone.attack(enemy);
+        }
+      }
+      (_, Some(enemy)) => {
+        mineral_walk(game, &drone, enemy.get_position(), base, bases);
+      }
+      (_, None) => {}
+    }
+  }
+}
+
+/// Gathers from a patch away from the danger. Drones ignore unit collision while moving to
+/// minerals, so this gets them past units blocking the way. Patches at another base are
+/// preferred, otherwise the patch furthest from the danger here.
+fn mineral_walk(game: &Game, drone: &Unit, danger: Position, base: &Base, bases: &[Base]) {
+  let patch_position = |id: &usize| game.get_unit(*id).filter(|m| m.exists());
+
+  let other_base_patch = bases
+    .iter()
+    .filter(|other| other.index != base.index && other.is_active())
+    .min_by_key(|other| pixel_distance(other.center(), base.center()) as i32)
+    .and_then(|other| other.mineral_ids.iter().find_map(patch_position));
+  let far_patch = || {
+    base
+      .mineral_ids
+      .iter()
+      .filter_map(patch_position)
+      .max_by_key(|m| pixel_distance(m.get_position(), danger) as i32)
+  };
+  let Some(patch) = other_base_patch.or_else(far_patch) else {
+    return;
+  };
+
+  let already_walking = drone.get_order() == Order::MoveToMinerals
+    && drone
+      .get_order_target()
+      .is_some_and(|t| t.get_id() == patch.get_id());
+  if !already_walking {
+    let _ = drone.gather(&patch);
+  }
+}
@@ -0,0 +1,157 @@
+use rsbwapi::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::utils::game_state::GameState;
+use crate::utils::map_utils::geometry::pixel_distance;
+
+/// Rally points are checked this often
+const UPDATE_FRAMES: i32 = 24;
+/// A rally point this close to the wanted one is left alone
+const RALLY_TOLERANCE: f32 = 32.0;
+
+/// What a hatchery's rally point is set up for. A hatchery has a single rally point, so it
+/// follows the last unit its larvae were picked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RallyRole {
+  /// The mineral line of the hatchery's base
+  Workers,
+  /// The gathering point of the squad new army units join
+  Army,
+}
+
+impl RallyRole {
+  /// `None` for units that don't care, like overlords
+  pub fn for_unit(unit_type: UnitType) -> Option<Self> {
+    if unit_type.is_worker() {
+      Some(RallyRole::Workers)
+    } else if unit_type == UnitType::Zerg_Overlord || unit_type.is_building() {
+      None
+    } else {
+      Some(RallyRole::Army)
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HatcheryRally {
+  pub role: RallyRole,
+  pub position: Position,
+  pub set_frame: i32,
+}
+
+/// Rally points of our hatcheries, keyed by hatchery id
+#[derive(Debug, Clone, Default)]
+pub struct RallyPoints {
+  /// Role wanted for each hatchery, from the last larva picked there
+  pub roles: HashMap<usize, RallyRole>,
+  /// Rally point last set on each hatchery
+  pub rallies: HashMap<usize, HatcheryRally>,
+  last_update_frame: Option<i32>,
+}
+
+/// Called when a larva of `hatchery_id` is picked to make `unit_type`
+pub fn note_larva_use(game_state: &mut GameState, hatchery_id: usize, unit_type: UnitType) {
+  if let Some(role) = RallyRole::for_unit(unit_type) {
+    game_state.rally_points.roles.insert(hatchery_id, role);
+  }
+}
+
+/// Where new army units gather, the target of the squad they will join. See
+/// `military_management::assign_unit_to_squad`.
+pub fn army_rally_point(game_state: &GameState) -> Option<Position> {
+  let squad = game_state.military_squads.last()?;
+  let (x, y) = squad.target_position?;
+  Some(Position::new(x, y))
+}
+
+/// Middle of the minerals still left at the hatchery's base
+fn mineral_line(game: &Game, game_state: &GameState, hatchery_id: usize) -> Option<Position> {
+  let base = game_state
+    .bases
+    .iter()
+    .find(|base| base.depot_id == Some(hatchery_id))?;
+  let minerals: Vec<Position> = base
+    .mineral_ids
+    .iter()
+    .filter_map(|id| game.get_unit(*id))
+    .filter(|mineral| mineral.exists())
+    .map(|mineral| mineral.get_position())
+    .collect();
+  if minerals.is_empty() {
+    return None;
+  }
+  let count = minerals.len() as i32;
+  Some(Position::new(
+    minerals.iter().map(|p| p.x).sum::<i32>() / count,
+    minerals.iter().map(|p| p.y).sum::<i32>() / count,
+  ))
+}
+
+/// Points every finished hatchery's rally at its mineral line or the army rally point,
+/// whichever its last larva was used for
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let Some(self_player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+  if game_state
+    .rally_points
+    .last_update_frame
+    .is_some_and(|last| frame - last < UPDATE_FRAMES)
+  {
+    return;
+  }
+  game_state.rally_points.last_update_frame = Some(frame);
+
+  let hatcheries: Vec<Unit> = self_player
+    .get_units()
+    .into_iter()
+    .filter(|u| u.get_type().is_resource_depot() && u.is_completed())
+    .collect();
+  let alive: Vec<usize> = hatcheries.iter().map(|h| h.get_id()).collect();
+  game_state
+    .rally_points
+    .roles
+    .retain(|hatchery_id, _| alive.contains(hatchery_id));
+  game_state
+    .rally_points
+    .rallies
+    .retain(|hatchery_id, _| alive.contains(hatchery_id));
+
+  let army_point = army_rally_point(game_state);
+  for hatchery in &hatcheries {
+    let hatchery_id = hatchery.get_id();
+    let role = game_state
+      .rally_points
+      .roles
+      .get(&hatchery_id)
+      .copied()
+      .unwrap_or(RallyRole::Workers);
+    let wanted = match role {
+      RallyRole::Army => army_point.or_else(|| mineral_line(game, game_state, hatchery_id)),
+      RallyRole::Workers => mineral_line(game, game_state, hatchery_id),
+    };
+    let Some(wanted) = wanted else {
+      continue;
+    };
+
+    let current = hatchery.get_rally_position();
+    if current.is_some_and(|current| pixel_distance(current, wanted) <= RALLY_TOLERANCE) {
+      continue;
+    }
+    let _ = hatchery.set_rally_point(wanted);
+    println!(
+      "[RALLY] hatchery {} rallies {:?} to ({}, {})",
+      hatchery_id, role, wanted.x, wanted.y
+    );
+    game_state.rally_points.rallies.insert(
+      hatchery_id,
+      HatcheryRally {
+        role,
+        position: wanted,
+        set_frame: frame,
+      },
+    );
+  }
+}
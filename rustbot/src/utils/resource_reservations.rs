@@ -0,0 +1,138 @@
+use crate::utils::build_order_management;
+use crate::utils::game_state::{GameState, ReservationKind, ResourceReservation};
+use rsbwapi::*;
+
+/// Reserves the cost of a build order item once a larva, drone or building is assigned to it
+pub fn reserve(game: &Game, game_state: &mut GameState, build_idx: usize, kind: ReservationKind) {
+  // Items that lose their larva or drone get reassigned, they keep the reservation they had
+  if game_state.resource_reservations.contains_key(&build_idx) {
+    return;
+  }
+  let Some(player) = game.self_() else {
+    return;
+  };
+  let Some(item) = game_state.build_order.get(build_idx) else {
+    return;
+  };
+
+  let (minerals, gas) = build_order_management::item_cost(&player, item);
+  game_state.resource_reservations.insert(
+    build_idx,
+    ResourceReservation {
+      build_order_index: build_idx,
+      kind,
+      minerals,
+      gas,
+      reserved_at_frame: game.get_frame_count(),
+    },
+  );
+  println!(
+    "[RESERVE] {} minerals, {} gas for build order index {} ({:?})",
+    minerals, gas, build_idx, kind
+  );
+}
+
+pub fn release(game_state: &mut GameState, build_idx: usize, reason: &str) {
+  if let Some(reservation) = game_state.resource_reservations.remove(&build_idx) {
+    println!(
+      "[RESERVE] released {} minerals, {} gas for build order index {} | {}",
+      reservation.minerals, reservation.gas, build_idx, reason
+    );
+  }
+}
+
+/// Drops reservations for items that started, failed or were removed from the build order
+pub fn release_stale(game_state: &mut GameState) {
+  let stale: Vec<usize> = game_state
+    .resource_reservations
+    .keys()
+    .copied()
+    .filter(|&idx| {
+      !game_state
+        .build_order_status
+        .get(idx)
+        .is_some_and(|status| status.is_unstarted())
+    })
+    .collect();
+
+  for build_idx in stale {
+    release(game_state, build_idx, "item no longer waiting to start");
+  }
+}
+
+pub fn reserved_totals(game_state: &GameState) -> (i32, i32) {
+  game_state
+    .resource_reservations
+    .values()
+    .fold((0, 0), |(minerals, gas), reservation| {
+      (minerals + reservation.minerals, gas + reservation.gas)
+    })
+}
+
+/// Current minerals and gas minus what is reserved for every item other than `build_idx`
+pub fn available_for(game: &Game, game_state: &GameState, build_idx: Option<usize>) -> (i32, i32) {
+  let Some(player) = game.self_() else {
+    return (0, 0);
+  };
+
+  let (reserved_minerals, reserved_gas) = game_state
+    .resource_reservations
+    .iter()
+    .filter(|(&idx, _)| Some(idx) != build_idx)
+    .fold((0, 0), |(minerals, gas), (_, reservation)| {
+      (minerals + reservation.minerals, gas + reservation.gas)
+    });
+
+  (
+    player.minerals() - reserved_minerals,
+    player.gas() - reserved_gas,
+  )
+}
+
+/// Whether the item at `build_idx` can be paid for without touching money promised to other
+/// items. Earlier items that are ready but still waiting for a larva or drone have not
+/// reserved anything yet, so their cost is held back too.
+pub fn can_spend(game: &Game, game_state: &GameState, build_idx: usize) -> bool {
+  let Some(player) = game.self_() else {
+    return false;
+  };
+
+  let (available_minerals, available_gas) = available_for(game, game_state, Some(build_idx));
+
+  let (needed_minerals, needed_gas) = (game_state.build_order_index..=build_idx)
+    .filter(|&idx| {
+      idx == build_idx
+        || (!game_state.resource_reservations.contains_key(&idx)
+          && build_order_management::is_ready(game, game_state, idx))
+    })
+    .filter_map(|idx| game_state.build_order.get(idx))
+    .fold((0, 0), |(minerals, gas), item| {
+      let (item_minerals, item_gas) = build_order_management::item_cost(&player, item);
+      (minerals + item_minerals, gas + item_gas)
+    });
+
+  available_minerals >= needed_minerals && available_gas >= needed_gas
+}
+
+/// What is left for the upgrade queue once every reservation and every ready item in the build
+/// order window that hasn't reserved yet are paid for
+pub fn spare_resources(game: &Game, game_state: &GameState) -> (i32, i32) {
+  let Some(player) = game.self_() else {
+    return (0, 0);
+  };
+
+  let (available_minerals, available_gas) = available_for(game, game_state, None);
+  build_order_management::build_order_window_indices(game_state)
+    .filter(|idx| {
+      !game_state.resource_reservations.contains_key(idx)
+        && build_order_management::is_ready(game, game_state, *idx)
+    })
+    .filter_map(|idx| game_state.build_order.get(idx))
+    .fold(
+      (available_minerals, available_gas),
+      |(minerals, gas), item| {
+        let (item_minerals, item_gas) = build_order_management::item_cost(&player, item);
+        (minerals - item_minerals, gas - item_gas)
+      },
+    )
+}
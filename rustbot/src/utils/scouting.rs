@@ -0,0 +1,299 @@
+use rsbwapi::*;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::game_state::{GameState, WorkerAssignment, WorkerAssignmentType};
+use crate::utils::map_utils::geometry::{base_center, pixel_distance};
+
+/// Supply used, in BWAPI's doubled units, at which a drone goes scouting
+const DRONE_SCOUT_SUPPLY: i32 = 12 * 2;
+/// An enemy building this close to a start location gives the enemy main away
+const ENEMY_MAIN_RADIUS: f32 = 16.0 * 32.0;
+
+/// How the enemy main was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnemyMainSource {
+  /// An enemy building was seen there
+  Seen,
+  /// Every other start location was seen empty
+  Eliminated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoutKind {
+  Overlord,
+  Drone,
+}
+
+/// A start location that isn't ours
+#[derive(Debug, Clone)]
+pub struct ScoutedLocation {
+  pub tile: TilePosition,
+  /// First frame it was visible
+  pub explored_frame: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scout {
+  pub unit_id: usize,
+  pub kind: ScoutKind,
+  pub target: Option<TilePosition>,
+}
+
+/// Where the enemy main is, and the overlord and drone sent to find it
+#[derive(Debug, Clone, Default)]
+pub struct Scouting {
+  /// Nearest to our start location first
+  pub locations: Vec<ScoutedLocation>,
+  pub enemy_main: Option<TilePosition>,
+  pub enemy_main_source: Option<EnemyMainSource>,
+  pub found_frame: Option<i32>,
+  pub scouts: Vec<Scout>,
+  overlord_sent: bool,
+  drone_sent: bool,
+}
+
+impl Scouting {
+  fn is_targeted(&self, tile: TilePosition, except_unit: usize) -> bool {
+    self
+      .scouts
+      .iter()
+      .any(|scout| scout.unit_id != except_unit && scout.target == Some(tile))
+  }
+}
+
+/// The enemy main once seen or worked out, what squads attack and defend against
+pub fn enemy_main(game_state: &GameState) -> Option<TilePosition> {
+  game_state.scouting.enemy_main
+}
+
+fn tile_distance(a: TilePosition, b: TilePosition) -> i32 {
+  let dx = a.x - b.x;
+  let dy = a.y - b.y;
+  dx * dx + dy * dy
+}
+
+/// Lists the other start locations, nearest first. On two player maps the enemy main is
+/// known right away.
+pub fn init(game: &Game, game_state: &mut GameState) {
+  let Some(self_player) = game.self_() else {
+    return;
+  };
+  let my_start = self_player.get_start_location();
+  let mut locations: Vec<TilePosition> = game
+    .get_start_locations()
+    .into_iter()
+    .filter(|tile| *tile != my_start)
+    .collect();
+  locations.sort_by_key(|tile| tile_distance(*tile, my_start));
+
+  let scouting = &mut game_state.scouting;
+  scouting.locations = locations
+    .into_iter()
+    .map(|tile| ScoutedLocation {
+      tile,
+      explored_frame: None,
+    })
+    .collect();
+
+  if let [only] = scouting.locations.as_slice() {
+    println!(
+      "[SCOUT] enemy main is the only other start location ({}, {})",
+      only.tile.x, only.tile.y
+    );
+    scouting.enemy_main = Some(only.tile);
+    scouting.enemy_main_source = Some(EnemyMainSource::Eliminated);
+    scouting.found_frame = Some(0);
+  }
+}
+
+/// Marks start locations seen, looks for the enemy main and moves the scouts on
+pub fn update(game: &Game, game_state: &mut GameState) {
+  if game_state.scouting.enemy_main.is_some() {
+    return;
+  }
+  let Some(self_player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+
+  for location in game_state.scouting.locations.iter_mut() {
+    if location.explored_frame.is_none() && game.is_visible(location.tile) {
+      println!(
+        "[SCOUT] start location ({}, {}) seen at frame {}",
+        location.tile.x, location.tile.y, frame
+      );
+      location.explored_frame = Some(frame);
+    }
+  }
+
+  if let Some((tile, source)) = find_enemy_main(game, game_state, &self_player) {
+    println!(
+      "[SCOUT] enemy main at ({}, {}) at frame {} ({:?})",
+      tile.x, tile.y, frame, source
+    );
+    let scouting = &mut game_state.scouting;
+    scouting.enemy_main = Some(tile);
+    scouting.enemy_main_source = Some(source);
+    scouting.found_frame = Some(frame);
+    release_scouts(game, game_state, &self_player);
+    return;
+  }
+
+  send_scouts(game, game_state, &self_player);
+  control_scouts(game, game_state);
+}
+
+fn find_enemy_main(
+  game: &Game,
+  game_state: &GameState,
+  self_player: &Player,
+) -> Option<(TilePosition, EnemyMainSource)> {
+  let locations = &game_state.scouting.locations;
+  let seen = game
+    .get_all_units()
+    .into_iter()
+    .filter(|u| u.get_type().is_building() && self_player.is_enemy(&u.get_player()))
+    .find_map(|building| {
+      locations
+        .iter()
+        .map(|location| {
+          let distance = pixel_distance(base_center(&location.tile), building.get_position());
+          (location.tile, distance)
+        })
+        .filter(|(_, distance)| *distance <= ENEMY_MAIN_RADIUS)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(tile, _)| tile)
+    });
+  if let Some(tile) = seen {
+    return Some((tile, EnemyMainSource::Seen));
+  }
+
+  let mut unexplored = locations
+    .iter()
+    .filter(|location| location.explored_frame.is_none());
+  match (unexplored.next(), unexplored.next()) {
+    (Some(last), None) if locations.len() > 1 => Some((last.tile, EnemyMainSource::Eliminated)),
+    _ => None,
+  }
+}
+
+/// The first overlord goes out straight away, a drone once supply reaches `DRONE_SCOUT_SUPPLY`
+fn send_scouts(game: &Game, game_state: &mut GameState, self_player: &Player) {
+  if !game_state.scouting.overlord_sent {
+    if let Some(overlord) = self_player
+      .get_units()
+      .into_iter()
+      .find(|u| u.get_type() == UnitType::Zerg_Overlord && u.is_completed())
+    {
+      println!("[SCOUT] overlord {} sent scouting", overlord.get_id());
+      game_state.scouting.scouts.push(Scout {
+        unit_id: overlord.get_id(),
+        kind: ScoutKind::Overlord,
+        target: None,
+      });
+      game_state.scouting.overlord_sent = true;
+    }
+  }
+
+  if !game_state.scouting.drone_sent && self_player.supply_used() >= DRONE_SCOUT_SUPPLY {
+    // A mineral drone that isn't carrying anything, so no income is thrown away
+    let drone = game_state
+      .worker_assignments
+      .iter()
+      .filter(|(_, assignment)| assignment.assignment_type == WorkerAssignmentType::Gathering)
+      .filter(|(&worker_id, _)| !game_state.worker_defense.is_pulled(worker_id))
+      .filter_map(|(&worker_id, _)| game.get_unit(worker_id))
+      .find(|drone| drone.is_gathering_minerals() && !drone.is_carrying_minerals());
+    if let Some(drone) = drone {
+      println!("[SCOUT] drone {} sent scouting", drone.get_id());
+      game_state.scouting.scouts.push(Scout {
+        unit_id: drone.get_id(),
+        kind: ScoutKind::Drone,
+        target: None,
+      });
+      game_state.scouting.drone_sent = true;
+    }
+  }
+}
+
+/// Sends every scout to the nearest start location not seen yet, the two scouts to different
+/// ones while there are two left
+fn control_scouts(game: &Game, game_state: &mut GameState) {
+  let scouting = &mut game_state.scouting;
+  scouting.scouts.retain(|scout| {
+    let alive = game.get_unit(scout.unit_id).is_some_and(|u| u.exists());
+    if !alive {
+      println!("[SCOUT] {:?} {} died scouting", scout.kind, scout.unit_id);
+    }
+    alive
+  });
+
+  for index in 0..scouting.scouts.len() {
+    let scout = &scouting.scouts[index];
+    let Some(unit) = game.get_unit(scout.unit_id) else {
+      continue;
+    };
+    let target_explored = scout.target.is_none_or(|target| {
+      scouting
+        .locations
+        .iter()
+        .any(|location| location.tile == target && location.explored_frame.is_some())
+    });
+
+    if target_explored {
+      let unit_tile = unit.get_tile_position();
+      let unexplored = || {
+        scouting
+          .locations
+          .iter()
+          .filter(|location| location.explored_frame.is_none())
+      };
+      let target = unexplored()
+        .filter(|location| !scouting.is_targeted(location.tile, scout.unit_id))
+        .min_by_key(|location| tile_distance(location.tile, unit_tile))
+        .or_else(|| unexplored().min_by_key(|location| tile_distance(location.tile, unit_tile)))
+        .map(|location| location.tile);
+      scouting.scouts[index].target = target;
+    }
+
+    let Some(target) = scouting.scouts[index].target else {
+      continue;
+    };
+    let target_position = base_center(&target);
+    if unit.get_order_target_position() != Some(target_position) {
+      let _ = unit.move_(target_position);
+    }
+  }
+
+  let drone_targets: Vec<(usize, TilePosition)> = scouting
+    .scouts
+    .iter()
+    .filter(|scout| scout.kind == ScoutKind::Drone)
+    .filter_map(|scout| Some((scout.unit_id, scout.target?)))
+    .collect();
+  for (drone_id, target) in drone_targets {
+    game_state
+      .worker_assignments
+      .insert(drone_id, WorkerAssignment::scouting((target.x, target.y)));
+  }
+}
+
+/// Drones go back to mining and overlords back home once the enemy main is known
+fn release_scouts(game: &Game, game_state: &mut GameState, self_player: &Player) {
+  let home = base_center(&self_player.get_start_location());
+  for scout in std::mem::take(&mut game_state.scouting.scouts) {
+    let Some(unit) = game.get_unit(scout.unit_id) else {
+      continue;
+    };
+    match scout.kind {
+      ScoutKind::Overlord => {
+        let _ = unit.move_(home);
+      }
+      ScoutKind::Drone => {
+        // Unassigned and idle, so the next assignment pass sends it to a mineral
+        game_state.worker_assignments.remove(&scout.unit_id);
+        let _ = unit.stop();
+      }
+    }
+  }
+}
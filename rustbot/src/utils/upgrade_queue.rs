@@ -0,0 +1,535 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use crate::utils::build_order_management;
+use crate::utils::build_orders::build_order_item::BuildOrderItem;
+use crate::utils::building_stuff::researching_stuff;
+use crate::utils::game_state::{
+  BuildOrderItemStatus, BuildingAssignment, GameState, ReservationKind,
+};
+use crate::utils::military::military_management;
+use crate::utils::resource_reservations;
+use rsbwapi::*;
+
+/// Build order upgrades go ahead of anything the army asked for
+const BUILD_ORDER_PRIORITY: i32 = 1000;
+/// Half supply of army an upgrade has to help before it is queued for the army, 8 shown supply
+const MIN_ARMY_SUPPLY_FOR_UPGRADE: i32 = 16;
+/// Frames a building gets to show it started before the research is ordered again
+const START_TIMEOUT_FRAMES: i32 = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResearchKind {
+  Upgrade(UpgradeType),
+  Tech(TechType),
+}
+
+impl ResearchKind {
+  fn what_researches(&self) -> UnitType {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => upgrade_type.what_upgrades(),
+      ResearchKind::Tech(tech_type) => tech_type.what_researches(),
+    }
+  }
+
+  /// Finished buildings needed besides the one researching, e.g. a Lair for level 2
+  fn required_unit(&self, level: i32) -> UnitType {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => upgrade_type.whats_required(level),
+      ResearchKind::Tech(tech_type) => tech_type.required_unit(),
+    }
+  }
+
+  pub fn cost(&self, level: i32) -> (i32, i32) {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => (
+        upgrade_type.mineral_price(level),
+        upgrade_type.gas_price(level),
+      ),
+      ResearchKind::Tech(tech_type) => (tech_type.mineral_price(), tech_type.gas_price()),
+    }
+  }
+
+  fn max_level(&self) -> i32 {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => upgrade_type.max_repeats(),
+      ResearchKind::Tech(_) => 1,
+    }
+  }
+
+  /// Finished levels, 0 or 1 for tech
+  fn current_level(&self, player: &Player) -> i32 {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => player.get_upgrade_level(*upgrade_type),
+      ResearchKind::Tech(tech_type) => player.has_researched(*tech_type) as i32,
+    }
+  }
+
+  fn in_progress(&self, player: &Player) -> bool {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => player.is_upgrading(*upgrade_type),
+      ResearchKind::Tech(tech_type) => player.is_researching(*tech_type),
+    }
+  }
+
+  fn is_researched_by(&self, building: &Unit) -> bool {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => {
+        building.is_upgrading() && building.get_upgrade() == *upgrade_type
+      }
+      ResearchKind::Tech(tech_type) => {
+        building.is_researching() && building.get_tech() == *tech_type
+      }
+    }
+  }
+
+  fn start(&self, building: &Unit) -> bool {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => building.upgrade(*upgrade_type).is_ok(),
+      ResearchKind::Tech(tech_type) => building.research(*tech_type).is_ok(),
+    }
+  }
+
+  /// Holds the building for the build order item until it is seen researching
+  fn building_assignment(&self, build_idx: usize) -> BuildingAssignment {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => {
+        BuildingAssignment::new_upgrade(*upgrade_type, build_idx)
+      }
+      ResearchKind::Tech(tech_type) => BuildingAssignment::new_research(*tech_type, build_idx),
+    }
+  }
+
+  pub fn describe(&self, level: i32) -> String {
+    match self {
+      ResearchKind::Upgrade(upgrade_type) => format!("{:?} level {}", upgrade_type, level),
+      ResearchKind::Tech(tech_type) => format!("{:?}", tech_type),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResearchSource {
+  /// Handed over by the build order item at this index, which completes with the research
+  BuildOrder(usize),
+  /// Picked from the upgrades that help the current army
+  Army,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedResearch {
+  pub kind: ResearchKind,
+  /// Level this entry researches, always 1 for tech
+  pub level: i32,
+  /// Higher starts first
+  pub priority: i32,
+  pub source: ResearchSource,
+  pub queued_frame: i32,
+  /// Building ordered to research it and the frame it was ordered
+  pub started: Option<(usize, i32)>,
+  /// Why it hasn't started, shown on the status server
+  pub waiting_for: String,
+}
+
+pub struct UpgradeQueue {
+  pub entries: Vec<QueuedResearch>,
+  /// Whether upgrades are queued on their own from the army composition
+  pub from_army: bool,
+}
+
+impl Default for UpgradeQueue {
+  fn default() -> Self {
+    Self {
+      entries: vec![],
+      from_army: true,
+    }
+  }
+}
+
+impl UpgradeQueue {
+  /// Whether any level of the research is queued or running
+  pub fn contains(&self, kind: ResearchKind) -> bool {
+    self.entries.iter().any(|entry| entry.kind == kind)
+  }
+
+  /// Follows build order items to their new index after an edit, entries for removed items go
+  /// back to the army
+  pub fn remap_build_order(&mut self, new_index: impl Fn(usize) -> Option<usize>) {
+    for entry in self.entries.iter_mut() {
+      if let ResearchSource::BuildOrder(idx) = entry.source {
+        entry.source = new_index(idx).map_or(ResearchSource::Army, ResearchSource::BuildOrder);
+      }
+    }
+  }
+
+  /// The level after everything finished, running or queued
+  fn next_level(&self, player: &Player, kind: ResearchKind) -> i32 {
+    let researched = kind.current_level(player) + kind.in_progress(player) as i32;
+    let queued = self
+      .entries
+      .iter()
+      .filter(|entry| entry.kind == kind)
+      .map(|entry| entry.level)
+      .max()
+      .unwrap_or(0);
+    researched.max(queued) + 1
+  }
+}
+
+/// Hands an upgrade or research item from the build order to the queue, which finds it a
+/// building. The item stays assigned until the building is seen researching it.
+pub fn enqueue_build_order_item(game: &Game, game_state: &mut GameState, build_idx: usize) {
+  let Some(player) = game.self_() else {
+    return;
+  };
+  if game_state
+    .upgrade_queue
+    .entries
+    .iter()
+    .any(|entry| entry.source == ResearchSource::BuildOrder(build_idx))
+  {
+    // Queued before and retried after its building died, the entry is still waiting
+    build_order_management::set_item_status(game_state, build_idx, BuildOrderItemStatus::Assigned);
+    return;
+  }
+  let kind = match game_state.build_order.get(build_idx) {
+    Some(BuildOrderItem::Upgrade { upgrade_type, .. }) => ResearchKind::Upgrade(*upgrade_type),
+    Some(BuildOrderItem::Research { tech_type, .. }) => ResearchKind::Tech(*tech_type),
+    _ => return,
+  };
+
+  // Take over what the army already asked for rather than queue the next level
+  let from_army = game_state
+    .upgrade_queue
+    .entries
+    .iter_mut()
+    .filter(|entry| entry.kind == kind && entry.source == ResearchSource::Army)
+    .min_by_key(|entry| entry.level);
+  let level = if let Some(entry) = from_army {
+    entry.source = ResearchSource::BuildOrder(build_idx);
+    entry.priority = BUILD_ORDER_PRIORITY;
+    entry.level
+  } else {
+    let level = game_state.upgrade_queue.next_level(&player, kind);
+    if level > kind.max_level() {
+      build_order_management::skip_item(
+        game_state,
+        build_idx,
+        &format!(
+          "{} is past the last level or already researched",
+          kind.describe(level)
+        ),
+      );
+      return;
+    }
+    game_state.upgrade_queue.entries.push(QueuedResearch {
+      kind,
+      level,
+      priority: BUILD_ORDER_PRIORITY,
+      source: ResearchSource::BuildOrder(build_idx),
+      queued_frame: game.get_frame_count(),
+      started: None,
+      waiting_for: String::new(),
+    });
+    level
+  };
+
+  build_order_management::set_item_status(game_state, build_idx, BuildOrderItemStatus::Assigned);
+  println!(
+    "[UPGRADES] queued {} for build order index {}",
+    kind.describe(level),
+    build_idx
+  );
+}
+
+/// Drops finished entries, queues upgrades for the army and starts whatever has an idle
+/// building. The first build order entry that can start holds a reservation, the rest use
+/// what the build order window doesn't need.
+pub fn update(game: &Game, game_state: &mut GameState) {
+  let Some(player) = game.self_() else {
+    return;
+  };
+  let frame = game.get_frame_count();
+
+  let mut finished: Vec<(usize, String)> = vec![];
+  game_state.upgrade_queue.entries.retain(|entry| {
+    let done = entry.kind.current_level(&player) >= entry.level;
+    if let (true, ResearchSource::BuildOrder(build_idx)) = (done, entry.source) {
+      finished.push((build_idx, entry.kind.describe(entry.level)));
+    }
+    !done
+  });
+  // Researched without the item ever seeing its building start, e.g. a level the army took
+  for (build_idx, what) in finished {
+    build_order_management::skip_item(
+      game_state,
+      build_idx,
+      &format!("{} is already researched", what),
+    );
+  }
+
+  let mut started: Vec<(usize, usize, String)> = vec![];
+  let mut timed_out: Vec<usize> = vec![];
+  for entry in game_state.upgrade_queue.entries.iter_mut() {
+    let Some((building_id, ordered_frame)) = entry.started else {
+      continue;
+    };
+    let researching = game
+      .get_unit(building_id)
+      .is_some_and(|building| building.exists() && entry.kind.is_researched_by(&building));
+    if researching {
+      if let ResearchSource::BuildOrder(build_idx) = entry.source {
+        started.push((build_idx, building_id, entry.kind.describe(entry.level)));
+      }
+    } else if frame - ordered_frame > START_TIMEOUT_FRAMES {
+      println!(
+        "[UPGRADES] {} not running on building {}, queued again",
+        entry.kind.describe(entry.level),
+        building_id
+      );
+      entry.started = None;
+      if let ResearchSource::BuildOrder(build_idx) = entry.source {
+        timed_out.push(build_idx);
+      }
+    }
+  }
+  // Only once it runs, the build order item then completes when the building stops
+  for (build_idx, building_id, what) in started {
+    if build_order_management::is_unstarted(game_state, build_idx) {
+      build_order_management::mark_item_started(
+        game_state,
+        build_idx,
+        Some(building_id),
+        &format!("{} started", what),
+      );
+    }
+  }
+  game_state
+    .building_assignments
+    .retain(|_, assignment| !timed_out.contains(&assignment.build_order_index));
+
+  if game_state.upgrade_queue.from_army {
+    queue_army_upgrades(game, game_state, &player);
+  }
+
+  // Stable, so equal priorities keep the order they were queued in
+  game_state
+    .upgrade_queue
+    .entries
+    .sort_by_key(|entry| Reverse(entry.priority));
+  start_queued_research(game, game_state, &player);
+}
+
+/// Own finished unit types, for checking tech requirements
+fn completed_types(game: &Game, player: &Player) -> Vec<UnitType> {
+  game
+    .get_all_units()
+    .into_iter()
+    .filter(|u| u.get_player().get_id() == player.get_id() && u.is_completed())
+    .map(|u| u.get_type())
+    .collect()
+}
+
+/// Queues the next level of every upgrade that helps enough of the army, priority by how much
+/// supply it helps
+fn queue_army_upgrades(game: &Game, game_state: &mut GameState, player: &Player) {
+  let mut army_supply: HashMap<UpgradeType, i32> = HashMap::new();
+  for unit in game.get_all_units() {
+    if unit.get_player().get_id() != player.get_id()
+      || !unit.is_completed()
+      || !military_management::is_military_unit(&unit)
+    {
+      continue;
+    }
+    let unit_type = unit.get_type();
+    for upgrade_type in researching_stuff::get_relevant_upgrades_for_unit(unit_type) {
+      *army_supply.entry(upgrade_type).or_insert(0) += unit_type.supply_required();
+    }
+  }
+
+  let queue = &mut game_state.upgrade_queue;
+  // The army shrank, drop what it no longer needs unless a building is already on it
+  queue.entries.retain(|entry| {
+    entry.source != ResearchSource::Army
+      || entry.started.is_some()
+      || matches!(entry.kind, ResearchKind::Upgrade(upgrade_type)
+        if army_supply.get(&upgrade_type).copied().unwrap_or(0) >= MIN_ARMY_SUPPLY_FOR_UPGRADE)
+  });
+  for entry in queue.entries.iter_mut() {
+    if let (ResearchSource::Army, ResearchKind::Upgrade(upgrade_type)) = (entry.source, entry.kind)
+    {
+      entry.priority = army_supply.get(&upgrade_type).copied().unwrap_or(0);
+    }
+  }
+
+  let mut wanted: Vec<(UpgradeType, i32)> = army_supply
+    .into_iter()
+    .filter(|(_, supply)| *supply >= MIN_ARMY_SUPPLY_FOR_UPGRADE)
+    .collect();
+  wanted.sort_by_key(|(_, supply)| Reverse(*supply));
+
+  for (upgrade_type, supply) in wanted {
+    let kind = ResearchKind::Upgrade(upgrade_type);
+    // One level waiting at a time, the next is queued once it starts
+    if queue
+      .entries
+      .iter()
+      .any(|entry| entry.kind == kind && entry.started.is_none())
+    {
+      continue;
+    }
+    let level = queue.next_level(player, kind);
+    if level > kind.max_level() {
+      continue;
+    }
+    println!(
+      "[UPGRADES] queued {} for {} army supply",
+      kind.describe(level),
+      supply / 2
+    );
+    queue.entries.push(QueuedResearch {
+      kind,
+      level,
+      priority: supply,
+      source: ResearchSource::Army,
+      queued_frame: game.get_frame_count(),
+      started: None,
+      waiting_for: String::new(),
+    });
+  }
+}
+
+/// Why the entry can't start yet regardless of resources, `None` once a building can take it
+fn blocked_reason(
+  entry: &QueuedResearch,
+  player: &Player,
+  completed: &[UnitType],
+  queue: &[QueuedResearch],
+) -> Option<String> {
+  // Levels go one after the other, the building only ever researches the next one
+  let next_level = entry.kind.current_level(player) + 1;
+  if entry.kind.in_progress(player) || entry.level > next_level {
+    return Some(format!(
+      "{} to finish first",
+      entry.kind.describe(entry.level - 1)
+    ));
+  }
+  if queue
+    .iter()
+    .any(|other| other.kind == entry.kind && other.level < entry.level && other.started.is_none())
+  {
+    return Some(format!(
+      "{} to start first",
+      entry.kind.describe(entry.level - 1)
+    ));
+  }
+
+  [
+    entry.kind.what_researches(),
+    entry.kind.required_unit(entry.level),
+  ]
+  .into_iter()
+  .filter(|required| *required != UnitType::None)
+  .find(|required| {
+    !completed
+      .iter()
+      .any(|owned| build_order_management::satisfies_requirement(*owned, *required))
+  })
+  .map(|required| format!("{:?}", required))
+}
+
+/// Reserves for the first entry that only waits for money when it comes from the build order,
+/// so larvae and drones spending every frame can't keep it from ever starting
+fn reserve_for_queue_head(
+  game: &Game,
+  game_state: &mut GameState,
+  player: &Player,
+  completed: &[UnitType],
+) {
+  let queue = &game_state.upgrade_queue.entries;
+  let head = queue.iter().find(|entry| {
+    entry.started.is_none() && blocked_reason(entry, player, completed, queue).is_none()
+  });
+  if let Some(ResearchSource::BuildOrder(build_idx)) = head.map(|entry| entry.source) {
+    resource_reservations::reserve(game, game_state, build_idx, ReservationKind::Research);
+  }
+}
+
+fn start_queued_research(game: &Game, game_state: &mut GameState, player: &Player) {
+  let frame = game.get_frame_count();
+  let completed = completed_types(game, player);
+  reserve_for_queue_head(game, game_state, player, &completed);
+  let (mut minerals, mut gas) = resource_reservations::spare_resources(game, game_state);
+  let mut busy: HashSet<usize> = game_state
+    .upgrade_queue
+    .entries
+    .iter()
+    .filter_map(|entry| entry.started.map(|(building_id, _)| building_id))
+    .collect();
+
+  for i in 0..game_state.upgrade_queue.entries.len() {
+    let entry = game_state.upgrade_queue.entries[i].clone();
+    if entry.started.is_some() {
+      continue;
+    }
+
+    let waiting_for = if let Some(reason) = blocked_reason(
+      &entry,
+      player,
+      &completed,
+      &game_state.upgrade_queue.entries,
+    ) {
+      Some(reason)
+    } else if let Some(building) = researching_stuff::find_idle_researcher(
+      game,
+      game_state,
+      player,
+      entry.kind.what_researches(),
+      &busy,
+    ) {
+      let (needed_minerals, needed_gas) = entry.kind.cost(entry.level);
+      let affordable = match entry.source {
+        ResearchSource::BuildOrder(build_idx)
+          if game_state.resource_reservations.contains_key(&build_idx) =>
+        {
+          resource_reservations::can_spend(game, game_state, build_idx)
+        }
+        _ => {
+          // Held back even when short, so cheaper entries behind it don't keep spending first
+          minerals -= needed_minerals;
+          gas -= needed_gas;
+          minerals >= 0 && gas >= 0
+        }
+      };
+      if !affordable {
+        Some(format!("{} minerals, {} gas", needed_minerals, needed_gas))
+      } else if entry.kind.start(&building) {
+        let building_id = building.get_id();
+        println!(
+          "[UPGRADES] started {} on {:?} {}",
+          entry.kind.describe(entry.level),
+          building.get_type(),
+          building_id
+        );
+        busy.insert(building_id);
+        game_state.upgrade_queue.entries[i].started = Some((building_id, frame));
+        if let ResearchSource::BuildOrder(build_idx) = entry.source {
+          game_state
+            .building_assignments
+            .insert(building_id, entry.kind.building_assignment(build_idx));
+        }
+        None
+      } else {
+        Some(format!(
+          "{:?} {} to accept the order",
+          building.get_type(),
+          building.get_id()
+        ))
+      }
+    } else {
+      Some(format!("an idle {:?}", entry.kind.what_researches()))
+    };
+
+    game_state.upgrade_queue.entries[i].waiting_for = waiting_for.unwrap_or_default();
+  }
+}
@@ -1,11 +1,14 @@
 use crate::utils::{
+  build_order_management,
   build_orders::build_order_item::BuildOrderItem,
-  building_stuff::build_location_utils,
+  building_stuff::build_location_utils::{self, PlacementContext},
   game_state::{GameState, WorkerAssignment, WorkerAssignmentType},
+  map_utils::geometry::{base_center, pixel_distance, BASE_RESOURCE_RADIUS},
+  resource_reservations,
 };
 use rand::seq::SliceRandom;
 use rsbwapi::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 fn get_my_workers(game: &Game) -> Vec<Unit> {
   let self_player = match game.self_() {
@@ -20,127 +23,209 @@ fn get_my_workers(game: &Game) -> Vec<Unit> {
     .collect()
 }
 
-pub fn update_assignments(game: &Game, game_state: &mut GameState) {
-  let my_units = get_my_workers(game);
-  let workers: Vec<_> = my_units.iter().collect();
-
-  let mut assignments = game_state.worker_assignments.clone();
+/// Drones wanted on each mineral patch, extractors follow the gas plan
+pub const WORKERS_PER_PATCH: usize = 2;
+
+/// Gatherers against targets for one base with a finished hatchery
+#[derive(Debug, Clone)]
+pub struct BaseSaturation {
+  pub base_index: usize,
+  pub mineral_ids: Vec<usize>,
+  pub extractor_ids: Vec<usize>,
+  pub mineral_workers: usize,
+  pub gas_workers: usize,
+  pub workers_per_extractor: usize,
+}
 
-  remove_dead_workers(&mut assignments, &workers);
+impl BaseSaturation {
+  pub fn mineral_target(&self) -> usize {
+    self.mineral_ids.len() * WORKERS_PER_PATCH
+  }
 
-  let mut claimed_base_indices = std::collections::HashSet::new();
-  claimed_base_indices.insert(0); // Main base is always claimed
+  pub fn gas_target(&self) -> usize {
+    self.extractor_ids.len() * self.workers_per_extractor
+  }
 
-  for (idx, item) in game_state.build_order.iter().enumerate() {
-    if idx >= game_state.build_order_index {
-      break; // Only look at completed items
-    }
-    if let BuildOrderItem::Unit {
-      unit_type,
-      base_index,
-    } = item
-    {
-      if *unit_type == UnitType::Zerg_Hatchery {
-        if let Some(base_idx) = base_index {
-          claimed_base_indices.insert(*base_idx);
-        }
-      }
-    }
+  /// Gatherers still missing before the base is saturated
+  pub fn unmet(&self) -> usize {
+    self.mineral_target().saturating_sub(self.mineral_workers)
+      + self.gas_target().saturating_sub(self.gas_workers)
   }
+}
 
-  // Collect minerals and extractors from all claimed bases
-  let static_minerals = game.get_static_minerals();
-  let mut minerals: Vec<&Unit> = Vec::new();
+/// Minerals and finished extractors of a base we own
+struct BaseResources<'a> {
+  base_index: usize,
+  minerals: Vec<&'a Unit>,
+  extractors: Vec<&'a Unit>,
+}
 
-  for &base_idx in &claimed_base_indices {
-    if let Some(base_location) = game_state.base_locations.get(base_idx) {
-      let base_pos = Position::new(base_location.x * 32 + 64, base_location.y * 32 + 48);
+impl BaseResources<'_> {
+  fn mineral_workers(&self, worker_count: &HashMap<usize, usize>) -> usize {
+    self
+      .minerals
+      .iter()
+      .map(|m| worker_count.get(&m.get_id()).copied().unwrap_or(0))
+      .sum()
+  }
 
-      let base_minerals: Vec<_> = static_minerals
-        .iter()
-        .filter(|m| {
-          let mineral_pos = m.get_position();
-          let dx = (mineral_pos.x - base_pos.x) as f32;
-          let dy = (mineral_pos.y - base_pos.y) as f32;
-          let distance = (dx * dx + dy * dy).sqrt();
-          distance <= 12.0 * 32.0
-        })
-        .collect();
-
-      minerals.extend(base_minerals);
-    }
+  fn mineral_target(&self) -> usize {
+    self.minerals.len() * WORKERS_PER_PATCH
   }
+}
+
+/// Active bases from the registry, with the minerals still there and their finished extractors
+fn owned_base_resources<'a>(
+  game_state: &GameState,
+  minerals: &'a [Unit],
+  extractors: &'a [Unit],
+) -> Vec<BaseResources<'a>> {
+  game_state
+    .bases
+    .iter()
+    .filter(|base| base.is_active())
+    .map(|base| BaseResources {
+      base_index: base.index,
+      minerals: minerals
+        .iter()
+        .filter(|m| m.exists() && base.mineral_ids.contains(&m.get_id()))
+        .collect(),
+      extractors: extractors
+        .iter()
+        .filter(|e| base.geyser_ids.contains(&e.get_id()))
+        .collect(),
+    })
+    .collect()
+}
 
-  let extractors: Vec<_> = game
+fn completed_extractors(game: &Game) -> Vec<Unit> {
+  game
     .get_all_units()
     .into_iter()
     .filter(|u| {
-      if u.get_type() != UnitType::Zerg_Extractor
-        || u.get_player().get_id() != game.self_().map(|p| p.get_id()).unwrap_or(0)
-        || !u.is_completed()
-      {
-        return false;
-      }
-
-      // Check if extractor is near any claimed base
-      let extractor_pos = u.get_position();
-      for &base_idx in &claimed_base_indices {
-        if let Some(base_location) = game_state.base_locations.get(base_idx) {
-          let base_pos = Position::new(base_location.x * 32 + 64, base_location.y * 32 + 48);
-          let dx = (extractor_pos.x - base_pos.x) as f32;
-          let dy = (extractor_pos.y - base_pos.y) as f32;
-          let distance = (dx * dx + dy * dy).sqrt();
-          if distance <= 12.0 * 32.0 {
-            return true;
-          }
-        }
-      }
-      false
+      u.get_type() == UnitType::Zerg_Extractor
+        && u.get_player().get_id() == game.self_().map(|p| p.get_id()).unwrap_or(0)
+        && u.is_completed()
     })
+    .collect()
+}
+
+/// What drones can gather from at our bases, shared by new drones and the assignment pass
+struct GatheringTargets<'a> {
+  bases: Vec<BaseResources<'a>>,
+  minerals: Vec<&'a Unit>,
+  extractors: Vec<&'a Unit>,
+}
+
+impl<'a> GatheringTargets<'a> {
+  fn new(game_state: &GameState, minerals: &'a [Unit], extractors: &'a [Unit]) -> Self {
+    let bases = owned_base_resources(game_state, minerals, extractors);
+    let minerals = bases
+      .iter()
+      .flat_map(|base| base.minerals.iter().copied())
+      .collect();
+    let extractors = bases
+      .iter()
+      .flat_map(|base| base.extractors.iter().copied())
+      .collect();
+    Self {
+      bases,
+      minerals,
+      extractors,
+    }
+  }
+
+  fn is_extractor(&self, unit_id: usize) -> bool {
+    self.extractors.iter().any(|e| e.get_id() == unit_id)
+  }
+
+  /// An extractor short of the gas plan first, then the base missing the most mineral drones,
+  /// then any patch with room for a third
+  fn pick(
+    &self,
+    worker_count: &HashMap<usize, usize>,
+    extractor_saturation: usize,
+  ) -> Option<usize> {
+    let undersaturated_extractor = self.extractors.iter().find(|extractor| {
+      worker_count.get(&extractor.get_id()).copied().unwrap_or(0) < extractor_saturation
+    });
+    if let Some(extractor) = undersaturated_extractor {
+      return Some(extractor.get_id());
+    }
+
+    let neediest_base = self
+      .bases
+      .iter()
+      .filter(|base| base.mineral_workers(worker_count) < base.mineral_target())
+      .max_by_key(|base| base.mineral_target() - base.mineral_workers(worker_count));
+    neediest_base
+      .and_then(|base| {
+        find_least_saturated_mineral(&base.minerals, worker_count, WORKERS_PER_PATCH)
+      })
+      .or_else(|| find_least_saturated_mineral(&self.minerals, worker_count, 3))
+      .map(|mineral| mineral.get_id())
+  }
+}
+
+/// Moves drones over on the next assignment pass, for a hatchery that just finished
+pub fn schedule_worker_transfer(game_state: &mut GameState, reason: &str) {
+  println!("[WORKERS] transfer scheduled | {}", reason);
+  game_state.worker_transfer_pending = true;
+}
+
+pub fn update_assignments(game: &Game, game_state: &mut GameState) {
+  let my_units = get_my_workers(game);
+  let workers: Vec<_> = my_units.iter().collect();
+
+  let mut assignments = game_state.worker_assignments.clone();
+
+  remove_dead_workers(&mut assignments, &workers);
+
+  let static_minerals = game.get_static_minerals();
+  let all_extractors = completed_extractors(game);
+  let targets = GatheringTargets::new(game_state, &static_minerals, &all_extractors);
+  let GatheringTargets {
+    bases,
+    minerals,
+    extractors,
+  } = &targets;
+
+  // Drones on a patch that ran out or at a base we lost go idle and get a new target below
+  let gathering_targets: HashSet<usize> = bases
+    .iter()
+    .flat_map(|base| base.minerals.iter().chain(base.extractors.iter()))
+    .map(|u| u.get_id())
     .collect();
+  assignments.retain(|_, assignment| {
+    assignment.assignment_type != WorkerAssignmentType::Gathering
+      || assignment
+        .target_unit
+        .is_some_and(|target| gathering_targets.contains(&target))
+  });
 
   let unassigned_idle_workers: Vec<_> = workers
     .iter()
-    .filter(|w| w.is_idle() && !assignments.contains_key(&w.get_id()))
+    .filter(|w| {
+      (w.is_idle() || w.is_gathering_minerals() || w.is_gathering_gas())
+        && !assignments.contains_key(&w.get_id())
+    })
     .copied()
     .collect();
 
   let mut mineral_worker_count = count_workers_per_resource(&assignments);
 
-  // Determine extractor saturation based on worker count
-  let total_workers = workers.len();
-  let extractor_saturation = if total_workers > 22 { 3 } else { 2 };
+  // Drones per extractor follow the gas the next items still need
+  let extractor_saturation = game_state.gas_plan.workers_per_extractor;
 
   for worker in unassigned_idle_workers {
-    let undersaturated_extractor = extractors.iter().find(|extractor| {
-      let extractor_id = extractor.get_id();
-      let worker_count = assignments
-        .values()
-        .filter(|a| {
-          a.assignment_type == WorkerAssignmentType::Gathering
-            && a.target_unit == Some(extractor_id)
-        })
-        .count();
-      worker_count < extractor_saturation
-    });
-
-    if let Some(extractor) = undersaturated_extractor {
-      let extractor_id = extractor.get_id();
-      assignments.insert(worker.get_id(), WorkerAssignment::gathering(extractor_id));
-    } else {
-      let best_mineral = find_least_saturated_mineral(&minerals, &mineral_worker_count, 2)
-        .or_else(|| find_least_saturated_mineral(&minerals, &mineral_worker_count, 3));
-
-      if let Some(mineral) = best_mineral {
-        let mineral_id = mineral.get_id();
-        assignments.insert(worker.get_id(), WorkerAssignment::gathering(mineral_id));
-        *mineral_worker_count.entry(mineral_id).or_insert(0) += 1;
-      }
+    if let Some(target_id) = targets.pick(&mineral_worker_count, extractor_saturation) {
+      assignments.insert(worker.get_id(), WorkerAssignment::gathering(target_id));
+      *mineral_worker_count.entry(target_id).or_insert(0) += 1;
     }
   }
 
   // If there are still undersaturated extractors, reassign workers from minerals
-  for extractor in &extractors {
+  for extractor in extractors {
     let extractor_id = extractor.get_id();
     let worker_count = assignments
       .values()
@@ -155,7 +240,7 @@ pub fn update_assignments(game: &Game, game_state: &mut GameState) {
         if assignment.assignment_type == WorkerAssignmentType::Gathering {
           if let Some(target_id) = assignment.target_unit {
             // Check if the target is a mineral (not an extractor)
-            if !extractors.iter().any(|e| e.get_id() == target_id) {
+            if !targets.is_extractor(target_id) {
               return Some(*worker_id);
             }
           }
@@ -166,27 +251,170 @@ pub fn update_assignments(game: &Game, game_state: &mut GameState) {
       if let Some(worker_id) = worker_to_reassign {
         assignments.insert(worker_id, WorkerAssignment::gathering(extractor_id));
         println!(
-          "Reassigned worker {} from minerals to extractor {}",
-          worker_id, extractor_id
+          "[GAS] drone {} moves from minerals to extractor {} | {}",
+          worker_id, extractor_id, game_state.gas_plan.reason
         );
       }
     }
   }
 
+  // Drones over the gas plan go back to minerals
+  for extractor in extractors {
+    let extractor_id = extractor.get_id();
+    let on_gas: Vec<usize> = assignments
+      .iter()
+      .filter(|(_, a)| {
+        a.assignment_type == WorkerAssignmentType::Gathering && a.target_unit == Some(extractor_id)
+      })
+      .map(|(worker_id, _)| *worker_id)
+      .collect();
+
+    for worker_id in on_gas.into_iter().skip(extractor_saturation) {
+      let Some(mineral) = find_least_saturated_mineral(minerals, &mineral_worker_count, 3) else {
+        break;
+      };
+      let mineral_id = mineral.get_id();
+      assignments.insert(worker_id, WorkerAssignment::gathering(mineral_id));
+      *mineral_worker_count.entry(mineral_id).or_insert(0) += 1;
+      println!(
+        "[GAS] drone {} moves from extractor {} to minerals {} | {}",
+        worker_id, extractor_id, mineral_id, game_state.gas_plan.reason
+      );
+    }
+  }
+
+  if game_state.worker_transfer_pending {
+    let moved = transfer_workers(bases, &mut assignments);
+    println!("[WORKERS] transferred {} drones between bases", moved);
+    game_state.worker_transfer_pending = false;
+  }
+
+  let worker_count = count_workers_per_resource(&assignments);
+  game_state.base_saturation = bases
+    .iter()
+    .map(|base| BaseSaturation {
+      base_index: base.base_index,
+      mineral_ids: base.minerals.iter().map(|m| m.get_id()).collect(),
+      extractor_ids: base.extractors.iter().map(|e| e.get_id()).collect(),
+      mineral_workers: base.mineral_workers(&worker_count),
+      gas_workers: base
+        .extractors
+        .iter()
+        .map(|e| worker_count.get(&e.get_id()).copied().unwrap_or(0))
+        .sum(),
+      workers_per_extractor: extractor_saturation,
+    })
+    .collect();
   game_state.worker_assignments = assignments;
 }
 
+/// Gives a drone that just hatched a resource straight away, instead of waiting for it to
+/// go idle at the rally point
+pub fn assign_new_worker(game: &Game, game_state: &mut GameState, worker: &Unit) {
+  if game_state.worker_assignments.contains_key(&worker.get_id()) {
+    return;
+  }
+  let static_minerals = game.get_static_minerals();
+  let all_extractors = completed_extractors(game);
+  let targets = GatheringTargets::new(game_state, &static_minerals, &all_extractors);
+  let worker_count = count_workers_per_resource(&game_state.worker_assignments);
+
+  let Some(target_id) = targets.pick(&worker_count, game_state.gas_plan.workers_per_extractor)
+  else {
+    return;
+  };
+  println!(
+    "[WORKERS] new drone {} assigned to {}",
+    worker.get_id(),
+    target_id
+  );
+  game_state
+    .worker_assignments
+    .insert(worker.get_id(), WorkerAssignment::gathering(target_id));
+}
+
+/// Moves mineral drones from bases over their target to bases under it, one at a time from the
+/// most crowded patch, until no base is over while another is under. Returns how many moved.
+fn transfer_workers(
+  bases: &[BaseResources],
+  assignments: &mut HashMap<usize, WorkerAssignment>,
+) -> usize {
+  let mut worker_count = count_workers_per_resource(assignments);
+  let mut moved = 0;
+
+  loop {
+    let surplus = |base: &&BaseResources| {
+      base.mineral_workers(&worker_count) as i32 - base.mineral_target() as i32
+    };
+    let Some(from) = bases
+      .iter()
+      .filter(|base| surplus(base) > 0)
+      .max_by_key(|base| surplus(base))
+    else {
+      break;
+    };
+    let Some(to) = bases
+      .iter()
+      .filter(|base| surplus(base) < 0)
+      .min_by_key(|base| surplus(base))
+    else {
+      break;
+    };
+
+    let Some(crowded) = from
+      .minerals
+      .iter()
+      .max_by_key(|m| worker_count.get(&m.get_id()).copied().unwrap_or(0))
+    else {
+      break;
+    };
+    let crowded_id = crowded.get_id();
+    let Some(worker_id) = assignments.iter().find_map(|(worker_id, assignment)| {
+      (assignment.assignment_type == WorkerAssignmentType::Gathering
+        && assignment.target_unit == Some(crowded_id))
+      .then_some(*worker_id)
+    }) else {
+      break;
+    };
+    let Some(target) = find_least_saturated_mineral(&to.minerals, &worker_count, WORKERS_PER_PATCH)
+    else {
+      break;
+    };
+
+    let target_id = target.get_id();
+    assignments.insert(worker_id, WorkerAssignment::gathering(target_id));
+    if let Some(count) = worker_count.get_mut(&crowded_id) {
+      *count -= 1;
+    }
+    *worker_count.entry(target_id).or_insert(0) += 1;
+    println!(
+      "[WORKERS] drone {} moves from base {} to base {}",
+      worker_id, from.base_index, to.base_index
+    );
+    moved += 1;
+  }
+
+  moved
+}
+
 pub fn enforce_assignments(game: &Game, game_state: &mut GameState) {
   let my_units = get_my_workers(game);
   let workers: Vec<_> = my_units.iter().collect();
 
   let build_order = game_state.build_order.clone();
-  let base_locations = game_state.base_locations.clone();
+  let affordable_build_indices: HashSet<usize> =
+    build_order_management::build_order_window_indices(game_state)
+      .filter(|&idx| resource_reservations::can_spend(game, game_state, idx))
+      .collect();
 
   let mut workers_to_clear: Vec<usize> = Vec::new();
 
   for worker in workers {
     let worker_id = worker.get_id();
+    // Fighting or running from an attack, worker_defense gives it back when the base is clear
+    if game_state.worker_defense.is_pulled(worker_id) {
+      continue;
+    }
     if let Some(assignment) = game_state.worker_assignments.get_mut(&worker_id) {
       match assignment.assignment_type {
         WorkerAssignmentType::Gathering => {
@@ -197,8 +425,24 @@ pub fn enforce_assignments(game: &Game, game_state: &mut GameState) {
           }
         }
         WorkerAssignmentType::Building => {
-          enforce_building_assignment(game, worker, assignment, &build_order, &base_locations);
+          let can_afford = assignment
+            .build_order_index
+            .is_some_and(|idx| affordable_build_indices.contains(&idx));
+          enforce_building_assignment(
+            game,
+            worker,
+            assignment,
+            &build_order,
+            &PlacementContext {
+              base_locations: &game_state.base_locations,
+              creep_map: &game_state.creep_map,
+              reservations: &game_state.tile_reservations,
+            },
+            can_afford,
+          );
         }
+        // Moved by scouting until the enemy main is found
+        WorkerAssignmentType::Scouting => {}
       }
     }
   }
@@ -206,17 +450,10 @@ pub fn enforce_assignments(game: &Game, game_state: &mut GameState) {
   // Get minerals and extractors for reassignment
   let static_minerals = game.get_static_minerals();
   let minerals: Vec<_> = static_minerals.iter().collect();
-  let extractors: Vec<_> = game
-    .get_all_units()
-    .into_iter()
-    .filter(|u| {
-      u.get_type() == UnitType::Zerg_Extractor
-        && u.get_player().get_id() == game.self_().map(|p| p.get_id()).unwrap_or(0)
-        && u.is_completed()
-    })
-    .collect();
+  let extractors = completed_extractors(game);
 
   // Try to reassign workers that need it
+  let extractor_saturation = game_state.gas_plan.workers_per_extractor;
   for worker_id in &workers_to_clear {
     if !assign_worker_to_other_resource(
       game,
@@ -224,6 +461,7 @@ pub fn enforce_assignments(game: &Game, game_state: &mut GameState) {
       &mut game_state.worker_assignments,
       &minerals,
       &extractors,
+      extractor_saturation,
     ) {
       // If couldn't reassign, clear the assignment
       game_state.worker_assignments.remove(worker_id);
@@ -389,19 +627,19 @@ fn enforce_gathering_assignment(
 fn get_hatchery_build_position(
   game: &Game,
   worker: &Unit,
-  base_locations: &[TilePosition],
   base_index: &Option<usize>,
+  placement: &PlacementContext,
   worker_id: usize,
 ) -> Option<TilePosition> {
   if let Some(idx) = base_index {
-    if let Some(base_tile) = base_locations.get(*idx) {
+    if let Some(base_tile) = placement.base_locations.get(*idx) {
       Some(TilePosition::new(base_tile.x, base_tile.y))
     } else {
       println!(
         "Worker {} base index {} out of bounds for hatchery (available bases: {})",
         worker_id,
         idx,
-        base_locations.len()
+        placement.base_locations.len()
       );
       None
     }
@@ -410,8 +648,8 @@ fn get_hatchery_build_position(
       game,
       worker,
       UnitType::Zerg_Hatchery,
-      base_locations,
       *base_index,
+      placement,
     )
   }
 }
@@ -422,21 +660,21 @@ fn get_or_find_build_position(
   assignment: &mut WorkerAssignment,
   building_type: UnitType,
   base_index: &Option<usize>,
-  base_locations: &[TilePosition],
+  placement: &PlacementContext,
   worker_id: usize,
 ) -> Option<TilePosition> {
   match assignment.target_position {
     Some((x, y)) => Some(TilePosition::new(x, y)),
     None => {
       let tile_pos = if building_type == UnitType::Zerg_Hatchery {
-        get_hatchery_build_position(game, worker, base_locations, base_index, worker_id)
+        get_hatchery_build_position(game, worker, base_index, placement, worker_id)
       } else {
         build_location_utils::get_buildable_location(
           game,
           worker,
           building_type,
-          base_locations,
           *base_index,
+          placement,
         )
       };
 
@@ -463,7 +701,8 @@ fn enforce_building_assignment(
   worker: &Unit,
   assignment: &mut WorkerAssignment,
   build_order: &[BuildOrderItem],
-  base_locations: &[TilePosition],
+  placement: &PlacementContext,
+  can_afford: bool,
 ) {
   let worker_order = worker.get_order();
   let worker_id = worker.get_id();
@@ -489,6 +728,7 @@ fn enforce_building_assignment(
   let BuildOrderItem::Unit {
     unit_type: building_type,
     base_index,
+    ..
   } = building_item
   else {
     println!(
@@ -522,7 +762,8 @@ fn enforce_building_assignment(
   let required_minerals = building_type.mineral_price();
   let required_gas = building_type.gas_price();
 
-  if required_minerals > current_minerals || required_gas > current_gas {
+  // `can_afford` also leaves room for what other build order items have reserved
+  if required_minerals > current_minerals || required_gas > current_gas || !can_afford {
     game.draw_text_screen(
       (0, 10),
       &format!(
@@ -539,7 +780,7 @@ fn enforce_building_assignment(
     assignment,
     *building_type,
     base_index,
-    base_locations,
+    placement,
     worker_id,
   ) else {
     return;
@@ -634,8 +875,8 @@ fn enforce_building_assignment(
           game,
           worker,
           *building_type,
-          base_locations,
           *base_index,
+          placement,
         )
         .map(|pos| (pos.x, pos.y));
       }
@@ -697,6 +938,7 @@ fn assign_worker_to_other_resource(
   assignments: &mut HashMap<usize, WorkerAssignment>,
   minerals: &[&Unit],
   extractors: &[Unit],
+  extractor_saturation: usize,
 ) -> bool {
   let mineral_worker_count = count_workers_per_resource(assignments);
   
@@ -714,11 +956,11 @@ fn assign_worker_to_other_resource(
     })
     .collect();
   
-  // Try to find an extractor with less than 2 workers
+  // Try to find an extractor the gas plan wants more drones on
   let undersaturated_extractor = extractors.iter().find(|extractor| {
     let extractor_id = extractor.get_id();
     let worker_count = mineral_worker_count.get(&extractor_id).copied().unwrap_or(0);
-    worker_count < 2
+    worker_count < extractor_saturation
   });
   
   if let Some(extractor) = undersaturated_extractor {
@@ -736,13 +978,9 @@ fn assign_worker_to_other_resource(
     .iter()
     .filter(|mineral| {
       let mineral_pos = mineral.get_position();
-      bases.iter().any(|base| {
-        let base_pos = base.get_position();
-        let dx = (mineral_pos.x - base_pos.x) as f32;
-        let dy = (mineral_pos.y - base_pos.y) as f32;
-        let distance = (dx * dx + dy * dy).sqrt();
-        distance <= 12.0 * 32.0
-      })
+      bases
+        .iter()
+        .any(|base| pixel_distance(mineral_pos, base.get_position()) <= BASE_RESOURCE_RADIUS)
     })
     .copied()
     .collect();
@@ -788,6 +1026,12 @@ pub fn draw_worker_resource_lines(
           game.draw_line_map(worker_pos, target_pos, Color::Yellow);
         }
       }
+      WorkerAssignmentType::Scouting => {
+        if let Some((target_x, target_y)) = assignment.target_position {
+          let target_pos = base_center(&TilePosition::new(target_x, target_y));
+          game.draw_line_map(worker.get_position(), target_pos, Color::Purple);
+        }
+      }
     }
   }
 }
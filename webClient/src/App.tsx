@@ -6,6 +6,10 @@ import { UnitOrders } from "./unitOrders/UnitOrders";
 import { BuildOrder } from "./buildOrder/BuildOrder";
 import { MapVisualization } from "./mapVisualization/MapVisualization";
 import { DebugFlags } from "./debugFlags/DebugFlags";
+import { MacroPlan } from "./macroPlan/MacroPlan";
+import { UpgradeQueue } from "./upgradeQueue/UpgradeQueue";
+import { Bases } from "./bases/Bases";
+import { Income } from "./income/Income";
 
 const App = () => {
   return (
@@ -16,9 +20,13 @@ const App = () => {
         <div className="flex-1 flex overflow-hidden">
           {/* Main content area */}
           <div className="flex-1 overflow-y-auto p-5">
+            <Bases />
+            <Income />
             <WorkerAssignments />
             <MilitaryAssignments />
             <LarvaeAssignments />
+            <MacroPlan />
+            <UpgradeQueue />
             <UnitOrders />
             <DebugFlags />
             <MapVisualization />
@@ -0,0 +1,139 @@
+import React from "react";
+import { useBases } from "./basesHooks";
+import { BaseOwner, BaseState } from "./basesService";
+import { ExpandableSection } from "../components/ExpandableSection";
+import { LoadingState } from "../components/LoadingState";
+import { EmptyState } from "../components/EmptyState";
+import { DataCard } from "../components/DataCard";
+import { StatCard } from "../components/StatCard";
+
+const stateColors: Record<BaseState, string> = {
+  Free: "text-slate-400",
+  Planned: "text-plasma-400",
+  Building: "text-amber-400",
+  Active: "text-green-500",
+  MinedOut: "text-slate-500",
+  Lost: "text-red-500",
+};
+
+const ownerColors: Record<BaseOwner, string> = {
+  Own: "text-green-500",
+  Enemy: "text-red-500",
+  Neutral: "text-slate-400",
+  Unknown: "text-slate-500",
+};
+
+export const Bases: React.FC = () => {
+  const { data: snapshot, isLoading, error } = useBases();
+
+  const renderContent = () => {
+    if (isLoading) {
+      return <LoadingState message="Waiting for bases..." />;
+    }
+
+    if (error) {
+      return <EmptyState message={`Error: ${error.message}`} />;
+    }
+
+    if (!snapshot || snapshot.frame_count < 0 || snapshot.bases.length === 0) {
+      return <EmptyState message="No bases" />;
+    }
+
+    const count = (state: BaseState) =>
+      snapshot.bases.filter((base) => base.state === state).length;
+
+    return (
+      <div className="space-y-4">
+        <div className="grid grid-cols-2 md:grid-cols-4 gap-4">
+          <StatCard label="Active" value={count("Active")} />
+          <StatCard label="Building" value={count("Building")} />
+          <StatCard label="Mined out" value={count("MinedOut")} />
+          <StatCard
+            label="Enemy"
+            value={snapshot.bases.filter((base) => base.owner === "Enemy").length}
+          />
+        </div>
+
+        <DataCard>
+          <div className="flex justify-between text-sm">
+            <span className="font-medium">Enemy main</span>
+            {snapshot.enemy_main !== null ? (
+              <span className="text-red-500">
+                ({snapshot.enemy_main[0]}, {snapshot.enemy_main[1]}),{" "}
+                {snapshot.enemy_main_source === "Seen" ? "seen" : "by elimination"}
+                {snapshot.enemy_main_found_frame !== null
+                  ? ` at frame ${snapshot.enemy_main_found_frame}`
+                  : ""}
+              </span>
+            ) : (
+              <span className="text-slate-400">
+                {snapshot.unexplored_start_locations.length} start locations unexplored
+              </span>
+            )}
+          </div>
+          {snapshot.scouts.map((scout) => (
+            <div key={scout.unit_id} className="text-xs text-slate-400">
+              {scout.kind} {scout.unit_id}
+              {scout.target !== null
+                ? ` heading to (${scout.target[0]}, ${scout.target[1]})`
+                : " idle"}
+            </div>
+          ))}
+        </DataCard>
+
+        {snapshot.bases.map((base) => (
+          <DataCard key={base.index}>
+            <div className="flex justify-between text-sm">
+              <span className="font-medium">
+                Base {base.index}
+                {base.is_start_location ? " (start)" : ""}
+              </span>
+              <span>
+                <span className={stateColors[base.state]}>{base.state}</span>
+                {", "}
+                <span className={ownerColors[base.owner]}>{base.owner}</span>
+              </span>
+            </div>
+            <div className="flex justify-between text-xs text-slate-400">
+              <span>
+                ({base.tile_x}, {base.tile_y}), {base.mineral_patches} patches,{" "}
+                {base.geysers} geysers
+              </span>
+              <span className="text-amber-400">
+                {base.minerals_left}m {base.gas_left}g
+              </span>
+            </div>
+            <div className="text-xs text-slate-500">
+              {base.last_seen_frame !== null
+                ? `seen frame ${base.last_seen_frame}`
+                : "never seen"}
+              {base.depot_id !== null ? `, depot ${base.depot_id}` : ""}
+            </div>
+            {base.rally_role !== null && base.rally_position !== null && (
+              <div className="text-xs text-slate-500">
+                rallies {base.rally_role.toLowerCase()} to ({base.rally_position[0]},{" "}
+                {base.rally_position[1]})
+                {base.rally_set_frame !== null ? ` since frame ${base.rally_set_frame}` : ""}
+              </div>
+            )}
+            {(base.sunken_sites.length > 0 || base.spore_sites.length > 0) && (
+              <div className="text-xs text-slate-500">
+                {base.sunken_sites.length} sunken sites
+                {base.defense_entrance !== null
+                  ? ` facing (${base.defense_entrance[0]}, ${base.defense_entrance[1]})`
+                  : ""}
+                , {base.spore_sites.length} spore sites
+              </div>
+            )}
+          </DataCard>
+        ))}
+      </div>
+    );
+  };
+
+  return (
+    <ExpandableSection title="Bases" defaultExpanded={false}>
+      {renderContent()}
+    </ExpandableSection>
+  );
+};
@@ -0,0 +1,18 @@
+import { useQuery } from '@tanstack/react-query';
+import * as api from './basesService';
+import { usePollInterval } from '../contexts/PollIntervalContext';
+
+// Query keys
+export const queryKeys = {
+  bases: ['bases'] as const,
+};
+
+export function useBases() {
+  const { pollInterval } = usePollInterval();
+
+  return useQuery({
+    queryKey: queryKeys.bases,
+    queryFn: api.fetchBases,
+    refetchInterval: pollInterval,
+  });
+}
@@ -0,0 +1,62 @@
+const BASE_URL = `http://localhost:3333`;
+
+export type BaseOwner = "Own" | "Enemy" | "Neutral" | "Unknown";
+
+export type BaseState =
+  | "Free"
+  | "Planned"
+  | "Building"
+  | "Active"
+  | "MinedOut"
+  | "Lost";
+
+export type RallyRole = "Workers" | "Army";
+
+export interface Base {
+  index: number;
+  tile_x: number;
+  tile_y: number;
+  is_start_location: boolean;
+  depot_id: number | null;
+  owner: BaseOwner;
+  state: BaseState;
+  mineral_patches: number;
+  geysers: number;
+  minerals_left: number;
+  gas_left: number;
+  last_seen_frame: number | null;
+  rally_role: RallyRole | null;
+  rally_position: [number, number] | null;
+  rally_set_frame: number | null;
+  defense_entrance: [number, number] | null;
+  sunken_sites: Array<[number, number]>;
+  spore_sites: Array<[number, number]>;
+}
+
+export type EnemyMainSource = "Seen" | "Eliminated";
+
+export type ScoutKind = "Overlord" | "Drone";
+
+export interface Scout {
+  unit_id: number;
+  kind: ScoutKind;
+  target: [number, number] | null;
+}
+
+export interface BasesSnapshot {
+  bases: Base[];
+  enemy_main: [number, number] | null;
+  enemy_main_source: EnemyMainSource | null;
+  enemy_main_found_frame: number | null;
+  unexplored_start_locations: [number, number][];
+  scouts: Scout[];
+  frame_count: number;
+}
+
+export async function fetchBases(): Promise<BasesSnapshot> {
+  const response = await fetch(`${BASE_URL}/bases`);
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status}`);
+  }
+  return response.json();
+}
@@ -1,8 +1,13 @@
-import React, { useRef, useEffect } from 'react';
-import { useBuildOrder } from "./buildOrderHooks";
+import React, { useRef, useEffect, useState } from 'react';
+import {
+  useAvailableBuildOrders,
+  useBuildOrder,
+  useBuildOrderTimings,
+  useEditBuildOrder,
+} from "./buildOrderHooks";
 import { LoadingState } from "../components/LoadingState";
 import { EmptyState } from "../components/EmptyState";
-import type { BuildOrderSnapshot, BuildOrderItem } from "./buildOrderService";
+import type { BuildOrderSnapshot, BuildOrderItem, BuildOrderItemStatus } from "./buildOrderService";
 
 function getItemDisplayName(item: BuildOrderItem): string {
   if (item.type === 'Unit') {
@@ -10,6 +15,8 @@ function getItemDisplayName(item: BuildOrderItem): string {
     return item.base_index !== null ? `${name} @base${item.base_index}` : name;
   } else if (item.type === 'Upgrade') {
     return item.upgrade_type.replace(/^(Terran|Protoss|Zerg)_/, "");
+  } else if (item.type === 'Research') {
+    return item.tech_type;
   } else if (item.type === 'Squad') {
     return `${item.name} (${item.role})`;
   }
@@ -20,13 +27,92 @@ function getItemType(item: BuildOrderItem): string {
   return item.type;
 }
 
+// Matches first_editable_index on the bot, items before it have started or sit behind one that has
+function getFirstEditableIndex(statuses: BuildOrderItemStatus[], buildOrderIndex: number): number {
+  let lastStarted = -1;
+  statuses.forEach((status, index) => {
+    if (status !== 'Pending' && status !== 'Assigned') {
+      lastStarted = index;
+    }
+  });
+  return Math.max(lastStarted + 1, buildOrderIndex);
+}
+
+const BuildOrderEditor: React.FC<{ data: BuildOrderSnapshot }> = ({ data }) => {
+  const editBuildOrder = useEditBuildOrder();
+  const { data: availableBuildOrders } = useAvailableBuildOrders();
+  const [newItem, setNewItem] = useState("");
+  const [buildOrderToLoad, setBuildOrderToLoad] = useState("");
+
+  const addItem = () => {
+    if (!newItem.trim()) return;
+    editBuildOrder.mutate(
+      { op: 'insert', index: data.build_order.length, item: newItem.trim() },
+      { onSuccess: () => setNewItem("") }
+    );
+  };
+
+  return (
+    <div className="flex flex-col gap-2 p-2 border-b border-plasma-800">
+      <div className="flex items-center gap-2">
+        <input
+          className="flex-1 bg-void-950 border border-plasma-800 rounded px-2 py-1 text-sm text-lavender-400"
+          placeholder="Zerg_Hatchery @base1 if supply >= 12"
+          value={newItem}
+          onChange={(e) => setNewItem(e.target.value)}
+          onKeyDown={(e) => e.key === 'Enter' && addItem()}
+        />
+        <button
+          className="px-2 py-1 text-sm rounded border border-plasma-800 text-lavender-400 hover:bg-plasma-500/20"
+          onClick={addItem}
+        >
+          Add
+        </button>
+        <button
+          className="px-2 py-1 text-sm rounded border border-plasma-800 text-lavender-400 hover:bg-plasma-500/20"
+          onClick={() => editBuildOrder.mutate({ op: 'skip' })}
+        >
+          Skip current
+        </button>
+      </div>
+      <div className="flex items-center gap-2">
+        <select
+          className="flex-1 bg-void-950 border border-plasma-800 rounded px-2 py-1 text-sm text-lavender-400"
+          value={buildOrderToLoad}
+          onChange={(e) => setBuildOrderToLoad(e.target.value)}
+        >
+          <option value="">Load build order...</option>
+          {availableBuildOrders?.map((name) => (
+            <option key={name} value={name}>{name}</option>
+          ))}
+        </select>
+        <button
+          className="px-2 py-1 text-sm rounded border border-plasma-800 text-lavender-400 hover:bg-plasma-500/20 disabled:opacity-40"
+          disabled={!buildOrderToLoad}
+          onClick={() => editBuildOrder.mutate({ op: 'load', name: buildOrderToLoad })}
+        >
+          Load
+        </button>
+      </div>
+      {editBuildOrder.error && (
+        <span className="text-xs text-red-500">{editBuildOrder.error.message}</span>
+      )}
+    </div>
+  );
+};
+
 export const BuildOrder: React.FC = () => {
   const { data, isLoading, error } = useBuildOrder();
+  const { data: timings } = useBuildOrderTimings();
+  const editBuildOrder = useEditBuildOrder();
   const currentItemRef = useRef<HTMLDivElement>(null);
 
   // Extract build_order_index for useEffect dependency
   const buildOrderData = data as BuildOrderSnapshot;
   const build_order_index = buildOrderData?.build_order_index;
+  const firstEditable = buildOrderData
+    ? getFirstEditableIndex(buildOrderData.build_order_status ?? [], buildOrderData.build_order_index)
+    : 0;
 
   // Scroll to current item when it changes
   useEffect(() => {
@@ -57,12 +143,20 @@ export const BuildOrder: React.FC = () => {
         <EmptyState message="No build order set" />
       )}
       
+      {!isLoading && !error && buildOrderData && <BuildOrderEditor data={buildOrderData} />}
+
       {!isLoading && !error && buildOrderData?.build_order && buildOrderData.build_order.length > 0 && (
         <div className="flex flex-col gap-2 overflow-y-auto p-2">
           {buildOrderData.build_order.map((item, index) => {
+            const isEditable = index >= firstEditable;
+            const status = buildOrderData.build_order_status?.[index] ?? 'Pending';
             const isComplete = index < buildOrderData.build_order_index;
             const isCurrent = index === buildOrderData.build_order_index;
+            const isInWindow =
+              index >= buildOrderData.build_order_index &&
+              index < buildOrderData.build_order_index + buildOrderData.build_order_window;
 
+            const startedDelta = timings?.items[index]?.started_delta ?? null;
             const displayName = getItemDisplayName(item);
             const itemType = getItemType(item);
 
@@ -98,10 +192,51 @@ export const BuildOrder: React.FC = () => {
                   {itemType === 'Upgrade' && (
                     <span className="text-xs text-slate-500">Upgrade</span>
                   )}
+                  {itemType === 'Research' && (
+                    <span className="text-xs text-slate-500">Research</span>
+                  )}
+                  {item.trigger && (
+                    <span className="text-xs text-slate-500">when {item.trigger}</span>
+                  )}
+                  {startedDelta !== null && (
+                    <span className={`text-xs ${startedDelta > 0 ? "text-red-500" : "text-green-500"}`}>
+                      {startedDelta > 0 ? `${startedDelta} frames behind` : `${-startedDelta} frames ahead`}
+                    </span>
+                  )}
                 </div>
-                {isComplete && (
-                  <span className="ml-auto text-green-500 text-sm">✓</span>
+                {isEditable && (
+                  <div className="flex gap-1 text-xs text-slate-500">
+                    <button
+                      className="hover:text-lavender-400 disabled:opacity-30"
+                      disabled={index <= firstEditable}
+                      onClick={() => editBuildOrder.mutate({ op: 'move', from: index, to: index - 1 })}
+                    >
+                      ▲
+                    </button>
+                    <button
+                      className="hover:text-lavender-400 disabled:opacity-30"
+                      disabled={index >= buildOrderData.build_order.length - 1}
+                      onClick={() => editBuildOrder.mutate({ op: 'move', from: index, to: index + 1 })}
+                    >
+                      ▼
+                    </button>
+                    <button
+                      className="hover:text-red-500"
+                      onClick={() => editBuildOrder.mutate({ op: 'remove', index })}
+                    >
+                      ✕
+                    </button>
+                  </div>
                 )}
+                {status === 'Failed' ? (
+                  <span className="ml-auto text-red-500 text-sm">failed</span>
+                ) : status === 'Skipped' ? (
+                  <span className="ml-auto text-slate-500 text-sm">skipped</span>
+                ) : status === 'Completed' ? (
+                  <span className="ml-auto text-green-500 text-sm">✓</span>
+                ) : (isComplete || isInWindow) && status !== 'Pending' ? (
+                  <span className="ml-auto text-slate-500 text-xs">{status.toLowerCase()}</span>
+                ) : null}
               </div>
             );
           })}
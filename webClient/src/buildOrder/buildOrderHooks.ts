@@ -1,10 +1,13 @@
-import { useQuery } from '@tanstack/react-query';
+import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import * as api from './buildOrderService';
+import type { BuildOrderEdit } from './buildOrderService';
 import { usePollInterval } from '../contexts/PollIntervalContext';
 
 // Query keys
 export const queryKeys = {
   buildOrder: ['buildOrder'] as const,
+  buildOrderTimings: ['buildOrderTimings'] as const,
+  availableBuildOrders: ['availableBuildOrders'] as const,
 };
 
 export function useBuildOrder() {
@@ -16,3 +19,32 @@ export function useBuildOrder() {
     refetchInterval: pollInterval,
   });
 }
+
+export function useBuildOrderTimings() {
+  const { pollInterval } = usePollInterval();
+  
+  return useQuery({
+    queryKey: queryKeys.buildOrderTimings,
+    queryFn: api.fetchBuildOrderTimings,
+    refetchInterval: pollInterval,
+  });
+}
+
+export function useAvailableBuildOrders() {
+  return useQuery({
+    queryKey: queryKeys.availableBuildOrders,
+    queryFn: api.fetchAvailableBuildOrders,
+  });
+}
+
+export function useEditBuildOrder() {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationFn: (edit: BuildOrderEdit) => api.editBuildOrder(edit),
+    onSuccess: (snapshot) => {
+      queryClient.setQueryData(queryKeys.buildOrder, snapshot);
+      queryClient.invalidateQueries({ queryKey: queryKeys.buildOrderTimings });
+    },
+  });
+}
@@ -1,13 +1,25 @@
 const BASE_URL = `http://localhost:3333`;
 
+export interface BuildOrderBenchmark {
+  assigned: number | null;
+  started: number | null;
+  completed: number | null;
+}
+
 export type BuildOrderItem =
-  | { type: 'Unit'; unit_type: string; base_index: number | null }
-  | { type: 'Upgrade'; upgrade_type: string }
-  | { type: 'Squad'; name: string; role: string; status: string };
+  | { type: 'Unit'; unit_type: string; base_index: number | null; trigger: string | null; benchmark: BuildOrderBenchmark | null }
+  | { type: 'Upgrade'; upgrade_type: string; trigger: string | null; benchmark: BuildOrderBenchmark | null }
+  | { type: 'Research'; tech_type: string; trigger: string | null; benchmark: BuildOrderBenchmark | null }
+  | { type: 'Squad'; name: string; role: string; status: string; trigger: string | null; benchmark: BuildOrderBenchmark | null };
+
+export type BuildOrderItemStatus = 'Pending' | 'Assigned' | 'Started' | 'Completed' | 'Failed' | 'Skipped';
 
 export interface BuildOrderSnapshot {
+  build_order_name: string;
   build_order: BuildOrderItem[];
+  build_order_status: BuildOrderItemStatus[];
   build_order_index: number;
+  build_order_window: number;
   frame_count: number;
 }
 
@@ -18,3 +30,67 @@ export async function fetchBuildOrder(): Promise<BuildOrderSnapshot> {
   }
   return response.json();
 }
+
+// Items are sent in the text form, e.g. `Zerg_Hatchery @base1 if supply >= 12`
+export type BuildOrderEdit =
+  | { op: 'insert'; index: number; item: string }
+  | { op: 'replace'; index: number; item: string }
+  | { op: 'remove'; index: number }
+  | { op: 'move'; from: number; to: number }
+  | { op: 'skip' }
+  | { op: 'load'; name: string };
+
+export async function editBuildOrder(edit: BuildOrderEdit): Promise<BuildOrderSnapshot> {
+  const { op, ...body } = edit;
+  const response = await fetch(`${BASE_URL}/build-order/${op}`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+    },
+    body: JSON.stringify(body),
+  });
+  if (!response.ok) {
+    throw new Error(await response.text() || `HTTP ${response.status}`);
+  }
+  return response.json();
+}
+
+export async function fetchAvailableBuildOrders(): Promise<string[]> {
+  const response = await fetch(`${BASE_URL}/build-orders`);
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status}`);
+  }
+  return response.json();
+}
+
+export interface BuildOrderItemReport {
+  index: number;
+  item: string;
+  status: BuildOrderItemStatus;
+  assigned_frame: number | null;
+  started_frame: number | null;
+  completed_frame: number | null;
+  failed_frame: number | null;
+  benchmark: BuildOrderBenchmark | null;
+  assigned_delta: number | null;
+  started_delta: number | null;
+  completed_delta: number | null;
+}
+
+export interface BuildOrderTimingReport {
+  build_order_name: string;
+  map_file_name: string;
+  enemy_race: string;
+  frame_count: number;
+  is_winner: boolean | null;
+  worst_started_delta: number | null;
+  items: BuildOrderItemReport[];
+}
+
+export async function fetchBuildOrderTimings(): Promise<BuildOrderTimingReport> {
+  const response = await fetch(`${BASE_URL}/build-order-timings`);
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status}`);
+  }
+  return response.json();
+}
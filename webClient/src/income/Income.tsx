@@ -0,0 +1,131 @@
+import React from "react";
+import { useIncome } from "./incomeHooks";
+import { ExpandableSection } from "../components/ExpandableSection";
+import { LoadingState } from "../components/LoadingState";
+import { EmptyState } from "../components/EmptyState";
+import { DataCard } from "../components/DataCard";
+import { StatCard } from "../components/StatCard";
+
+const RECENT_SAMPLES = 12;
+
+const formatTime = (frame: number) => {
+  const seconds = Math.floor(frame / 24);
+  return `${Math.floor(seconds / 60)}:${String(seconds % 60).padStart(2, "0")}`;
+};
+
+export const Income: React.FC = () => {
+  const { data: snapshot, isLoading, error } = useIncome();
+
+  const renderContent = () => {
+    if (isLoading) {
+      return <LoadingState message="Waiting for income..." />;
+    }
+
+    if (error) {
+      return <EmptyState message={`Error: ${error.message}`} />;
+    }
+
+    if (!snapshot || snapshot.frame_count < 0 || snapshot.samples.length === 0) {
+      return <EmptyState message="No income samples yet" />;
+    }
+
+    const latest = snapshot.samples[snapshot.samples.length - 1];
+    const recent = snapshot.samples.slice(-RECENT_SAMPLES).reverse();
+    const longDistance = snapshot.patches.filter(
+      (patch) => patch.long_distance && patch.workers > 0
+    );
+
+    return (
+      <div className="space-y-4">
+        <div className="grid grid-cols-2 md:grid-cols-4 gap-4">
+          <StatCard label="Minerals/min" value={Math.round(latest.minerals_per_minute)} />
+          <StatCard label="Gas/min" value={Math.round(latest.gas_per_minute)} />
+          <StatCard label="Idle drone seconds" value={Math.round(latest.idle_worker_seconds)} />
+          <StatCard
+            label="Long distance loss/min"
+            value={Math.round(latest.long_distance_loss_per_minute)}
+          />
+        </div>
+
+        <DataCard>
+          <div className="text-sm font-medium mb-1">Samples</div>
+          {recent.map((sample) => (
+            <div key={sample.frame} className="flex justify-between text-xs">
+              <span className="text-slate-400">
+                {formatTime(sample.frame)} ({sample.workers} drones)
+              </span>
+              <span>
+                <span className="text-amber-400">
+                  {Math.round(sample.minerals_per_minute)}m{" "}
+                  {Math.round(sample.gas_per_minute)}g
+                </span>
+                <span className="text-slate-500">
+                  {" "}
+                  {Math.round(sample.idle_worker_seconds)}s idle,{" "}
+                  {sample.long_distance_workers} long distance
+                </span>
+              </span>
+            </div>
+          ))}
+        </DataCard>
+
+        <DataCard>
+          <div className="text-sm font-medium mb-1">Bases (latest sample)</div>
+          {latest.bases.length === 0 ? (
+            <div className="text-xs text-slate-500">No deliveries</div>
+          ) : (
+            latest.bases.map((base) => (
+              <div key={base.base_index} className="flex justify-between text-xs">
+                <span>Base {base.base_index}</span>
+                <span className="text-amber-400">
+                  {Math.round(base.minerals_per_minute)}m{" "}
+                  {Math.round(base.gas_per_minute)}g ({base.trips} trips)
+                </span>
+              </div>
+            ))
+          )}
+        </DataCard>
+
+        <DataCard>
+          <div className="text-sm font-medium mb-1">
+            Patches (close trip {Math.round(snapshot.near_trip_frames)} frames)
+          </div>
+          {snapshot.patches.map((patch) => (
+            <div
+              key={patch.resource_id}
+              className={`flex justify-between text-xs ${
+                patch.long_distance ? "text-red-500" : ""
+              }`}
+            >
+              <span>
+                {patch.is_gas ? "Extractor" : "Mineral"} {patch.resource_id}
+                {patch.base_index !== null ? `, base ${patch.base_index}` : ""}
+              </span>
+              <span className="text-slate-400">
+                {patch.workers} drones,{" "}
+                {patch.depot_distance !== null
+                  ? `${Math.round(patch.depot_distance)}px`
+                  : "no hatchery"}
+                ,{" "}
+                {patch.average_trip_frames !== null
+                  ? `${Math.round(patch.average_trip_frames)} frames/trip`
+                  : "no trips timed"}
+              </span>
+            </div>
+          ))}
+          {longDistance.length > 0 && (
+            <div className="text-xs text-red-500 mt-1">
+              {longDistance.length} patches long distance mined
+            </div>
+          )}
+        </DataCard>
+      </div>
+    );
+  };
+
+  return (
+    <ExpandableSection title="Income" defaultExpanded={false}>
+      {renderContent()}
+    </ExpandableSection>
+  );
+};
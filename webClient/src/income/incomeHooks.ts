@@ -0,0 +1,18 @@
+import { useQuery } from '@tanstack/react-query';
+import * as api from './incomeService';
+import { usePollInterval } from '../contexts/PollIntervalContext';
+
+// Query keys
+export const queryKeys = {
+  income: ['income'] as const,
+};
+
+export function useIncome() {
+  const { pollInterval } = usePollInterval();
+
+  return useQuery({
+    queryKey: queryKeys.income,
+    queryFn: api.fetchIncome,
+    refetchInterval: pollInterval,
+  });
+}
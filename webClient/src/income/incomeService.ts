@@ -0,0 +1,45 @@
+const BASE_URL = `http://localhost:3333`;
+
+export interface BaseIncome {
+  base_index: number;
+  minerals_per_minute: number;
+  gas_per_minute: number;
+  trips: number;
+}
+
+export interface IncomeSample {
+  frame: number;
+  minerals_per_minute: number;
+  gas_per_minute: number;
+  bases: BaseIncome[];
+  workers: number;
+  idle_worker_seconds: number;
+  long_distance_workers: number;
+  long_distance_loss_per_minute: number;
+}
+
+export interface Patch {
+  resource_id: number;
+  base_index: number | null;
+  is_gas: boolean;
+  depot_distance: number | null;
+  long_distance: boolean;
+  trips: number;
+  average_trip_frames: number | null;
+  workers: number;
+}
+
+export interface IncomeSnapshot {
+  samples: IncomeSample[];
+  patches: Patch[];
+  near_trip_frames: number;
+  frame_count: number;
+}
+
+export async function fetchIncome(): Promise<IncomeSnapshot> {
+  const response = await fetch(`${BASE_URL}/income`);
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status}`);
+  }
+  return response.json();
+}
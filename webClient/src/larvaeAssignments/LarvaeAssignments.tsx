@@ -54,6 +54,11 @@ export const LarvaeAssignments: React.FC = () => {
                     </span>
                   </div>
                 )}
+                {responsibilities.selection_reasons[larvaId] && (
+                  <div className="text-xs text-slate-400">
+                    {responsibilities.selection_reasons[larvaId]}
+                  </div>
+                )}
               </div>
             </DataCard>
           )
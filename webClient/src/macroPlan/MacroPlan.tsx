@@ -0,0 +1,159 @@
+import React from "react";
+import { useMacroPlan } from "./macroPlanHooks";
+import type { MacroGoal } from "./macroPlanService";
+import { ExpandableSection } from "../components/ExpandableSection";
+import { LoadingState } from "../components/LoadingState";
+import { EmptyState } from "../components/EmptyState";
+import { DataCard } from "../components/DataCard";
+import { StatCard } from "../components/StatCard";
+
+const formatName = (name: string) =>
+  name.replace("Zerg_", "").replace(/_/g, " ");
+
+const GoalList: React.FC<{ title: string; goals: MacroGoal[] }> = ({
+  title,
+  goals,
+}) => (
+  <DataCard>
+    <div className="text-text-muted text-xs uppercase tracking-wider mb-2">
+      {title}
+    </div>
+    {goals.length === 0 && <div className="text-sm text-slate-400">None</div>}
+    {goals.map((goal) => (
+      <div key={goal.name} className="flex justify-between text-sm">
+        <span className={goal.done ? "text-green-500" : "text-plasma-400"}>
+          {formatName(goal.name)}
+        </span>
+        <span className="text-slate-400">{goal.min_drones} drones</span>
+      </div>
+    ))}
+  </DataCard>
+);
+
+export const MacroPlan: React.FC = () => {
+  const { data: plan, isLoading, error } = useMacroPlan();
+
+  const renderContent = () => {
+    if (isLoading) {
+      return <LoadingState message="Waiting for macro plan..." />;
+    }
+
+    if (error) {
+      return <EmptyState message={`Error: ${error.message}`} />;
+    }
+
+    if (!plan || plan.frame_count < 0) {
+      return <EmptyState message="No macro plan" />;
+    }
+
+    return (
+      <div className="space-y-4">
+        {!plan.active && (
+          <div className="text-sm text-slate-400">
+            Following the scripted build order, the planner takes over when it
+            runs out.
+          </div>
+        )}
+
+        <div className="grid grid-cols-2 md:grid-cols-4 gap-4">
+          <StatCard
+            label="Drones"
+            value={`${plan.drones} / ${plan.drone_target}`}
+          />
+          <StatCard
+            label="Hatcheries"
+            value={`${plan.hatcheries} / ${plan.hatcheries_needed}`}
+          />
+          <StatCard
+            label="Missing gatherers"
+            value={plan.unmet_saturation}
+          />
+          <StatCard
+            label="Drones per extractor"
+            value={plan.gas_workers_per_extractor}
+          />
+          <StatCard label="Gas demand" value={plan.gas_demand} />
+          <StatCard label="Bases" value={plan.bases} />
+          <StatCard
+            label="Larva / min"
+            value={plan.larva_per_minute.toFixed(1)}
+          />
+          <StatCard
+            label="Supply"
+            value={`${plan.supply_used} / ${plan.supply_total}`}
+          />
+          <StatCard
+            label="Supply in production"
+            value={plan.supply_in_production}
+          />
+          <StatCard label="Supply forecast" value={plan.supply_forecast} />
+          <StatCard label="Army supply" value={plan.army_supply} />
+        </div>
+
+        {plan.gas_reason && (
+          <DataCard>
+            <div className="text-text-muted text-xs uppercase tracking-wider mb-2">
+              Gas plan
+            </div>
+            <div className="text-sm text-text-secondary">{plan.gas_reason}</div>
+          </DataCard>
+        )}
+
+        <div className="grid grid-cols-1 md:grid-cols-3 gap-4">
+          <DataCard>
+            <div className="text-text-muted text-xs uppercase tracking-wider mb-2">
+              Army composition
+            </div>
+            {plan.army_composition.map((goal) => (
+              <div key={goal.unit_type} className="flex justify-between text-sm">
+                <span className="text-plasma-400">
+                  {formatName(goal.unit_type)}
+                </span>
+                <span className="text-amber-400">{goal.weight}</span>
+              </div>
+            ))}
+          </DataCard>
+          <GoalList title="Tech" goals={plan.tech} />
+          <GoalList title="Upgrades" goals={plan.upgrades} />
+        </div>
+
+        <DataCard>
+          <div className="text-text-muted text-xs uppercase tracking-wider mb-2">
+            Reasoning
+          </div>
+          {plan.reasoning.length === 0 && (
+            <div className="text-sm text-slate-400">Nothing planned yet</div>
+          )}
+          {plan.reasoning.map((line, index) => (
+            <div key={index} className="text-sm text-text-secondary">
+              {line}
+            </div>
+          ))}
+        </DataCard>
+
+        {plan.decisions.length > 0 && (
+          <DataCard>
+            <div className="text-text-muted text-xs uppercase tracking-wider mb-2">
+              Recent decisions
+            </div>
+            {plan.decisions.map((decision, index) => (
+              <div key={index} className="text-sm">
+                <span className="text-slate-400">{decision.frame} </span>
+                <span className="text-amber-400 font-medium">
+                  {formatName(decision.item)}
+                </span>
+                <span className="text-text-secondary"> {decision.reason}</span>
+              </div>
+            ))}
+          </DataCard>
+        )}
+      </div>
+    );
+  };
+
+  return (
+    <ExpandableSection title="Macro Plan" defaultExpanded={false}>
+      {renderContent()}
+    </ExpandableSection>
+  );
+};
@@ -0,0 +1,18 @@
+import { useQuery } from '@tanstack/react-query';
+import * as api from './macroPlanService';
+import { usePollInterval } from '../contexts/PollIntervalContext';
+
+// Query keys
+export const queryKeys = {
+  macroPlan: ['macroPlan'] as const,
+};
+
+export function useMacroPlan() {
+  const { pollInterval } = usePollInterval();
+  
+  return useQuery({
+    queryKey: queryKeys.macroPlan,
+    queryFn: api.fetchMacroPlan,
+    refetchInterval: pollInterval,
+  });
+}
@@ -0,0 +1,51 @@
+const BASE_URL = `http://localhost:3333`;
+
+export interface ArmyGoal {
+  unit_type: string;
+  weight: number;
+}
+
+export interface MacroGoal {
+  name: string;
+  min_drones: number;
+  done: boolean;
+}
+
+export interface MacroDecision {
+  frame: number;
+  item: string;
+  reason: string;
+}
+
+export interface MacroPlanSnapshot {
+  active: boolean;
+  army_composition: ArmyGoal[];
+  tech: MacroGoal[];
+  upgrades: MacroGoal[];
+  drones: number;
+  drone_target: number;
+  unmet_saturation: number;
+  gas_workers_per_extractor: number;
+  gas_demand: number;
+  gas_reason: string;
+  bases: number;
+  hatcheries: number;
+  hatcheries_needed: number;
+  larva_per_minute: number;
+  supply_used: number;
+  supply_total: number;
+  supply_in_production: number;
+  supply_forecast: number;
+  army_supply: number;
+  reasoning: string[];
+  decisions: MacroDecision[];
+  frame_count: number;
+}
+
+export async function fetchMacroPlan(): Promise<MacroPlanSnapshot> {
+  const response = await fetch(`${BASE_URL}/macro-plan`);
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status}`);
+  }
+  return response.json();
+}
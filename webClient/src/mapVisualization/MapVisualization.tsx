@@ -1,5 +1,6 @@
 import React from 'react';
 import { useMap } from './mapVisualizationHooks';
+import { TileReservation } from './mapVisualizationService';
 import { ExpandableSection } from '../components/ExpandableSection';
 import { LoadingState } from '../components/LoadingState';
 import { EmptyState } from '../components/EmptyState';
@@ -12,6 +13,9 @@ const COLORS = {
   ENEMY_UNITS: '#FF0000',
   MINERALS: '#00FFFF',
   GAS: '#00FF00',
+  CREEP: '#7a3f8f',
+  CREEP_FORECAST: '#c58be0',
+  RESERVED: '#ffb000',
   UNIT_STROKE: '#FFFFFF',
 };
 
@@ -41,6 +45,9 @@ const Legend: React.FC = () => {
     { color: COLORS.ENEMY_UNITS, label: 'Enemy Units' },
     { color: COLORS.MINERALS, label: 'Minerals' },
     { color: COLORS.GAS, label: 'Gas' },
+    { color: COLORS.CREEP, label: 'Creep' },
+    { color: COLORS.CREEP_FORECAST, label: 'Creep Forecast' },
+    { color: COLORS.RESERVED, label: 'Kept Clear' },
   ];
 
   return (
@@ -58,7 +65,12 @@ const Legend: React.FC = () => {
   );
 };
 
-const MapSvg: React.FC<{ mapData: MapData }> = ({ mapData }) => {
+const MapSvg: React.FC<{
+  mapData: MapData;
+  creepTiles: Array<[number, number]>;
+  creepForecast: Array<[number, number, number]>;
+  reservedTiles: Array<[number, number, TileReservation]>;
+}> = ({ mapData, creepTiles, creepForecast, reservedTiles }) => {
   if (!mapData || mapData.width === 0 || mapData.height === 0) {
     return <EmptyState message="Invalid map data" />;
   }
@@ -66,6 +78,7 @@ const MapSvg: React.FC<{ mapData: MapData }> = ({ mapData }) => {
   const scale = 3; // pixels per walk tile
   const svgWidth = mapData.width * scale;
   const svgHeight = mapData.height * scale;
+  const tileSize = scale * 4; // build tiles are 4 walk tiles across
 
   return (
     <div className="bg-black rounded overflow-auto max-h-150 border border-border-primary">
@@ -98,6 +111,49 @@ const MapSvg: React.FC<{ mapData: MapData }> = ({ mapData }) => {
           })
         )}
 
+        {/* Draw creep and where it is expected */}
+        {creepTiles?.map(([x, y]) => (
+          <rect
+            key={`creep-${x}-${y}`}
+            x={x * tileSize}
+            y={y * tileSize}
+            width={tileSize}
+            height={tileSize}
+            fill={COLORS.CREEP}
+            opacity={0.5}
+          />
+        ))}
+        {creepForecast?.map(([x, y, frame]) => (
+          <rect
+            key={`creep-forecast-${x}-${y}`}
+            x={x * tileSize}
+            y={y * tileSize}
+            width={tileSize}
+            height={tileSize}
+            fill={COLORS.CREEP_FORECAST}
+            opacity={0.35}
+          >
+            <title>{`Creep expected at frame ${frame}`}</title>
+          </rect>
+        ))}
+
+        {/* Draw tiles kept free of buildings */}
+        {reservedTiles?.map(([x, y, reservation]) => (
+          <rect
+            key={`reserved-${x}-${y}`}
+            x={x * tileSize}
+            y={y * tileSize}
+            width={tileSize}
+            height={tileSize}
+            fill="none"
+            stroke={COLORS.RESERVED}
+            strokeWidth={1}
+            opacity={0.7}
+          >
+            <title>{reservation}</title>
+          </rect>
+        ))}
+
         {/* Draw resources */}
         {mapData.resources?.map((resource, i) => {
           const color = resource.resource_type.includes('Geyser')
@@ -160,7 +216,12 @@ export const MapVisualization: React.FC = () => {
     return (
       <>
         <Legend />
-        <MapSvg mapData={mapData.map_data} />
+        <MapSvg
+          mapData={mapData.map_data}
+          creepTiles={mapData.creep_tiles}
+          creepForecast={mapData.creep_forecast}
+          reservedTiles={mapData.reserved_tiles}
+        />
       </>
     );
   };
@@ -23,8 +23,21 @@ export interface MapData {
   resources: ResourceInfo[];
 }
 
+export type TileReservation =
+  | "PlannedBuilding"
+  | "MineralLine"
+  | "GeyserPath"
+  | "Ramp"
+  | "Expansion";
+
 export interface MapSnapshot {
   map_data: MapData;
+  // Build tiles, 4 walk tiles across
+  creep_tiles: Array<[number, number]>;
+  // Build tiles expected to get creep, with the frame
+  creep_forecast: Array<[number, number, number]>;
+  // Build tiles kept free of buildings, with why
+  reserved_tiles: Array<[number, number, TileReservation]>;
   frame_count: number;
 }
 
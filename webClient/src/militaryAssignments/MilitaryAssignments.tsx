@@ -3,7 +3,31 @@ import { useMilitaryAssignments } from "./militaryAssignmentsHooks";
 import { ExpandableSection } from "../components/ExpandableSection";
 import { LoadingState } from "../components/LoadingState";
 import { EmptyState } from "../components/EmptyState";
+import { DataCard } from "../components/DataCard";
 import { SquadCard } from "./SquadCard";
+import { OverlordInfo } from "./militaryAssignmentsService";
+
+const OverlordList: React.FC<{ overlords: OverlordInfo[] }> = ({ overlords }) => (
+  <DataCard>
+    <div className="text-sm font-medium mb-1">Overlords</div>
+    {overlords.map((overlord) => (
+      <div key={overlord.unit_id} className="flex justify-between text-xs">
+        <span>Overlord {overlord.unit_id}</span>
+        {overlord.retreating ? (
+          <span className="text-red-500">retreating from anti-air</span>
+        ) : (
+          <span className="text-slate-400">
+            {overlord.spot ?? "unassigned"}
+            {overlord.base_index !== null ? ` (base ${overlord.base_index})` : ""}
+            {overlord.spot_position !== null
+              ? ` at (${overlord.spot_position[0]}, ${overlord.spot_position[1]})`
+              : ""}
+          </span>
+        )}
+      </div>
+    ))}
+  </DataCard>
+);
 
 export const MilitaryAssignments: React.FC = () => {
   const { data: assignments, isLoading, error } = useMilitaryAssignments();
@@ -17,12 +41,18 @@ export const MilitaryAssignments: React.FC = () => {
       return <EmptyState message={`Error: ${error.message}`} />;
     }
 
-    if (!assignments || assignments.squads.length === 0) {
+    if (
+      !assignments ||
+      (assignments.squads.length === 0 && assignments.overlords.length === 0)
+    ) {
       return <EmptyState message="No military squads" />;
     }
 
     return (
       <div className="">
+        {assignments.overlords.length > 0 && (
+          <OverlordList overlords={assignments.overlords} />
+        )}
         {assignments.squads.map((squad, index) => (
           <SquadCard key={`${squad.name}-${index}`} squad={squad} />
         ))}
@@ -18,8 +18,24 @@ export interface SquadData {
   target_path_index: number | null;
 }
 
+export type OverlordSpotKind =
+  | "EnemyNatural"
+  | "OurChoke"
+  | "BaseDetection"
+  | "MapEdge";
+
+export interface OverlordInfo {
+  unit_id: number;
+  spot: OverlordSpotKind | null;
+  spot_position: [number, number] | null;
+  base_index: number | null;
+  retreating: boolean;
+  current_position: [number, number];
+}
+
 export interface MilitaryAssignmentsSnapshot {
   squads: SquadData[];
+  overlords: OverlordInfo[];
   frame_count: number;
 }
 
@@ -0,0 +1,79 @@
+import React from "react";
+import { useUpgradeQueue } from "./upgradeQueueHooks";
+import { ExpandableSection } from "../components/ExpandableSection";
+import { LoadingState } from "../components/LoadingState";
+import { EmptyState } from "../components/EmptyState";
+import { DataCard } from "../components/DataCard";
+import { StatCard } from "../components/StatCard";
+
+const formatName = (name: string) =>
+  name.replace("Zerg_", "").replace(/_/g, " ");
+
+export const UpgradeQueue: React.FC = () => {
+  const { data: queue, isLoading, error } = useUpgradeQueue();
+
+  const renderContent = () => {
+    if (isLoading) {
+      return <LoadingState message="Waiting for upgrade queue..." />;
+    }
+
+    if (error) {
+      return <EmptyState message={`Error: ${error.message}`} />;
+    }
+
+    if (!queue || queue.frame_count < 0) {
+      return <EmptyState message="No upgrade queue" />;
+    }
+
+    return (
+      <div className="space-y-4">
+        <div className="grid grid-cols-2 md:grid-cols-3 gap-4">
+          <StatCard label="Spare minerals" value={queue.spare_minerals} />
+          <StatCard label="Spare gas" value={queue.spare_gas} />
+          <StatCard
+            label="Army upgrades"
+            value={queue.from_army ? "On" : "Off"}
+          />
+        </div>
+
+        {queue.entries.length === 0 && (
+          <EmptyState message="Nothing queued" />
+        )}
+        {queue.entries.map((entry) => (
+          <DataCard key={`${entry.name}-${entry.queued_frame}`}>
+            <div className="flex justify-between text-sm">
+              <span
+                className={
+                  entry.started_frame !== null
+                    ? "text-green-500 font-medium"
+                    : "text-plasma-400 font-medium"
+                }
+              >
+                {formatName(entry.name)}
+              </span>
+              <span className="text-amber-400">
+                {entry.minerals}m {entry.gas}g
+              </span>
+            </div>
+            <div className="flex justify-between text-xs text-slate-400">
+              <span>
+                {entry.source}, priority {entry.priority}
+              </span>
+              <span>
+                {entry.started_frame !== null
+                  ? `started ${entry.started_frame} on ${entry.building_id}`
+                  : `waiting for ${entry.waiting_for || "next frame"}`}
+              </span>
+            </div>
+          </DataCard>
+        ))}
+      </div>
+    );
+  };
+
+  return (
+    <ExpandableSection title="Upgrade Queue" defaultExpanded={false}>
+      {renderContent()}
+    </ExpandableSection>
+  );
+};
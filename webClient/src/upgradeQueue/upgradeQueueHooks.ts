@@ -0,0 +1,18 @@
+import { useQuery } from '@tanstack/react-query';
+import * as api from './upgradeQueueService';
+import { usePollInterval } from '../contexts/PollIntervalContext';
+
+// Query keys
+export const queryKeys = {
+  upgradeQueue: ['upgradeQueue'] as const,
+};
+
+export function useUpgradeQueue() {
+  const { pollInterval } = usePollInterval();
+
+  return useQuery({
+    queryKey: queryKeys.upgradeQueue,
+    queryFn: api.fetchUpgradeQueue,
+    refetchInterval: pollInterval,
+  });
+}
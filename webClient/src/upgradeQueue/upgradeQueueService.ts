@@ -0,0 +1,29 @@
+const BASE_URL = `http://localhost:3333`;
+
+export interface QueuedResearch {
+  name: string;
+  priority: number;
+  source: string;
+  minerals: number;
+  gas: number;
+  queued_frame: number;
+  started_frame: number | null;
+  building_id: number | null;
+  waiting_for: string;
+}
+
+export interface UpgradeQueueSnapshot {
+  entries: QueuedResearch[];
+  from_army: boolean;
+  spare_minerals: number;
+  spare_gas: number;
+  frame_count: number;
+}
+
+export async function fetchUpgradeQueue(): Promise<UpgradeQueueSnapshot> {
+  const response = await fetch(`${BASE_URL}/upgrade-queue`);
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status}`);
+  }
+  return response.json();
+}
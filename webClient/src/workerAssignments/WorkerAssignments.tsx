@@ -4,6 +4,9 @@ import { ExpandableSection } from "../components/ExpandableSection";
 import { LoadingState } from "../components/LoadingState";
 import { EmptyState } from "../components/EmptyState";
 import type {
+  BaseSaturation,
+  BaseThreat,
+  Builder,
   WorkerAssignment,
   WorkerAssignmentType,
 } from "./workerAssignmentsService";
@@ -79,6 +82,162 @@ const WorkerCard: React.FC<{ worker: WorkerCardData }> = ({ worker }) => {
   );
 };
 
+const saturationColor = (workers: number, target: number) =>
+  workers > target
+    ? "text-amber-400"
+    : workers === target
+      ? "text-emerald-500"
+      : "text-plasma-400";
+
+const BaseSaturationTable: React.FC<{ bases: BaseSaturation[] }> = ({
+  bases,
+}) => {
+  if (bases.length === 0) return null;
+
+  return (
+    <div className="mb-6">
+      <div className="flex justify-between items-center mb-3 pb-2 border-b-2 border-border-primary">
+        <h3 className="text-text-secondary text-lg font-semibold">
+          Saturation
+        </h3>
+      </div>
+      <div className="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-4">
+        {bases.map((base) => (
+          <div
+            key={base.base_index}
+            className="bg-void-950 border border-plasma-800 rounded p-4 text-sm"
+          >
+            <div className="font-bold mb-2">Base {base.base_index}</div>
+            <div className="flex justify-between">
+              <span className="text-slate-400">
+                Minerals ({base.patches} patches)
+              </span>
+              <span
+                className={saturationColor(
+                  base.mineral_workers,
+                  base.mineral_target
+                )}
+              >
+                {base.mineral_workers} / {base.mineral_target}
+              </span>
+            </div>
+            <div className="flex justify-between">
+              <span className="text-slate-400">
+                Gas ({base.extractors} extractors)
+              </span>
+              <span className={saturationColor(base.gas_workers, base.gas_target)}>
+                {base.gas_workers} / {base.gas_target}
+              </span>
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+};
+
+const ThreatList: React.FC<{ threats: BaseThreat[] }> = ({ threats }) => {
+  if (threats.length === 0) return null;
+
+  return (
+    <div className="mb-6">
+      <div className="flex justify-between items-center mb-3 pb-2 border-b-2 border-border-primary">
+        <h3 className="text-text-secondary text-lg font-semibold">
+          Mineral line threats
+        </h3>
+      </div>
+      <div className="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-4">
+        {threats.map((threat) => (
+          <div
+            key={threat.base_index}
+            className="bg-void-950 border border-red-800 rounded p-4 text-sm"
+          >
+            <div className="flex justify-between font-bold mb-2">
+              <span>Base {threat.base_index}</span>
+              <span
+                className={
+                  threat.outnumbered ? "text-red-500" : "text-amber-400"
+                }
+              >
+                {threat.outnumbered ? "Mineral walking" : "Fighting"}
+              </span>
+            </div>
+            <div className="flex justify-between">
+              <span className="text-slate-400">Enemies</span>
+              <span>{threat.enemies}</span>
+            </div>
+            <div className="flex justify-between">
+              <span className="text-slate-400">Drones fighting</span>
+              <span>
+                {threat.fighting.length} / {threat.drones_needed}
+              </span>
+            </div>
+            <div className="flex justify-between">
+              <span className="text-slate-400">Drones escaping</span>
+              <span>{threat.escaping.length}</span>
+            </div>
+            <div className="text-xs text-slate-500">
+              since frame {threat.first_seen_frame}
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+};
+
+const BuilderList: React.FC<{
+  builders: Builder[];
+  buildOrder: string[];
+}> = ({ builders, buildOrder }) => {
+  if (builders.length === 0) return null;
+
+  return (
+    <div className="mb-6">
+      <div className="flex justify-between items-center mb-3 pb-2 border-b-2 border-border-primary">
+        <h3 className="text-text-secondary text-lg font-semibold">Builders</h3>
+      </div>
+      <div className="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 gap-4">
+        {builders.map((builder) => (
+          <div
+            key={builder.build_order_index}
+            className="bg-void-950 border border-plasma-800 rounded p-4 text-sm"
+          >
+            <div className="flex justify-between font-bold mb-2">
+              <span>
+                #{builder.build_order_index}{" "}
+                {buildOrder[builder.build_order_index] ?? ""}
+              </span>
+              <span className="text-plasma-400">
+                {builder.state ?? "No builder"}
+              </span>
+            </div>
+            {builder.drone_id !== null && (
+              <div className="text-xs text-slate-400">
+                Drone {builder.drone_id} since frame {builder.assigned_frame}
+              </div>
+            )}
+            {builder.failures.map((failure) => (
+              <div
+                key={`${failure.drone_id}-${failure.frame}`}
+                className="flex justify-between text-xs text-red-400"
+              >
+                <span>
+                  {failure.failure}, drone {failure.drone_id}
+                  {failure.site
+                    ? ` at (${failure.site[0]}, ${failure.site[1]})`
+                    : ""}
+                </span>
+                <span>frame {failure.frame}</span>
+              </div>
+            ))}
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+};
+
 const AssignmentGroup: React.FC<{
   type: WorkerAssignmentType;
   workers: WorkerCardData[];
@@ -149,6 +308,12 @@ export const WorkerAssignments: React.FC = () => {
 
     return (
       <>
+        <ThreatList threats={assignments.threats} />
+        <BaseSaturationTable bases={assignments.base_saturation} />
+        <BuilderList
+          builders={assignments.builders}
+          buildOrder={assignments.build_order}
+        />
         <AssignmentGroup type="Building" workers={grouped.Building} />
         <AssignmentGroup type="Gathering" workers={grouped.Gathering} />
         <AssignmentGroup type="Scouting" workers={grouped.Scouting} />
@@ -9,8 +9,59 @@ export interface WorkerAssignment {
   build_order_index: number | null;
 }
 
+export interface BaseSaturation {
+  base_index: number;
+  patches: number;
+  extractors: number;
+  mineral_workers: number;
+  mineral_target: number;
+  gas_workers: number;
+  gas_target: number;
+}
+
+export interface BaseThreat {
+  base_index: number;
+  enemies: number;
+  drones_needed: number;
+  outnumbered: boolean;
+  first_seen_frame: number;
+  fighting: number[];
+  escaping: number[];
+}
+
+export type BuilderState =
+  | "Travelling"
+  | "WaitingForMoney"
+  | "AtSite"
+  | "Placing";
+
+export type BuilderFailure =
+  | "Killed"
+  | "Threatened"
+  | "Stuck"
+  | "TimedOut"
+  | "SiteBlocked";
+
+export interface BuilderFailureRecord {
+  frame: number;
+  drone_id: number;
+  site: [number, number] | null;
+  failure: BuilderFailure;
+}
+
+export interface Builder {
+  build_order_index: number;
+  drone_id: number | null;
+  state: BuilderState | null;
+  assigned_frame: number | null;
+  failures: BuilderFailureRecord[];
+}
+
 export interface WorkerStatusSnapshot {
   worker_assignments: Record<string, WorkerAssignment>;
+  base_saturation: BaseSaturation[];
+  threats: BaseThreat[];
+  builders: Builder[];
   build_order: string[];
   frame_count: number;
 }